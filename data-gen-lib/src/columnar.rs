@@ -0,0 +1,317 @@
+use crate::data_type::DataType;
+use crate::schema::Schema;
+use arrow::datatypes::{DataType as ArrowType, Field, Fields, Schema as ArrowSchema, TimeUnit};
+use chrono::format::{parse, Item, Parsed, StrftimeItems};
+use chrono::{Local, NaiveDate, Timelike};
+use std::sync::Arc;
+
+/// Derives the Arrow schema of a [Schema], with one
+/// top level field per schema field, sorted by name.
+///
+/// # Examples
+///
+/// ```
+/// use arrow::datatypes::DataType as ArrowType;
+/// use data_gen_lib::columnar::arrow_schema;
+/// use data_gen_lib::data_type::DataType;
+/// use data_gen_lib::schema::Schema;
+///
+/// let mut schema = Schema::default();
+/// schema.with_field("id", DataType::Serial);
+///
+/// let arrow_schema = arrow_schema(&schema);
+/// assert_eq!(&ArrowType::Int32, arrow_schema.field(0).data_type());
+/// ```
+pub fn arrow_schema(schema: &Schema) -> ArrowSchema {
+    let fields: Vec<_> = schema
+        .iter()
+        .map(|(name, dt)| arrow_field(name, dt))
+        .collect();
+    ArrowSchema::new(fields)
+}
+
+/// Derives the Arrow field of a [DataType]. Nullable and optional
+/// types are nullable fields, objects are structs, and timestamps
+/// whose format can be parsed back are temporal types. Unions of
+/// differently typed variants, and objects or arrays of pool
+/// members, are written as text.
+pub fn arrow_field(name: &str, dt: &DataType) -> Field {
+    derive_field(name, dt, false)
+}
+
+fn derive_field(name: &str, dt: &DataType, nullable: bool) -> Field {
+    let tpe = match dt {
+        DataType::Nullable { element, .. } | DataType::Optional { element, .. } => {
+            return derive_field(name, element, true)
+        }
+        DataType::Cardinality { element, .. } | DataType::Unique { element } => {
+            return derive_field(name, element, nullable)
+        }
+        DataType::Member {
+            element: Some(element),
+            ..
+        } => {
+            let field = derive_field(name, element, true);
+            return match field.data_type() {
+                ArrowType::List(_) | ArrowType::Map(..) | ArrowType::Struct(_) => {
+                    Field::new(name, ArrowType::Utf8, true)
+                }
+                _ => field,
+            };
+        }
+        DataType::SmallInt => ArrowType::Int16,
+        DataType::Integer | DataType::Serial => ArrowType::Int32,
+        DataType::BigInt => ArrowType::Int64,
+        DataType::Range { from, to, .. } => {
+            if i32::try_from(*from).is_ok() && i32::try_from(to - 1).is_ok() {
+                ArrowType::Int32
+            } else {
+                ArrowType::Int64
+            }
+        }
+        DataType::Boolean => ArrowType::Boolean,
+        DataType::Float { .. } => ArrowType::Float64,
+        DataType::Decimal {
+            precision, scale, ..
+        } => ArrowType::Decimal128(*precision as u8, *scale as i8),
+        DataType::Timestamp { format } => {
+            match temporal_kind(format.as_deref().unwrap_or("%F %r")) {
+                Some(Temporal::Timestamp) => {
+                    ArrowType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into()))
+                }
+                Some(Temporal::Date) => ArrowType::Date32,
+                Some(Temporal::Time) => ArrowType::Time64(TimeUnit::Microsecond),
+                None => ArrowType::Utf8,
+            }
+        }
+        DataType::Array { element, .. } => {
+            ArrowType::List(Arc::new(derive_field("item", element, true)))
+        }
+        DataType::Map { values, .. } => {
+            let entries = Field::new(
+                "entries",
+                ArrowType::Struct(Fields::from(vec![
+                    Field::new("key", ArrowType::Utf8, false),
+                    derive_field("value", values, true),
+                ])),
+                false,
+            );
+            ArrowType::Map(Arc::new(entries), false)
+        }
+        DataType::Object { fields } => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            ArrowType::Struct(
+                fields
+                    .into_iter()
+                    .map(|(name, dt)| derive_field(name, dt, false))
+                    .collect(),
+            )
+        }
+        DataType::Union { variants } => {
+            let mut derived = variants
+                .iter()
+                .map(|variant| derive_field(name, variant, true));
+            match derived.next() {
+                Some(first) if derived.all(|field| field.data_type() == first.data_type()) => {
+                    first.data_type().clone()
+                }
+                _ => ArrowType::Utf8,
+            }
+        }
+        DataType::Email
+        | DataType::Generator { .. }
+        | DataType::Literal { .. }
+        | DataType::Member { element: None, .. }
+        | DataType::OneOf { .. }
+        | DataType::PhoneNumber
+        | DataType::Regex { .. }
+        | DataType::Uuid => ArrowType::Utf8,
+    };
+
+    let nullable = nullable || matches!(dt, DataType::Union { .. });
+    Field::new(name, tpe, nullable)
+}
+
+/// The kind of value a formatted timestamp is parsed back into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Temporal {
+    Timestamp,
+    Date,
+    Time,
+}
+
+/// Determines whether timestamps of the given format can be parsed
+/// back into a date and time, a date, or a time of day.
+fn temporal_kind(format: &str) -> Option<Temporal> {
+    let items: Vec<_> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+
+    let sample = Local::now().format_with_items(items.iter()).to_string();
+    let mut parsed = Parsed::new();
+    parse(&mut parsed, &sample, items.iter()).ok()?;
+
+    if parsed.to_naive_datetime_with_offset(0).is_ok() {
+        Some(Temporal::Timestamp)
+    } else if parsed.to_naive_date().is_ok() {
+        Some(Temporal::Date)
+    } else if parsed.to_naive_time().is_ok() {
+        Some(Temporal::Time)
+    } else {
+        None
+    }
+}
+
+/// Parses a formatted timestamp into microseconds since the epoch,
+/// days since the epoch, or microseconds since midnight.
+pub fn temporal(s: &str, format: &str, kind: Temporal) -> Option<i64> {
+    let mut parsed = Parsed::new();
+    parse(&mut parsed, s, StrftimeItems::new(format)).ok()?;
+
+    match kind {
+        Temporal::Timestamp => {
+            let timestamp = match parsed.to_datetime() {
+                Ok(timestamp) => timestamp.naive_utc(),
+                Err(_) => parsed.to_naive_datetime_with_offset(0).ok()?,
+            };
+            Some(timestamp.and_utc().timestamp_micros())
+        }
+        Temporal::Date => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+            Some((parsed.to_naive_date().ok()? - epoch).num_days())
+        }
+        Temporal::Time => {
+            let time = parsed.to_naive_time().ok()?;
+            Some(
+                time.num_seconds_from_midnight() as i64 * 1_000_000
+                    + time.nanosecond() as i64 / 1_000,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arrow_field, temporal, temporal_kind, Temporal};
+    use crate::data_type::DataType;
+    use crate::generator::DataGenerator;
+    use crate::schema::Schema;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{DataType as ArrowType, Decimal128Type, Int32Type, TimeUnit};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_temporal() {
+        assert_eq!(Some(Temporal::Timestamp), temporal_kind("%F %r"));
+        assert_eq!(Some(Temporal::Date), temporal_kind("%Y-%m-%d"));
+        assert_eq!(Some(Temporal::Time), temporal_kind("%H:%M:%S"));
+        assert!(temporal_kind("%H:%M").is_some());
+        assert!(temporal_kind("%A").is_none());
+
+        assert_eq!(
+            Some(1_700_000_000_000_000),
+            temporal("2023-11-14 10:13:20 PM", "%F %r", Temporal::Timestamp)
+        );
+        assert_eq!(Some(19675), temporal("2023-11-14", "%F", Temporal::Date));
+        assert_eq!(
+            Some(3_723_000_000),
+            temporal("01:02:03", "%T", Temporal::Time)
+        );
+    }
+
+    #[test]
+    fn test_arrow_field() {
+        let dt: DataType = serde_json::from_str(
+            r#"{
+                "type": "object",
+                "fields": {
+                    "at": { "type": "timestamp" },
+                    "tags": { "type": "array", "element": { "type": "range", "from": 0, "to": 10 }, "size": 3 },
+                    "score": { "type": "nullable", "element": { "type": "decimal", "precision": 6, "scale": 2 }, "null_rate": 0.5 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let field = arrow_field("record", &dt);
+        let fields = match field.data_type() {
+            ArrowType::Struct(fields) => fields.clone(),
+            other => panic!("expected a struct, found {}", other),
+        };
+
+        assert_eq!(
+            &ArrowType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+            fields[0].data_type()
+        );
+        assert!(matches!(fields[1].data_type(), ArrowType::Decimal128(6, 2)));
+        assert!(fields[1].is_nullable());
+        assert!(
+            matches!(fields[2].data_type(), ArrowType::List(item) if item.data_type() == &ArrowType::Int32)
+        );
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "id": { "type": "serial" },
+                "drink": { "type": "nullable", "element": { "type": "one_of", "options": ["coffee", "tea"] }, "null_rate": 0.5 },
+                "tags": { "type": "array", "element": { "type": "uuid" }, "size": 3, "min_size": 0 },
+                "attrs": { "type": "map", "keys": { "type": "range", "from": 0, "to": 100 }, "values": { "type": "boolean" }, "size": 2 },
+                "code": { "type": "union", "variants": [{ "type": "range", "from": 0, "to": 10 }, { "type": "email" }] },
+                "customer": {
+                    "type": "optional",
+                    "missing_rate": 0.5,
+                    "element": { "type": "object", "fields": {
+                        "since": { "type": "timestamp", "format": "%F" },
+                        "visits": { "type": "range", "from": 0, "to": 10 }
+                    } }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let gen = DataGenerator::new(&schema);
+        let batch = gen.record_batch(&mut thread_rng(), 100).unwrap();
+
+        assert_eq!(100, batch.num_rows());
+        assert_eq!(gen.arrow_schema(), batch.schema());
+
+        let ids = batch.column(4).as_primitive::<Int32Type>();
+        assert_eq!(1, ids.value(0));
+        assert_eq!(100, ids.value(99));
+
+        let drinks = batch.column(3).as_string::<i32>();
+        assert!(drinks.null_count() > 0 && drinks.null_count() < 100);
+        assert!(drinks
+            .iter()
+            .flatten()
+            .all(|drink| drink == "coffee" || drink == "tea"));
+
+        let codes = batch.column(1).as_string::<i32>();
+        assert_eq!(0, codes.null_count());
+
+        let batch = gen.record_batch(&mut thread_rng(), 10).unwrap();
+        let ids = batch.column(4).as_primitive::<Int32Type>();
+        assert_eq!(101, ids.value(0));
+    }
+
+    #[test]
+    fn test_decimal_column() {
+        let schema: Schema = serde_json::from_str(
+            r#"{ "amount": { "type": "decimal", "precision": 30, "scale": 10 } }"#,
+        )
+        .unwrap();
+
+        let gen = DataGenerator::new(&schema);
+        let batch = gen.record_batch(&mut thread_rng(), 100).unwrap();
+
+        let amounts = batch.column(0).as_primitive::<Decimal128Type>();
+        assert_eq!((30, 10), (amounts.precision(), amounts.scale()));
+        assert!(amounts.validate_decimal_precision(30).is_ok());
+        // values span all 30 digits, rather than the range of a double
+        assert!(amounts.iter().flatten().any(|n| n.abs() > 10i128.pow(25)));
+    }
+}
@@ -1,6 +1,12 @@
 use crate::interpolator::Interpolator;
 use crate::regex_pattern::RegexPattern;
-use serde::{Deserialize, Serialize};
+use crate::selection::Selection;
+use schemars::JsonSchema;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::json;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Display, Error, Formatter};
 
@@ -8,14 +14,23 @@ use std::fmt::{Display, Error, Formatter};
 /// fields within a [Schema]. Data types
 /// are intended to create realistic looking
 /// values for schema fields.
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
-#[serde(tag = "type", rename_all = "snake_case")]
+///
+/// Any type may also set `"unique": true` or `"cardinality": N`,
+/// which are read as the [DataType::Unique] and
+/// [DataType::Cardinality] types wrapping it.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+#[serde(remote = "Self", tag = "type", rename_all = "snake_case")]
 pub enum DataType<'a> {
     /// An homogenous fixed sized collection of a
     /// specified [DataType].
     Array {
+        /// The subtype of each element.
         element: Box<DataType<'a>>,
+        /// The maximum size of the array.
         size: u32,
+        /// The minimum size of the array, defaults to 1.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_size: Option<u32>,
     },
 
     /// A 2 byte, small-range integer from -32768 to +32767.
@@ -24,29 +39,133 @@ pub enum DataType<'a> {
     /// A 4 byte integer from -2147483648 to +2147483647.
     Integer,
 
+    /// An 8 byte integer from -9223372036854775808 to +9223372036854775807.
+    BigInt,
+
     /// A simple boolean.
     Boolean,
 
+    /// A value drawn from a pool of a fixed number of distinct
+    /// values of the element type, sampled once, so that exactly
+    /// that many distinct values are generated in a run. It is
+    /// written as the `cardinality` of the element type.
+    #[serde(skip_deserializing)]
+    #[schemars(skip)]
+    Cardinality {
+        /// The type of the values of the pool.
+        element: Box<DataType<'a>>,
+        /// The number of distinct values.
+        count: u32,
+    },
+
+    /// An exact decimal number with a fixed number of
+    /// digits after the decimal point.
+    Decimal {
+        /// The total number of significant digits.
+        precision: u32,
+        /// The number of digits after the decimal point.
+        scale: u32,
+        /// (optional) The inclusive lower bound of the values.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        /// (optional) The inclusive upper bound of the values.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+
+    /// A realistic looking email address.
+    Email,
+
+    /// A random floating point number from a given range.
+    Float {
+        /// The inclusive lower bound of the range.
+        from: f64,
+        /// The exclusive upper bound of the range.
+        to: f64,
+    },
+
+    /// A string created from a format specifier which
+    /// interpolates values from predefined datasets
+    /// within `#{}` tags.
     Generator {
+        /// The format specifier with 0 or more interpolator patterns.
         format: Interpolator,
     },
 
     /// A literal type which always returns
     /// a static value.
     Literal {
-        value: &'a str,
+        /// The static value for the field.
+        #[serde(borrow)]
+        value: Cow<'a, str>,
+    },
+
+    /// A collection of key-value pairs with string
+    /// keys and homogenous values.
+    Map {
+        /// The type of each key, values are converted to strings.
+        keys: Box<DataType<'a>>,
+        /// The type of each value.
+        values: Box<DataType<'a>>,
+        /// The maximum number of entries.
+        size: u32,
+    },
+
+    /// A field of a member of a pool of the schema, or the whole
+    /// member. Every field of a value referencing the same pool
+    /// references the same member, so their fields stay consistent.
+    Member {
+        /// The name of the pool.
+        pool: Cow<'a, str>,
+        /// (optional) The field of the member, or the whole member as an object.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        field: Option<Cow<'a, str>>,
+        /// The data type of the referenced values,
+        /// which is set when added to a schema.
+        #[serde(skip)]
+        #[schemars(skip)]
+        element: Option<Box<DataType<'a>>>,
+    },
+
+    /// A value that is null some fraction of the time
+    /// and is otherwise generated from the element type.
+    Nullable {
+        /// The type of non-null values.
+        element: Box<DataType<'a>>,
+        /// The probability, between 0 and 1, of a null value.
+        null_rate: f64,
     },
 
     /// A nested structured type comprised of
     /// heterogeneous named fields.
     Object {
-        fields: HashMap<&'a str, DataType<'a>>,
+        /// A map of field names to field types.
+        fields: HashMap<Cow<'a, str>, DataType<'a>>,
     },
 
     /// An enumeration type that returns
     /// one of a fixed set of values.
     OneOf {
-        options: Vec<&'a str>,
+        /// The list of options.
+        options: Vec<Cow<'a, str>>,
+        /// (optional) The relative weight of each option, in the
+        /// same order as the options. Defaults to equal weights.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weights: Option<Vec<f64>>,
+        /// (optional) How options are selected, from the first option as
+        /// the hottest, instead of by weight. Defaults to equal weights.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        selection: Option<Selection>,
+    },
+
+    /// A field that is omitted from its enclosing object
+    /// some fraction of the time. When not nested directly
+    /// within an object the field is null instead.
+    Optional {
+        /// The type of the field when present.
+        element: Box<DataType<'a>>,
+        /// The probability, between 0 and 1, of omitting the field.
+        missing_rate: f64,
     },
 
     /// A realistic looking phone number of the format
@@ -55,8 +174,14 @@ pub enum DataType<'a> {
 
     /// A random number from a given range.
     Range {
+        /// The inclusive lower bound of the range.
         from: i64,
+        /// The exclusive upper bound of the range.
         to: i64,
+        /// (optional) How numbers are selected, from the lower bound
+        /// as the hottest number. Defaults to a uniform selection.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        selection: Option<Selection>,
     },
 
     /// A random value that matches the specified
@@ -77,6 +202,7 @@ pub enum DataType<'a> {
     /// };
     /// ```
     Regex {
+        /// The regular expression all values must match.
         pattern: RegexPattern,
     },
 
@@ -88,7 +214,78 @@ pub enum DataType<'a> {
     /// current time. For many applications
     /// it makes sense for timestamps to be
     /// monotonically increasing.
-    Timestamp,
+    Timestamp {
+        /// A [strftime](https://docs.rs/chrono/latest/chrono/format/strftime)
+        /// format string, defaults to `%F %r`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        format: Option<String>,
+    },
+
+    /// A value generated from one of several
+    /// types, chosen at random.
+    Union {
+        /// The types to choose from.
+        variants: Vec<DataType<'a>>,
+    },
+
+    /// A value of the element type which is never repeated within
+    /// a run, such as a primary key. Generating fails once no new
+    /// value is found after many attempts. It is written as the
+    /// element type with `unique` set.
+    #[serde(skip_deserializing)]
+    #[schemars(skip)]
+    Unique {
+        /// The type of the values.
+        element: Box<DataType<'a>>,
+    },
+
+    /// A random version 4 UUID.
+    Uuid,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for DataType<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let (unique, cardinality) = match value.as_object_mut() {
+            Some(fields) => (fields.remove("unique"), fields.remove("cardinality")),
+            None => (None, None),
+        };
+
+        let mut dt = DataType::deserialize(value).map_err(D::Error::custom)?;
+        if let Some(cardinality) = cardinality {
+            let count = u32::deserialize(cardinality).map_err(D::Error::custom)?;
+            dt = DataType::Cardinality {
+                element: Box::new(dt),
+                count,
+            };
+        }
+        if let Some(unique) = unique {
+            if bool::deserialize(unique).map_err(D::Error::custom)? {
+                dt = DataType::Unique {
+                    element: Box::new(dt),
+                };
+            }
+        }
+        Ok(dt)
+    }
+}
+
+impl Serialize for DataType<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (element, key, value) = match self {
+            DataType::Cardinality { element, count } => (element, "cardinality", json!(count)),
+            DataType::Unique { element } => (element, "unique", json!(true)),
+            _ => return DataType::serialize(self, serializer),
+        };
+
+        match serde_json::to_value(element).map_err(S::Error::custom)? {
+            serde_json::Value::Object(mut fields) => {
+                fields.insert(key.to_owned(), value);
+                fields.serialize(serializer)
+            }
+            _ => Err(S::Error::custom("a data type must be an object")),
+        }
+    }
 }
 
 impl Display for DataType<'_> {
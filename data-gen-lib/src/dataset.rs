@@ -0,0 +1,506 @@
+//! Datasets of several tables, whose rows reference the
+//! rows of other tables through foreign keys.
+
+use crate::data_type::DataType;
+use crate::generator::DataGenerator;
+use crate::physical::distributions::{Choose, DynDistribution, Iter};
+use crate::physical::physical_types::PhysicalDataType;
+use crate::schema::Schema;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use rand_distr::Poisson;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::iter::repeat_n;
+use std::ops::Range;
+use std::rc::Rc;
+use thiserror::Error;
+
+/// A [Dataset] defines tables by name, each with
+/// its own [Schema] and foreign keys to other tables.
+#[derive(Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct Dataset<'a> {
+    #[serde(flatten, borrow)]
+    tables: BTreeMap<Cow<'a, str>, Table<'a>>,
+}
+
+/// A table of a [Dataset], whose number of rows is either set by
+/// its count, or by the children of the rows of another table.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Table<'a> {
+    /// The number of rows of the table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+
+    /// The fields of the table, other than its foreign keys.
+    #[serde(borrow)]
+    pub schema: Schema<'a>,
+
+    /// The fields referencing the rows of other tables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// A field whose values are the values of a field of another
+/// table, so that each row references an existing row.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ForeignKey {
+    /// The name of the field.
+    pub field: String,
+
+    /// The referenced field, given as `<table>.<field>`.
+    pub references: String,
+
+    /// (optional) The number of rows of the table per referenced row,
+    /// which then sets the number of rows of the table. By default
+    /// each row references a row chosen at random.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Cardinality>,
+}
+
+/// The number of child rows of each parent row.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Cardinality {
+    /// The same number of children for every parent.
+    Fixed {
+        /// The number of children.
+        count: u32,
+    },
+
+    /// A random number of children from a given range.
+    Range {
+        /// The inclusive lower bound of the range.
+        from: u32,
+        /// The exclusive upper bound of the range.
+        to: u32,
+    },
+
+    /// A random number of children, where the number
+    /// `n` is chosen with the `n`th weight.
+    Weighted {
+        /// The relative weight of 0, 1, 2 and more children.
+        weights: Vec<f64>,
+    },
+
+    /// A Poisson distributed number of children.
+    Poisson {
+        /// The mean number of children.
+        mean: f64,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid reference `{references}` of table `{table}`, expected <table>.<field>")]
+    InvalidReference { table: String, references: String },
+
+    #[error("table `{table}` references `{references}`, which does not exist")]
+    UnknownReference { table: String, references: String },
+
+    #[error("the foreign key `{field}` of table `{table}` is also a field of its schema")]
+    DuplicateField { table: String, field: String },
+
+    #[error("table `{table}` needs a count, or a foreign key with children")]
+    MissingCount { table: String },
+
+    #[error("table `{table}` can only have a count, or a single foreign key with children")]
+    AmbiguousCount { table: String },
+
+    #[error("invalid children of table `{table}`: {reason}")]
+    InvalidChildren { table: String, reason: String },
+
+    #[error("the tables {tables} reference each other in a cycle")]
+    Cycle { tables: String },
+
+    #[error("table `{table}` references `{references}`, which has no values")]
+    NoKeys { table: String, references: String },
+}
+
+/// A sampler of the number of children of a parent row.
+enum Children {
+    Fixed(usize),
+    Range(Range<u32>),
+    Weighted(WeightedIndex<f64>),
+    Poisson(Poisson<f64>),
+}
+
+impl Children {
+    fn new(cardinality: &Cardinality) -> Result<Children, String> {
+        let children = match cardinality {
+            Cardinality::Fixed { count } => Children::Fixed(*count as usize),
+            Cardinality::Range { from, to } if from < to => Children::Range(*from..*to),
+            Cardinality::Range { .. } => return Err("`from` must be less than `to`".to_owned()),
+            Cardinality::Weighted { weights } => {
+                Children::Weighted(WeightedIndex::new(weights).map_err(|e| e.to_string())?)
+            }
+            Cardinality::Poisson { mean } => {
+                Children::Poisson(Poisson::new(*mean).map_err(|e| e.to_string())?)
+            }
+        };
+        Ok(children)
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match self {
+            Children::Fixed(count) => *count,
+            Children::Range(r) => rng.gen_range(r.clone()) as usize,
+            Children::Weighted(weights) => weights.sample(rng),
+            Children::Poisson(poisson) => poisson.sample(rng) as usize,
+        }
+    }
+}
+
+/// A foreign key whose reference is resolved.
+struct Reference {
+    field: String,
+    /// The referenced table and field.
+    table: String,
+    key: String,
+    children: Option<Children>,
+}
+
+/// A [DatasetGenerator] generates the tables of a [Dataset] one at
+/// a time, where tables are generated after the tables they reference.
+/// The values of referenced fields are kept, so that foreign keys
+/// only reference values which were generated.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::dataset::{Dataset, DatasetGenerator};
+/// use rand::{thread_rng, Rng};
+/// use serde_json::Value;
+///
+/// let dataset: Dataset = serde_json::from_str(r#"{
+///     "customers": { "count": 10, "schema": { "id": { "type": "serial" } } },
+///     "orders": {
+///         "schema": { "total": { "type": "float", "from": 1, "to": 100 } },
+///         "foreign_keys": [{
+///             "field": "customer_id",
+///             "references": "customers.id",
+///             "children": { "type": "fixed", "count": 3 }
+///         }]
+///     }
+/// }"#).unwrap();
+///
+/// let generator = DatasetGenerator::new(&dataset).unwrap();
+/// for table in generator.tables() {
+///     let (gen, rows) = generator.table(table, &mut thread_rng()).unwrap();
+///     let values: Vec<Value> = thread_rng().sample_iter(&gen).take(rows as usize).collect();
+///     assert_eq!(values.len(), if table == "orders" { 30 } else { 10 });
+/// }
+/// ```
+pub struct DatasetGenerator<'a> {
+    dataset: &'a Dataset<'a>,
+    /// The names of the tables in the order they are generated.
+    order: Vec<&'a str>,
+    /// The schemas of the tables, including their foreign keys.
+    schemas: HashMap<&'a str, Schema<'a>>,
+    references: HashMap<&'a str, Vec<Reference>>,
+    /// The values generated for each referenced `<table>.<field>`.
+    keys: HashMap<(String, String), Rc<RefCell<Vec<Value>>>>,
+}
+
+impl<'a> DatasetGenerator<'a> {
+    /// Creates a new generator, checking that every foreign key references
+    /// a field of another table, and that the tables are not in a cycle.
+    pub fn new(dataset: &'a Dataset<'a>) -> Result<Self, Error> {
+        let mut schemas = HashMap::new();
+        let mut references = HashMap::new();
+        let mut keys = HashMap::new();
+        let mut dependencies: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+        for (name, table) in &dataset.tables {
+            let name = name.as_ref();
+            let mut schema = table.schema.clone();
+            let mut resolved = Vec::new();
+
+            for foreign_key in &table.foreign_keys {
+                let (parent, key) = foreign_key.references.split_once('.').ok_or_else(|| {
+                    Error::InvalidReference {
+                        table: name.to_owned(),
+                        references: foreign_key.references.clone(),
+                    }
+                })?;
+                let dt = dataset
+                    .tables
+                    .get(parent)
+                    .and_then(|parent| field(&parent.schema, key))
+                    .ok_or_else(|| Error::UnknownReference {
+                        table: name.to_owned(),
+                        references: foreign_key.references.clone(),
+                    })?;
+                if field(&table.schema, &foreign_key.field).is_some() {
+                    return Err(Error::DuplicateField {
+                        table: name.to_owned(),
+                        field: foreign_key.field.clone(),
+                    });
+                }
+
+                let children = foreign_key
+                    .children
+                    .as_ref()
+                    .map(Children::new)
+                    .transpose()
+                    .map_err(|reason| Error::InvalidChildren {
+                        table: name.to_owned(),
+                        reason,
+                    })?;
+
+                schema.with_field(foreign_key.field.clone(), dt.clone());
+                dependencies.entry(name).or_default().insert(parent);
+                keys.insert((parent.to_owned(), key.to_owned()), Rc::default());
+                resolved.push(Reference {
+                    field: foreign_key.field.clone(),
+                    table: parent.to_owned(),
+                    key: key.to_owned(),
+                    children,
+                });
+            }
+
+            match (
+                table.count,
+                resolved.iter().filter(|r| r.children.is_some()).count(),
+            ) {
+                (None, 0) => {
+                    return Err(Error::MissingCount {
+                        table: name.to_owned(),
+                    })
+                }
+                (Some(_), 0) | (None, 1) => {}
+                _ => {
+                    return Err(Error::AmbiguousCount {
+                        table: name.to_owned(),
+                    })
+                }
+            }
+
+            schemas.insert(name, schema);
+            references.insert(name, resolved);
+        }
+
+        // Tables are ordered after the tables they reference, and
+        // otherwise by name, so that the order is deterministic.
+        let mut order = Vec::new();
+        let mut remaining: BTreeSet<&str> =
+            dataset.tables.keys().map(|name| name.as_ref()).collect();
+        while !remaining.is_empty() {
+            let ready: Vec<_> = remaining
+                .iter()
+                .copied()
+                .filter(|name| {
+                    dependencies
+                        .get(name)
+                        .is_none_or(|parents| parents.iter().all(|parent| order.contains(parent)))
+                })
+                .collect();
+            if ready.is_empty() {
+                let tables: Vec<_> = remaining.iter().map(|name| format!("`{}`", name)).collect();
+                return Err(Error::Cycle {
+                    tables: tables.join(", "),
+                });
+            }
+            for name in ready {
+                remaining.remove(name);
+                order.push(name);
+            }
+        }
+
+        Ok(DatasetGenerator {
+            dataset,
+            order,
+            schemas,
+            references,
+            keys,
+        })
+    }
+
+    /// The names of the tables, in the order they are generated.
+    pub fn tables(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.order.iter().copied()
+    }
+
+    /// The schema of a table, including its foreign keys, whose
+    /// data types are the data types of the referenced fields.
+    pub fn schema(&self, table: &str) -> Option<&Schema<'a>> {
+        self.schemas.get(table)
+    }
+
+    /// Creates the generator of a table along with its number of rows.
+    /// Every table it references must be generated beforehand, in
+    /// the order of [DatasetGenerator::tables], as the rows of the
+    /// table only reference the values generated until then.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not a table of the dataset.
+    pub fn table<R: Rng + ?Sized>(
+        &self,
+        table: &str,
+        rng: &mut R,
+    ) -> Result<(DataGenerator<'_>, u64), Error> {
+        let mut gen = DataGenerator::new(&self.schemas[table]);
+        let mut rows = self.dataset.tables[table].count.unwrap_or_default();
+
+        for reference in &self.references[table] {
+            let keys = &self.keys[&(reference.table.clone(), reference.key.clone())];
+            let f: Box<dyn DynDistribution> = match &reference.children {
+                Some(children) => {
+                    let values: Vec<_> = keys
+                        .borrow()
+                        .iter()
+                        .flat_map(|key| repeat_n(key.clone(), children.sample(rng)))
+                        .collect();
+                    rows = values.len() as u64;
+                    Box::new(Iter::new(values))
+                }
+                None if keys.borrow().is_empty() && rows > 0 => {
+                    return Err(Error::NoKeys {
+                        table: table.to_owned(),
+                        references: format!("{}.{}", reference.table, reference.key),
+                    })
+                }
+                None => Box::new(Choose::new(keys.clone())),
+            };
+
+            if let Some(dt) = gen.field_mut(&reference.field) {
+                *dt = PhysicalDataType::Proxy { f };
+            }
+        }
+
+        // The values of the fields referenced by other tables are kept.
+        for ((parent, key), values) in &self.keys {
+            if parent != table {
+                continue;
+            }
+            values.borrow_mut().clear();
+            if let Some(dt) = gen.field_mut(key) {
+                let element = std::mem::replace(dt, PhysicalDataType::Boolean);
+                *dt = PhysicalDataType::Recorded {
+                    element: Box::new(element),
+                    values: values.clone(),
+                };
+            }
+        }
+
+        Ok((gen, rows))
+    }
+}
+
+/// Finds the data type of a top level field of a schema.
+fn field<'s>(schema: &'s Schema<'s>, name: &str) -> Option<&'s DataType<'s>> {
+    schema
+        .iter()
+        .find(|(field, _)| *field == name)
+        .map(|(_, dt)| dt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dataset, DatasetGenerator, Error};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde_json::Value;
+    use std::collections::{HashMap, HashSet};
+
+    const SHOP: &str = r#"{
+        "order_items": {
+            "schema": { "quantity": { "type": "range", "from": 1, "to": 5 } },
+            "foreign_keys": [
+                {
+                    "field": "order_id",
+                    "references": "orders.id",
+                    "children": { "type": "range", "from": 1, "to": 4 }
+                },
+                { "field": "sku", "references": "products.sku" }
+            ]
+        },
+        "orders": {
+            "schema": { "id": { "type": "uuid" } },
+            "foreign_keys": [{
+                "field": "customer_id",
+                "references": "customers.id",
+                "children": { "type": "weighted", "weights": [1, 0, 3] }
+            }]
+        },
+        "customers": { "count": 100, "schema": { "id": { "type": "serial" } } },
+        "products": { "count": 20, "schema": { "sku": { "type": "regex", "pattern": "[A-Z]{8}" } } }
+    }"#;
+
+    #[test]
+    fn test_referential_integrity() {
+        let dataset: Dataset = serde_json::from_str(SHOP).unwrap();
+        let generator = DatasetGenerator::new(&dataset).unwrap();
+        assert_eq!(
+            generator.tables().collect::<Vec<_>>(),
+            ["customers", "products", "orders", "order_items"]
+        );
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut tables: HashMap<&str, Vec<Value>> = HashMap::new();
+        for table in generator.tables() {
+            let (gen, rows) = generator.table(table, &mut rng).unwrap();
+            let values = (0..rows).map(|_| rng.sample(&gen)).collect();
+            tables.insert(table, values);
+        }
+
+        let keys = |table: &str, field: &str| -> HashSet<Value> {
+            tables[table].iter().map(|row| row[field].clone()).collect()
+        };
+        assert_eq!(tables["customers"].len(), 100);
+        assert!(keys("customers", "id").is_superset(&keys("orders", "customer_id")));
+        assert!(keys("orders", "id").is_superset(&keys("order_items", "order_id")));
+        assert!(keys("products", "sku").is_superset(&keys("order_items", "sku")));
+
+        // Customers have either no orders or two orders.
+        let mut orders: HashMap<&Value, usize> = HashMap::new();
+        for order in &tables["orders"] {
+            *orders.entry(&order["customer_id"]).or_default() += 1;
+        }
+        assert!(orders.values().all(|count| *count == 2));
+        assert_eq!(tables["orders"].len(), orders.len() * 2);
+
+        let items = tables["order_items"].len();
+        let orders = tables["orders"].len();
+        assert!(orders <= items && items < orders * 4);
+    }
+
+    #[test]
+    fn test_invalid_datasets() {
+        let invalid = [
+            r#"{ "a": { "schema": {} } }"#,
+            r#"{ "a": { "count": 1, "schema": {}, "foreign_keys": [{ "field": "b_id", "references": "b" }] } }"#,
+            r#"{ "a": { "count": 1, "schema": {}, "foreign_keys": [{ "field": "b_id", "references": "b.id" }] } }"#,
+            r#"{
+                "a": { "count": 1, "schema": { "id": { "type": "serial" } }, "foreign_keys": [{ "field": "b_id", "references": "b.id" }] },
+                "b": { "count": 1, "schema": { "id": { "type": "serial" } }, "foreign_keys": [{ "field": "a_id", "references": "a.id" }] }
+            }"#,
+            r#"{
+                "a": { "count": 1, "schema": { "id": { "type": "serial" } } },
+                "b": { "schema": {}, "foreign_keys": [{ "field": "a_id", "references": "a.id", "children": { "type": "range", "from": 2, "to": 2 } }] }
+            }"#,
+        ];
+
+        for dataset in invalid {
+            let dataset: Dataset = serde_json::from_str(dataset).unwrap();
+            assert!(DatasetGenerator::new(&dataset).is_err());
+        }
+
+        let dataset: Dataset = serde_json::from_str(
+            r#"{
+                "a": { "count": 1, "schema": { "id": { "type": "serial" } } },
+                "b": { "schema": { "a_id": { "type": "integer" } }, "foreign_keys": [{ "field": "a_id", "references": "a.id" }] }
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            DatasetGenerator::new(&dataset),
+            Err(Error::DuplicateField { .. })
+        ));
+    }
+}
@@ -1,9 +1,30 @@
-use crate::physical::physical_types::PhysicalDataType;
+use crate::physical::physical_types::{sample_fields, PhysicalDataType};
+use crate::pool::Members;
 use crate::schema::Schema;
+use crate::selection::KeyFrequencies;
+#[cfg(feature = "arrow")]
+use arrow::{
+    array::{RecordBatch, RecordBatchOptions},
+    datatypes::SchemaRef,
+    error::ArrowError,
+};
 use rand::distributions::Distribution;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
+use thiserror::Error;
+
+/// An error while generating values, which
+/// cannot be detected before sampling them.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("the values of field `{field}` are exhausted after {count} distinct values")]
+    Exhausted { field: String, count: usize },
+}
 
 /// A [DataGenerator] is a [Distribution] that can
 /// be used to generate realistic looking values
@@ -30,32 +51,168 @@ use std::collections::HashMap;
 /// println!("{}", thread_rng().sample(&gen))
 /// ```
 pub struct DataGenerator<'a> {
-    fields: HashMap<&'a str, PhysicalDataType<'a>>,
+    fields: BTreeMap<&'a str, PhysicalDataType<'a>>,
+    pools: BTreeMap<&'a str, Rc<Members<'a>>>,
+    #[cfg(feature = "arrow")]
+    arrow_schema: SchemaRef,
 }
 
 impl<'a> DataGenerator<'a> {
     /// Creates a new generator.
     pub fn new(schema: &'a Schema<'a>) -> Self {
         let mut generator = DataGenerator {
-            fields: HashMap::new(),
+            fields: BTreeMap::new(),
+            pools: BTreeMap::new(),
+            #[cfg(feature = "arrow")]
+            arrow_schema: Arc::new(crate::columnar::arrow_schema(schema)),
         };
 
+        for (name, pool) in schema.pools() {
+            generator.pools.insert(name, Rc::new(Members::new(pool)));
+        }
+
+        let pools: HashMap<_, _> = generator
+            .pools
+            .iter()
+            .map(|(name, members)| (*name, members.clone()))
+            .collect();
         for (field, data_type) in schema.iter() {
-            generator.fields.insert(field, data_type.into());
+            let mut dt = PhysicalDataType::from(data_type);
+            dt.resolve(&pools, true);
+            generator.fields.insert(field, dt);
         }
 
         generator
     }
-}
 
-impl<'a> Distribution<Value> for DataGenerator<'a> {
-    fn sample<'b, R: Rng + ?Sized>(&self, rng: &mut R) -> Value {
-        let fields = self
+    /// Checks that every value generated so far was valid, which is
+    /// not the case when the distinct values of a unique type, or of
+    /// a cardinality pool, were exhausted and nulls were generated.
+    pub fn check(&self) -> Result<(), Error> {
+        if let Some((field, count)) = self
             .fields
             .iter()
-            .map(|(name, dt)| (name.to_string(), rng.sample(dt)))
-            .collect();
+            .find_map(|(field, dt)| Some((field, dt.exhausted()?)))
+        {
+            return Err(Error::Exhausted {
+                field: field.to_string(),
+                count,
+            });
+        }
+
+        // The fields of members are named after their pool
+        for (pool, members) in &self.pools {
+            if let Err(Error::Exhausted { field, count }) = members.generator().check() {
+                return Err(Error::Exhausted {
+                    field: format!("{}.{}", pool, field),
+                    count,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Continues after the given number of values generated from the
+    /// seed, such as the values of the previous pages of a seed, where
+    /// serials count on from the offset. The pools of cardinalities
+    /// and the members of pools are generated from the seed alone, so
+    /// that they are the same at every offset.
+    pub fn seek(&self, seed: u64, offset: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for dt in self.fields.values() {
+            dt.seek(offset, &mut rng);
+        }
+        for members in self.pools.values() {
+            members.seed(seed);
+        }
+    }
+
+    /// Whether any field is unique, whose values can not be continued
+    /// with [DataGenerator::seek] but only by generating the values
+    /// before the offset again.
+    pub fn has_unique(&self) -> bool {
+        self.fields.values().any(|dt| dt.has_unique())
+    }
 
-        Value::Object(fields)
+    /// How often each key of the selections of the fields and pools
+    /// was used so far, such as the numbers of a `range` with a Zipf
+    /// selection. Only selections which are set are included.
+    pub fn key_frequencies(&self) -> Vec<KeyFrequencies> {
+        let mut frequencies = Vec::new();
+        for (field, dt) in &self.fields {
+            dt.frequencies(field, &mut frequencies);
+        }
+        for (pool, members) in &self.pools {
+            frequencies.extend(members.frequencies(pool));
+        }
+        frequencies
+    }
+
+    /// The physical type of a top level field, which datasets
+    /// replace to generate foreign keys and to record keys.
+    pub(crate) fn field_mut(&mut self, name: &str) -> Option<&mut PhysicalDataType<'a>> {
+        self.fields.get_mut(name)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl<'a> DataGenerator<'a> {
+    /// The Arrow schema of the record batches
+    /// created by [DataGenerator::record_batch].
+    pub fn arrow_schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
+
+    /// Generates a [RecordBatch] of the given number of rows. Values
+    /// are generated column-wise, which is considerably faster than
+    /// sampling individual [Value]s and converting them to Arrow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use data_gen_lib::generator::DataGenerator;
+    /// use data_gen_lib::schema::Schema;
+    /// use data_gen_lib::data_type::DataType;
+    /// use rand::thread_rng;
+    ///
+    /// let mut schema = Schema::default();
+    /// schema.with_field("my_field", DataType::Boolean);
+    ///
+    /// let gen = DataGenerator::new(&schema);
+    /// let batch = gen.record_batch(&mut thread_rng(), 1024).unwrap();
+    ///
+    /// assert_eq!(1024, batch.num_rows());
+    /// ```
+    pub fn record_batch<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        rows: usize,
+    ) -> Result<RecordBatch, ArrowError> {
+        for members in self.pools.values() {
+            members.select(rows, rng);
+        }
+        let columns = self
+            .arrow_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                self.fields[field.name().as_str()].sample_column(field.data_type(), rows, rng)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        RecordBatch::try_new_with_options(
+            self.arrow_schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(rows)),
+        )
+    }
+}
+
+impl<'a> Distribution<Value> for DataGenerator<'a> {
+    fn sample<'b, R: Rng + ?Sized>(&self, rng: &mut R) -> Value {
+        for members in self.pools.values() {
+            members.select(1, rng);
+        }
+        sample_fields(&self.fields, rng)
     }
 }
@@ -0,0 +1,519 @@
+use crate::data_type::DataType;
+use crate::import::{Import, Warning};
+use crate::schema::Schema;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The maximum number of generated elements
+/// for Avro arrays and maps.
+const COLLECTION_SIZE: u32 = 5;
+
+/// The probability of a null value for
+/// unions which include `null`.
+const NULL_RATE: f64 = 0.1;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("malformed Avro schema")]
+    Malformed { source: serde_json::Error },
+
+    #[error("the top level of an Avro schema must be a record")]
+    NotARecord,
+}
+
+/// Converts an [Avro](https://avro.apache.org/docs/current/specification/)
+/// schema into a [Schema]. The top level type must be a record, each
+/// of whose fields becomes a field of the [Schema].
+///
+/// | Avro                             | DataType     |
+/// |----------------------------------|--------------|
+/// | `record`                         | `object`     |
+/// | `enum`                           | `one_of`     |
+/// | `array`                          | `array`      |
+/// | `map`                            | `map`        |
+/// | `["null", T]`                    | `nullable`   |
+/// | `[A, B]`                         | `union`      |
+/// | `int`                            | `integer`    |
+/// | `long`                           | `big_int`    |
+/// | `float`, `double`                | `float`      |
+/// | `timestamp-millis`, `timestamp-micros`, `date` | `timestamp` |
+/// | `decimal`                        | `decimal`    |
+/// | `uuid`                           | `uuid`       |
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::import::avro;
+///
+/// let import = avro::import(r#"{
+///     "type": "record",
+///     "name": "User",
+///     "fields": [
+///         { "name": "id", "type": { "type": "string", "logicalType": "uuid" } },
+///         { "name": "age", "type": ["null", "int"] }
+///     ]
+/// }"#).unwrap();
+///
+/// assert!(import.warnings.is_empty());
+/// assert_eq!(2, import.schema.iter().count());
+/// ```
+pub fn import(source: &str) -> Result<Import, Error> {
+    let root: Value = serde_json::from_str(source).map_err(|e| Error::Malformed { source: e })?;
+    from_value(&root)
+}
+
+/// Converts an already parsed Avro schema into a [Schema].
+pub fn from_value(root: &Value) -> Result<Import, Error> {
+    let mut converter = Converter {
+        names: HashMap::new(),
+        expanding: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    match converter.convert("", None, root) {
+        Some(DataType::Object { fields }) if is_record(root) => {
+            let mut schema = Schema::default();
+            for (name, dt) in fields {
+                schema.with_field(name, dt);
+            }
+
+            Ok(Import {
+                schema,
+                warnings: converter.warnings,
+            })
+        }
+        _ => Err(Error::NotARecord),
+    }
+}
+
+fn is_record(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("record")
+}
+
+struct Converter {
+    /// Named types, keyed by their full name.
+    names: HashMap<String, Value>,
+    /// Named types currently being converted,
+    /// used to detect recursive types.
+    expanding: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Converter {
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        let path = if path.is_empty() { "<root>" } else { path };
+        self.warnings.push(Warning {
+            path: path.to_string(),
+            message: message.into(),
+        })
+    }
+
+    fn convert(
+        &mut self,
+        path: &str,
+        namespace: Option<&str>,
+        schema: &Value,
+    ) -> Option<DataType<'static>> {
+        match schema {
+            Value::String(name) => self.named(path, namespace, name),
+            Value::Array(branches) => self.union(path, namespace, branches),
+            Value::Object(schema) => self.complex(path, namespace, schema),
+            _ => {
+                self.warn(
+                    path,
+                    format!("invalid Avro schema `{}` was dropped", schema),
+                );
+                None
+            }
+        }
+    }
+
+    fn named(
+        &mut self,
+        path: &str,
+        namespace: Option<&str>,
+        name: &str,
+    ) -> Option<DataType<'static>> {
+        if let Some(dt) = primitive(name) {
+            return Some(dt);
+        }
+
+        let full_name = full_name(namespace, name);
+        let (full_name, schema) = match self.names.get(&full_name) {
+            Some(schema) => (full_name, schema.clone()),
+            None => match self.names.get(name) {
+                Some(schema) => (name.to_string(), schema.clone()),
+                None => {
+                    self.warn(path, format!("unknown type `{}` was dropped", name));
+                    return None;
+                }
+            },
+        };
+
+        if self.expanding.contains(&full_name) {
+            self.warn(
+                path,
+                format!("recursive type `{}` was truncated", full_name),
+            );
+            return None;
+        }
+
+        self.expanding.push(full_name);
+        let dt = self.convert_defined(path, namespace, &schema);
+        self.expanding.pop();
+        dt
+    }
+
+    fn union(
+        &mut self,
+        path: &str,
+        namespace: Option<&str>,
+        branches: &[Value],
+    ) -> Option<DataType<'static>> {
+        let nullable = branches.iter().any(|branch| branch == "null");
+        let mut variants: Vec<_> = branches
+            .iter()
+            .filter(|branch| *branch != "null")
+            .filter_map(|branch| self.convert(path, namespace, branch))
+            .collect();
+
+        let dt = match variants.len() {
+            0 => DataType::Nullable {
+                element: Box::new(DataType::Boolean),
+                null_rate: 1.0,
+            },
+            1 => variants.pop().unwrap(),
+            _ => DataType::Union { variants },
+        };
+
+        if nullable && !always_null(&dt) {
+            Some(DataType::Nullable {
+                element: Box::new(dt),
+                null_rate: NULL_RATE,
+            })
+        } else {
+            Some(dt)
+        }
+    }
+
+    fn complex(
+        &mut self,
+        path: &str,
+        namespace: Option<&str>,
+        schema: &Map<String, Value>,
+    ) -> Option<DataType<'static>> {
+        let tpe = match schema.get("type") {
+            Some(Value::String(tpe)) => tpe.as_str(),
+            Some(tpe) => return self.convert(path, namespace, tpe),
+            None => {
+                self.warn(path, "missing `type`, the field was dropped");
+                return None;
+            }
+        };
+
+        if let Some(Value::String(logical)) = schema.get("logicalType") {
+            if let Some(dt) = self.logical(path, logical, schema) {
+                return Some(dt);
+            }
+        }
+
+        match tpe {
+            "record" | "error" | "enum" | "fixed" => {
+                let name = schema.get("name").and_then(Value::as_str).unwrap_or(tpe);
+                let namespace = schema
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .or(namespace);
+                let full_name = full_name(namespace, name);
+
+                self.names
+                    .insert(full_name.clone(), Value::Object(schema.clone()));
+                self.expanding.push(full_name);
+                let dt = self.convert_defined(path, namespace, &Value::Object(schema.clone()));
+                self.expanding.pop();
+                dt
+            }
+            "array" => {
+                let items = schema.get("items").unwrap_or(&Value::Null);
+                let element = self.convert(&format!("{}[]", path), namespace, items)?;
+                Some(DataType::Array {
+                    element: Box::new(element),
+                    size: COLLECTION_SIZE,
+                    min_size: Some(0),
+                })
+            }
+            "map" => {
+                let values = schema.get("values").unwrap_or(&Value::Null);
+                let values = self.convert(&format!("{}{{}}", path), namespace, values)?;
+                Some(DataType::Map {
+                    keys: Box::new(regex("[a-z]{3,10}")),
+                    values: Box::new(values),
+                    size: COLLECTION_SIZE,
+                })
+            }
+            name => self.named(path, namespace, name),
+        }
+    }
+
+    /// Converts the definition of a named type.
+    fn convert_defined(
+        &mut self,
+        path: &str,
+        namespace: Option<&str>,
+        schema: &Value,
+    ) -> Option<DataType<'static>> {
+        let namespace = schema
+            .get("namespace")
+            .and_then(Value::as_str)
+            .or(namespace)
+            .map(str::to_string);
+        let namespace = namespace.as_deref();
+
+        match schema.get("type").and_then(Value::as_str) {
+            Some("record") | Some("error") => {
+                let mut fields = HashMap::new();
+                let empty = Vec::new();
+                let definitions = schema
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .unwrap_or(&empty);
+
+                for field in definitions {
+                    let name = match field.get("name").and_then(Value::as_str) {
+                        Some(name) => name,
+                        None => {
+                            self.warn(path, "record field without a name was dropped");
+                            continue;
+                        }
+                    };
+
+                    let path = if path.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{}.{}", path, name)
+                    };
+
+                    let tpe = field.get("type").unwrap_or(&Value::Null);
+                    if let Some(dt) = self.convert(&path, namespace, tpe) {
+                        fields.insert(Cow::Owned(name.to_string()), dt);
+                    }
+                }
+
+                Some(DataType::Object { fields })
+            }
+            Some("enum") => {
+                let symbols = schema
+                    .get("symbols")
+                    .and_then(Value::as_array)
+                    .map(|symbols| {
+                        symbols
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(|symbol| Cow::Owned(symbol.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                Some(DataType::OneOf {
+                    options: symbols,
+                    weights: None,
+                    selection: None,
+                })
+            }
+            Some("fixed") => {
+                let size = schema.get("size").and_then(Value::as_u64).unwrap_or(0);
+                Some(regex(&format!("[a-zA-Z0-9]{{{}}}", size)))
+            }
+            _ => {
+                self.warn(path, "invalid named type was dropped");
+                None
+            }
+        }
+    }
+
+    fn logical(
+        &mut self,
+        path: &str,
+        logical: &str,
+        schema: &Map<String, Value>,
+    ) -> Option<DataType<'static>> {
+        let timestamp = |format: &str| DataType::Timestamp {
+            format: Some(format.to_owned()),
+        };
+
+        match logical {
+            "timestamp-millis" | "local-timestamp-millis" => Some(timestamp("%s%3f")),
+            "timestamp-micros" | "local-timestamp-micros" => Some(timestamp("%s%6f")),
+            "date" => Some(timestamp("%F")),
+            "time-millis" => Some(DataType::Range {
+                from: 0,
+                to: 86_400_000,
+                selection: None,
+            }),
+            "time-micros" => Some(DataType::Range {
+                from: 0,
+                to: 86_400_000_000,
+                selection: None,
+            }),
+            "uuid" => Some(DataType::Uuid),
+            "decimal" => {
+                let precision = schema.get("precision").and_then(Value::as_u64);
+                let scale = schema.get("scale").and_then(Value::as_u64).unwrap_or(0);
+                match precision {
+                    Some(precision) => Some(DataType::Decimal {
+                        precision: precision as u32,
+                        scale: scale as u32,
+                        min: None,
+                        max: None,
+                    }),
+                    None => {
+                        self.warn(
+                            path,
+                            "decimal without a precision, using the underlying type",
+                        );
+                        None
+                    }
+                }
+            }
+            unknown => {
+                self.warn(
+                    path,
+                    format!(
+                        "unsupported logical type `{}`, using the underlying type",
+                        unknown
+                    ),
+                );
+                None
+            }
+        }
+    }
+}
+
+fn always_null(dt: &DataType) -> bool {
+    matches!(dt, DataType::Nullable { null_rate, .. } if *null_rate >= 1.0)
+}
+
+fn full_name(namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn primitive(name: &str) -> Option<DataType<'static>> {
+    match name {
+        "null" => Some(DataType::Nullable {
+            element: Box::new(DataType::Boolean),
+            null_rate: 1.0,
+        }),
+        "boolean" => Some(DataType::Boolean),
+        "int" => Some(DataType::Integer),
+        "long" => Some(DataType::BigInt),
+        "float" | "double" => Some(DataType::Float {
+            from: -1_000_000.0,
+            to: 1_000_000.0,
+        }),
+        "bytes" | "string" => Some(regex("[a-zA-Z]{5,15}")),
+        _ => None,
+    }
+}
+
+fn regex(pattern: &str) -> DataType<'static> {
+    DataType::Regex {
+        pattern: pattern.to_owned().try_into().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::import;
+    use serde_json::json;
+
+    #[test]
+    fn test_import() {
+        let import = import(
+            r#"{
+            "type": "record",
+            "name": "Order",
+            "namespace": "com.example",
+            "fields": [
+                { "name": "id", "type": { "type": "string", "logicalType": "uuid" } },
+                { "name": "quantity", "type": "int" },
+                { "name": "total", "type": { "type": "bytes", "logicalType": "decimal", "precision": 8, "scale": 2 } },
+                { "name": "created", "type": { "type": "long", "logicalType": "timestamp-millis" } },
+                { "name": "status", "type": { "type": "enum", "name": "Status", "symbols": ["NEW", "SHIPPED"] } },
+                { "name": "previous", "type": ["null", "Status"] },
+                { "name": "tags", "type": { "type": "array", "items": "string" } },
+                { "name": "attributes", "type": { "type": "map", "values": "long" } },
+                { "name": "customer", "type": {
+                    "type": "record",
+                    "name": "Customer",
+                    "fields": [{ "name": "vip", "type": "boolean" }]
+                } }
+            ]
+        }"#,
+        )
+        .unwrap();
+
+        assert!(import.warnings.is_empty());
+
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        let expected = json!({
+            "id": { "type": "uuid" },
+            "quantity": { "type": "integer" },
+            "total": { "type": "decimal", "precision": 8, "scale": 2 },
+            "created": { "type": "timestamp", "format": "%s%3f" },
+            "status": { "type": "one_of", "options": ["NEW", "SHIPPED"] },
+            "previous": {
+                "type": "nullable",
+                "element": { "type": "one_of", "options": ["NEW", "SHIPPED"] },
+                "null_rate": 0.1
+            },
+            "tags": {
+                "type": "array",
+                "element": { "type": "regex", "pattern": "[a-zA-Z]{5,15}" },
+                "size": 5,
+                "min_size": 0
+            },
+            "attributes": {
+                "type": "map",
+                "keys": { "type": "regex", "pattern": "[a-z]{3,10}" },
+                "values": { "type": "big_int" },
+                "size": 5
+            },
+            "customer": {
+                "type": "object",
+                "fields": { "vip": { "type": "boolean" } }
+            }
+        });
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_recursive_types_warn() {
+        let import = import(
+            r#"{
+            "type": "record",
+            "name": "Node",
+            "fields": [
+                { "name": "value", "type": "int" },
+                { "name": "next", "type": ["null", "Node"] }
+            ]
+        }"#,
+        )
+        .unwrap();
+
+        assert_eq!(1, import.warnings.len());
+        assert_eq!("next", import.warnings[0].path);
+    }
+
+    #[test]
+    fn test_top_level_must_be_record() {
+        assert!(import(r#""string""#).is_err());
+    }
+}
@@ -0,0 +1,849 @@
+use crate::data_type::DataType;
+use crate::import::{Import, Warning};
+use crate::regex_pattern::RegexPattern;
+use crate::schema::Schema;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// Keywords which constrain generated values but
+/// cannot be represented by a [DataType].
+const UNSUPPORTED: &[&str] = &[
+    "contains",
+    "dependencies",
+    "dependentRequired",
+    "dependentSchemas",
+    "if",
+    "maxProperties",
+    "minProperties",
+    "multipleOf",
+    "not",
+    "patternProperties",
+    "prefixItems",
+    "uniqueItems",
+];
+
+/// The span of generated numbers when a schema
+/// only bounds one side of a range.
+const DEFAULT_SPAN: i64 = 1_000_000;
+
+/// The probability of omitting a property that
+/// is not listed as `required`.
+const MISSING_RATE: f64 = 0.2;
+
+/// The probability of a null value for types
+/// that include `null`.
+const NULL_RATE: f64 = 0.1;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("malformed JSON Schema")]
+    Malformed { source: serde_json::Error },
+
+    #[error("the root of a JSON Schema must describe an object with properties")]
+    NotAnObject,
+
+    #[error("{path}: {message}")]
+    Unsatisfiable { path: String, message: String },
+}
+
+/// Converts a [JSON Schema](https://json-schema.org) document
+/// into a [Schema]. The root of the document must describe
+/// an object, each of whose properties becomes a field.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::import::json_schema;
+///
+/// let import = json_schema::import(r#"{
+///     "type": "object",
+///     "properties": {
+///         "id": { "type": "integer", "minimum": 1, "maximum": 10 },
+///         "email": { "type": "string", "format": "email" }
+///     },
+///     "required": ["id", "email"]
+/// }"#).unwrap();
+///
+/// assert!(import.warnings.is_empty());
+/// assert_eq!(2, import.schema.iter().count());
+/// ```
+pub fn import(source: &str) -> Result<Import, Error> {
+    let root: Value = serde_json::from_str(source).map_err(|e| Error::Malformed { source: e })?;
+    from_value(&root)
+}
+
+/// Converts an already parsed JSON Schema document into a [Schema].
+pub fn from_value(root: &Value) -> Result<Import, Error> {
+    let mut converter = Converter {
+        root,
+        references: Vec::new(),
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let dt = converter.convert("#", root);
+    if let Some(error) = converter.error {
+        return Err(error);
+    }
+
+    let mut warnings: Vec<Warning> = Vec::new();
+    for warning in converter.warnings {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    match dt {
+        Some(DataType::Object { fields }) => {
+            let mut schema = Schema::default();
+            for (name, dt) in fields {
+                schema.with_field(name, dt);
+            }
+
+            Ok(Import { schema, warnings })
+        }
+        _ => Err(Error::NotAnObject),
+    }
+}
+
+struct Converter<'r> {
+    root: &'r Value,
+    references: Vec<String>,
+    warnings: Vec<Warning>,
+    /// The first constraint found that no value satisfies.
+    error: Option<Error>,
+}
+
+impl<'r> Converter<'r> {
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        self.warnings.push(Warning {
+            path: path.to_string(),
+            message: message.into(),
+        })
+    }
+
+    fn fail(&mut self, path: &str, message: impl Into<String>) {
+        if self.error.is_none() {
+            self.error = Some(Error::Unsatisfiable {
+                path: path.to_string(),
+                message: message.into(),
+            })
+        }
+    }
+
+    fn convert(&mut self, path: &str, schema: &'r Value) -> Option<DataType<'static>> {
+        let schema = match schema {
+            Value::Object(schema) => schema,
+            Value::Bool(true) => return Some(string(None, None)),
+            _ => {
+                self.warn(path, "schema never matches a value and was dropped");
+                return None;
+            }
+        };
+
+        if let Some(reference) = schema.get("$ref") {
+            return self.reference(path, reference);
+        }
+
+        for keyword in UNSUPPORTED {
+            if schema.contains_key(*keyword) {
+                self.warn(
+                    path,
+                    format!("unsupported keyword `{}` was ignored", keyword),
+                );
+            }
+        }
+
+        if let Some(value) = schema.get("const") {
+            return self.constant(path, value);
+        }
+
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            return self.enumeration(path, values);
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(variants)) = schema.get(keyword) {
+                return self.union(&format!("{}/{}", path, keyword), variants);
+            }
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            return self.all_of(path, schema, schemas);
+        }
+
+        match schema.get("type") {
+            Some(Value::String(tpe)) => self.typed(path, tpe, schema),
+            Some(Value::Array(types)) => {
+                let nullable = types.iter().any(|tpe| tpe == "null");
+                let variants: Vec<_> = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|tpe| *tpe != "null")
+                    .filter_map(|tpe| self.typed(path, tpe, schema))
+                    .collect();
+
+                let dt = match variants.len() {
+                    0 => return self.typed(path, "null", schema),
+                    1 => variants.into_iter().next().unwrap(),
+                    _ => DataType::Union { variants },
+                };
+
+                if nullable {
+                    Some(DataType::Nullable {
+                        element: Box::new(dt),
+                        null_rate: NULL_RATE,
+                    })
+                } else {
+                    Some(dt)
+                }
+            }
+            Some(_) => {
+                self.warn(path, "`type` must be a string or an array of strings");
+                None
+            }
+            None => {
+                let tpe = if schema.contains_key("properties") {
+                    "object"
+                } else if schema.contains_key("items") {
+                    "array"
+                } else if schema.contains_key("minimum") || schema.contains_key("maximum") {
+                    "number"
+                } else {
+                    if !schema.contains_key("pattern") && !schema.contains_key("format") {
+                        self.warn(path, "schema has no `type`, generating strings");
+                    }
+                    "string"
+                };
+                self.typed(path, tpe, schema)
+            }
+        }
+    }
+
+    fn typed(
+        &mut self,
+        path: &str,
+        tpe: &str,
+        schema: &'r Map<String, Value>,
+    ) -> Option<DataType<'static>> {
+        match tpe {
+            "object" => Some(self.object(path, schema)),
+            "array" => self.array(path, schema),
+            "string" => Some(self.string(path, schema)),
+            "integer" => Some(self.integer(path, schema)),
+            "number" => Some(self.number(path, schema)),
+            "boolean" => Some(DataType::Boolean),
+            "null" => Some(DataType::Nullable {
+                element: Box::new(DataType::Boolean),
+                null_rate: 1.0,
+            }),
+            unknown => {
+                self.warn(path, format!("unknown type `{}` was dropped", unknown));
+                None
+            }
+        }
+    }
+
+    fn reference(&mut self, path: &str, reference: &Value) -> Option<DataType<'static>> {
+        let reference = match reference.as_str() {
+            Some(reference) => reference,
+            None => {
+                self.warn(path, "`$ref` must be a string");
+                return None;
+            }
+        };
+
+        if self.references.iter().any(|r| r == reference) {
+            self.warn(
+                path,
+                format!("recursive reference `{}` was truncated", reference),
+            );
+            return None;
+        }
+
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer));
+
+        match target {
+            Some(target) => {
+                self.references.push(reference.to_string());
+                let dt = self.convert(reference, target);
+                self.references.pop();
+                dt
+            }
+            None => {
+                self.warn(
+                    path,
+                    format!("unresolved reference `{}` was dropped", reference),
+                );
+                None
+            }
+        }
+    }
+
+    fn constant(&mut self, path: &str, value: &Value) -> Option<DataType<'static>> {
+        match value {
+            Value::String(s) => Some(DataType::Literal {
+                value: Cow::Owned(s.clone()),
+            }),
+            Value::Number(n) if n.is_i64() => {
+                let n = n.as_i64().unwrap();
+                Some(DataType::Range {
+                    from: n,
+                    to: n.saturating_add(1),
+                    selection: None,
+                })
+            }
+            Value::Number(n) => n.as_f64().map(|n| DataType::Float {
+                from: n,
+                to: n.next_up(),
+            }),
+            Value::Bool(b) => {
+                self.warn(
+                    path,
+                    format!("boolean constant `{}` is generated as a string", b),
+                );
+                Some(DataType::Literal {
+                    value: Cow::Owned(b.to_string()),
+                })
+            }
+            Value::Null => Some(DataType::Nullable {
+                element: Box::new(DataType::Boolean),
+                null_rate: 1.0,
+            }),
+            _ => {
+                self.warn(
+                    path,
+                    format!("unsupported constant `{}` was dropped", value),
+                );
+                None
+            }
+        }
+    }
+
+    fn enumeration(&mut self, path: &str, values: &[Value]) -> Option<DataType<'static>> {
+        let nullable = values.iter().any(Value::is_null);
+        let values: Vec<_> = values.iter().filter(|value| !value.is_null()).collect();
+
+        let dt = if values.contains(&&Value::Bool(true))
+            && values.contains(&&Value::Bool(false))
+            && values.iter().all(|value| value.is_boolean())
+        {
+            DataType::Boolean
+        } else if values.iter().all(|value| value.is_string()) {
+            DataType::OneOf {
+                options: values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(|value| Cow::Owned(value.to_string()))
+                    .collect(),
+                weights: None,
+                selection: None,
+            }
+        } else {
+            let variants: Vec<_> = values
+                .iter()
+                .filter_map(|value| self.constant(path, value))
+                .collect();
+
+            match variants.len() {
+                0 => return None,
+                1 => variants.into_iter().next().unwrap(),
+                _ => DataType::Union { variants },
+            }
+        };
+
+        if nullable {
+            Some(DataType::Nullable {
+                element: Box::new(dt),
+                null_rate: NULL_RATE,
+            })
+        } else {
+            Some(dt)
+        }
+    }
+
+    fn union(&mut self, path: &str, schemas: &'r [Value]) -> Option<DataType<'static>> {
+        let mut variants: Vec<_> = schemas
+            .iter()
+            .enumerate()
+            .filter_map(|(i, schema)| self.convert(&format!("{}/{}", path, i), schema))
+            .collect();
+
+        match variants.len() {
+            0 => None,
+            1 => variants.pop(),
+            _ => Some(DataType::Union { variants }),
+        }
+    }
+
+    /// Merges the subschemas of an `allOf` into a single
+    /// schema. Only object properties and `required` lists
+    /// are combined, for all other keywords the first
+    /// occurrence wins.
+    fn all_of(
+        &mut self,
+        path: &str,
+        schema: &Map<String, Value>,
+        schemas: &[Value],
+    ) -> Option<DataType<'static>> {
+        let mut merged = schema.clone();
+        merged.remove("allOf");
+
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+
+        for (i, subschema) in schemas.iter().enumerate() {
+            let subschema = match subschema {
+                Value::Object(subschema) => match subschema.get("$ref").and_then(Value::as_str) {
+                    Some(reference) => match reference
+                        .strip_prefix('#')
+                        .and_then(|pointer| self.root.pointer(pointer))
+                        .and_then(Value::as_object)
+                    {
+                        Some(target) => target,
+                        None => {
+                            self.warn(
+                                &format!("{}/allOf/{}", path, i),
+                                format!("unresolved reference `{}` was dropped", reference),
+                            );
+                            continue;
+                        }
+                    },
+                    None => subschema,
+                },
+                _ => continue,
+            };
+
+            for (key, value) in subschema {
+                match (key.as_str(), value) {
+                    ("properties", Value::Object(props)) => {
+                        properties.extend(props.clone());
+                    }
+                    ("required", Value::Array(names)) => {
+                        required.extend(names.clone());
+                    }
+                    _ => {
+                        merged.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Object(props)) = schema.get("properties") {
+            properties.extend(props.clone());
+        }
+        if let Some(Value::Array(names)) = schema.get("required") {
+            required.extend(names.clone());
+        }
+        if !properties.is_empty() {
+            merged.insert("properties".to_owned(), Value::Object(properties));
+        }
+        if !required.is_empty() {
+            merged.insert("required".to_owned(), Value::Array(required));
+        }
+
+        // the merged schema is not part of the document so
+        // it cannot be borrowed for the lifetime of the root
+        let merged = Value::Object(merged);
+        let mut converter = Converter {
+            root: self.root,
+            references: self.references.clone(),
+            warnings: Vec::new(),
+            error: None,
+        };
+        let dt = converter.convert(path, &merged);
+        self.warnings.extend(converter.warnings);
+        if let Some(error) = converter.error {
+            self.error.get_or_insert(error);
+        }
+        dt
+    }
+
+    fn object(&mut self, path: &str, schema: &'r Map<String, Value>) -> DataType<'static> {
+        let required: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        let mut fields = std::collections::HashMap::new();
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, property) in properties {
+                let path = format!("{}/properties/{}", path, name);
+                if let Some(dt) = self.convert(&path, property) {
+                    let dt = if required.contains(&name.as_str()) {
+                        dt
+                    } else {
+                        DataType::Optional {
+                            element: Box::new(dt),
+                            missing_rate: MISSING_RATE,
+                        }
+                    };
+                    fields.insert(Cow::Owned(name.clone()), dt);
+                }
+            }
+        }
+
+        DataType::Object { fields }
+    }
+
+    fn array(&mut self, path: &str, schema: &'r Map<String, Value>) -> Option<DataType<'static>> {
+        let element = match schema.get("items") {
+            Some(Value::Array(items)) => {
+                self.warn(
+                    path,
+                    "tuple `items` are not supported, using the first item",
+                );
+                items.first()
+            }
+            items => items,
+        };
+
+        let element = match element {
+            Some(element) => self.convert(&format!("{}/items", path), element)?,
+            None => string(None, None),
+        };
+
+        let min_size = unsigned(schema, "minItems");
+        let size = unsigned(schema, "maxItems").unwrap_or_else(|| min_size.unwrap_or(0).max(5));
+        if min_size.is_some_and(|min_size| min_size > size) {
+            self.fail(path, "`minItems` is greater than `maxItems`");
+            return None;
+        }
+        // arrays hold at least one element unless they must be empty
+        let min_size = min_size.or((size == 0).then_some(0));
+
+        Some(DataType::Array {
+            element: Box::new(element),
+            size,
+            min_size,
+        })
+    }
+
+    fn string(&mut self, path: &str, schema: &Map<String, Value>) -> DataType<'static> {
+        if let Some(Value::String(pattern)) = schema.get("pattern") {
+            // JSON Schema patterns are not anchored, but generated
+            // values are always a full match of the expression
+            let pattern = pattern.trim_start_matches('^').trim_end_matches('$');
+            match RegexPattern::try_from(pattern.to_string()) {
+                Ok(pattern) => return DataType::Regex { pattern },
+                Err(e) => self.warn(&format!("{}/pattern", path), e.to_string()),
+            }
+        }
+
+        if let Some(Value::String(format)) = schema.get("format") {
+            match format.as_str() {
+                "email" | "idn-email" => return DataType::Email,
+                "uuid" => return DataType::Uuid,
+                "date-time" => {
+                    return DataType::Timestamp {
+                        format: Some("%Y-%m-%dT%H:%M:%S%.3f%:z".to_owned()),
+                    }
+                }
+                "date" => {
+                    return DataType::Timestamp {
+                        format: Some("%F".to_owned()),
+                    }
+                }
+                "time" => {
+                    return DataType::Timestamp {
+                        format: Some("%T%:z".to_owned()),
+                    }
+                }
+                "ipv4" => {
+                    return regex(
+                        r"(25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)(\.(25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)){3}",
+                    )
+                }
+                "uri" | "url" => {
+                    return regex(r"https://www\.[a-z]{3,10}\.(com|org|io)(/[a-z]{1,8}){0,3}")
+                }
+                "hostname" => return regex(r"[a-z]{3,10}\.[a-z]{3,10}\.(com|org|io)"),
+                unknown => self.warn(
+                    &format!("{}/format", path),
+                    format!("unsupported format `{}`, generating plain strings", unknown),
+                ),
+            }
+        }
+
+        string(unsigned(schema, "minLength"), unsigned(schema, "maxLength"))
+    }
+
+    fn integer(&mut self, path: &str, schema: &Map<String, Value>) -> DataType<'static> {
+        let integer = |key: &str| schema.get(key).and_then(Value::as_f64);
+
+        // draft 4 uses booleans for exclusive bounds
+        // while later drafts use numbers
+        let from = match (integer("minimum"), schema.get("exclusiveMinimum")) {
+            (Some(min), Some(Value::Bool(true))) => Some((min.floor() as i64).saturating_add(1)),
+            (_, Some(Value::Number(min))) => min
+                .as_f64()
+                .map(|min| (min.floor() as i64).saturating_add(1)),
+            (Some(min), _) => Some(min.ceil() as i64),
+            (None, _) => None,
+        };
+
+        let to = match (integer("maximum"), schema.get("exclusiveMaximum")) {
+            (Some(max), Some(Value::Bool(true))) => Some(max.ceil() as i64),
+            (_, Some(Value::Number(max))) => max.as_f64().map(|max| max.ceil() as i64),
+            (Some(max), _) => Some((max.floor() as i64).saturating_add(1)),
+            (None, _) => None,
+        };
+
+        let (from, to) = match (from, to) {
+            (None, None) => return DataType::Integer,
+            (Some(from), None) => (from, from.saturating_add(DEFAULT_SPAN)),
+            (None, Some(to)) => (to.saturating_sub(DEFAULT_SPAN), to),
+            (Some(from), Some(to)) => (from, to),
+        };
+
+        if from >= to {
+            self.fail(path, "integer bounds do not contain any values");
+        }
+
+        DataType::Range {
+            from,
+            to,
+            selection: None,
+        }
+    }
+
+    fn number(&mut self, path: &str, schema: &Map<String, Value>) -> DataType<'static> {
+        // a bound is exclusive if given by the exclusive keyword, or
+        // by draft 4 booleans next to the inclusive keyword
+        let bound = |inclusive: &str, exclusive: &str| match schema.get(exclusive) {
+            Some(Value::Number(n)) => n.as_f64().map(|n| (n, true)),
+            Some(Value::Bool(exclusive)) => schema
+                .get(inclusive)
+                .and_then(Value::as_f64)
+                .map(|n| (n, *exclusive)),
+            _ => schema
+                .get(inclusive)
+                .and_then(Value::as_f64)
+                .map(|n| (n, false)),
+        };
+
+        let span = DEFAULT_SPAN as f64;
+        let (from, to) = match (
+            bound("minimum", "exclusiveMinimum"),
+            bound("maximum", "exclusiveMaximum"),
+        ) {
+            (None, None) => (-span, span),
+            (Some((from, _)), None) => (from, from + span),
+            (None, Some((to, _))) => (to - span, to),
+            // the upper bound of a float is exclusive, which only
+            // matters when both inclusive bounds are the same number
+            (Some((from, false)), Some((to, false))) if from == to => (from, to.next_up()),
+            (Some((from, _)), Some((to, _))) => (from, to),
+        };
+
+        if from >= to {
+            self.fail(path, "number bounds do not contain any values");
+        }
+
+        DataType::Float { from, to }
+    }
+}
+
+fn unsigned(schema: &Map<String, Value>, key: &str) -> Option<u32> {
+    schema
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(|n| n.min(u32::MAX as u64) as u32)
+}
+
+fn regex(pattern: &str) -> DataType<'static> {
+    DataType::Regex {
+        pattern: pattern.to_owned().try_into().unwrap(),
+    }
+}
+
+fn string(min_length: Option<u32>, max_length: Option<u32>) -> DataType<'static> {
+    let (min, max) = match (min_length, max_length) {
+        (None, None) => (5, 15),
+        (Some(min), None) => (min, min.max(15)),
+        (None, Some(max)) => (max.min(5), max),
+        (Some(min), Some(max)) => (min, max.max(min)),
+    };
+
+    regex(&format!("[a-zA-Z]{{{},{}}}", min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import, Error};
+    use crate::data_type::DataType;
+    use serde_json::json;
+
+    #[test]
+    fn test_import() {
+        let import = import(
+            r##"{
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "age": { "type": "integer", "minimum": 18, "exclusiveMaximum": 65 },
+                "score": { "type": ["number", "null"], "minimum": 0, "maximum": 1 },
+                "status": { "enum": ["active", "inactive"] },
+                "tags": { "type": "array", "items": { "type": "string", "pattern": "^[a-z]{3}$" }, "minItems": 2, "maxItems": 4 },
+                "address": { "$ref": "#/$defs/address" },
+                "contact": { "oneOf": [{ "type": "string", "format": "email" }, { "type": "boolean" }] }
+            },
+            "required": ["id", "age", "score", "status", "tags", "address", "contact"],
+            "$defs": {
+                "address": {
+                    "type": "object",
+                    "properties": { "zip": { "type": "string", "pattern": "\\d{5}" } },
+                    "required": ["zip"]
+                }
+            }
+        }"##,
+        )
+        .unwrap();
+
+        assert!(import.warnings.is_empty());
+
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        let expected = json!({
+            "id": { "type": "uuid" },
+            "age": { "type": "range", "from": 18, "to": 65 },
+            "score": {
+                "type": "nullable",
+                "element": { "type": "float", "from": 0.0, "to": 1.0 },
+                "null_rate": 0.1
+            },
+            "status": { "type": "one_of", "options": ["active", "inactive"] },
+            "tags": {
+                "type": "array",
+                "element": { "type": "regex", "pattern": "[a-z]{3}" },
+                "size": 4,
+                "min_size": 2
+            },
+            "address": {
+                "type": "object",
+                "fields": { "zip": { "type": "regex", "pattern": "\\d{5}" } }
+            },
+            "contact": {
+                "type": "union",
+                "variants": [{ "type": "email" }, { "type": "boolean" }]
+            }
+        });
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_optional_properties() {
+        let import = import(r#"{ "properties": { "name": { "type": "boolean" } } }"#).unwrap();
+
+        let (_, dt) = import.schema.iter().next().unwrap();
+        assert!(matches!(dt, DataType::Optional { .. }));
+    }
+
+    #[test]
+    fn test_unsupported_constructs_warn() {
+        let import = import(
+            r##"{
+            "type": "object",
+            "properties": {
+                "even": { "type": "integer", "multipleOf": 2 },
+                "node": { "$ref": "#" },
+                "remote": { "$ref": "https://example.com/schema.json" }
+            }
+        }"##,
+        )
+        .unwrap();
+
+        let paths: Vec<_> = import
+            .warnings
+            .iter()
+            .map(|warning| warning.path.as_str())
+            .collect();
+
+        assert_eq!(
+            vec![
+                "#/properties/even",
+                "#/properties/node",
+                "#/properties/remote"
+            ],
+            paths
+        );
+    }
+
+    #[test]
+    fn test_closed_bounds() {
+        let import = import(
+            r#"{
+            "type": "object",
+            "properties": {
+                "five": { "type": "number", "minimum": 5, "maximum": 5 },
+                "empty": { "type": "array", "items": { "type": "boolean" }, "maxItems": 0 }
+            },
+            "required": ["five", "empty"]
+        }"#,
+        )
+        .unwrap();
+
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        let expected = json!({
+            "five": { "type": "float", "from": 5.0, "to": 5.0_f64.next_up() },
+            "empty": { "type": "array", "element": { "type": "boolean" }, "size": 0, "min_size": 0 }
+        });
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_unsatisfiable_bounds_fail() {
+        for property in [
+            r#"{ "type": "number", "minimum": 5, "exclusiveMaximum": 5 }"#,
+            r#"{ "type": "integer", "minimum": 5, "maximum": 4 }"#,
+            r#"{ "type": "array", "minItems": 3, "maxItems": 2 }"#,
+        ] {
+            let source = format!(r#"{{ "properties": {{ "x": {} }} }}"#, property);
+            assert!(
+                matches!(import(&source), Err(Error::Unsatisfiable { .. })),
+                "{}",
+                property
+            );
+        }
+    }
+
+    #[test]
+    fn test_enum_of_booleans_and_numbers() {
+        let import = import(
+            r#"{
+            "type": "object",
+            "properties": {
+                "flag": { "enum": [true, false] },
+                "ratio": { "enum": [0.5, 2] },
+                "on": { "const": true }
+            },
+            "required": ["flag", "ratio", "on"]
+        }"#,
+        )
+        .unwrap();
+
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        let expected = json!({
+            "flag": { "type": "boolean" },
+            "ratio": {
+                "type": "union",
+                "variants": [
+                    { "type": "float", "from": 0.5, "to": 0.5_f64.next_up() },
+                    { "type": "range", "from": 2, "to": 3 }
+                ]
+            },
+            "on": { "type": "literal", "value": "true" }
+        });
+        assert_eq!(expected, actual);
+        assert_eq!(1, import.warnings.len());
+    }
+
+    #[test]
+    fn test_root_must_be_object() {
+        assert!(import(r#"{ "type": "string" }"#).is_err());
+    }
+}
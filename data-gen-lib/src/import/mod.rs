@@ -0,0 +1,32 @@
+//! Importers which convert schemas written in other
+//! formats into data-gen [Schema]s.
+
+pub mod avro;
+pub mod json_schema;
+pub mod protobuf;
+pub mod sql;
+
+use crate::schema::Schema;
+use std::fmt::{Display, Formatter};
+
+/// The result of importing a foreign schema. Constructs
+/// that cannot be represented are reported as [Warning]s
+/// rather than silently dropped.
+pub struct Import {
+    pub schema: Schema<'static>,
+    pub warnings: Vec<Warning>,
+}
+
+/// A non-fatal problem found while importing a schema,
+/// along with the location of the offending construct.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
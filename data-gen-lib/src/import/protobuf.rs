@@ -0,0 +1,501 @@
+use crate::data_type::DataType;
+use crate::import::sql::heuristic;
+use crate::import::{Import, Warning};
+use crate::schema::Schema;
+use prost_reflect::{DescriptorPool, FieldDescriptor, Kind, MessageDescriptor};
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use protox::Compiler;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// The maximum number of generated elements
+/// for repeated and map fields.
+const COLLECTION_SIZE: u32 = 5;
+
+/// The probability of omitting a field with explicit presence.
+const MISSING_RATE: f64 = 0.2;
+
+/// The probability of a null value for wrapper types.
+const NULL_RATE: f64 = 0.1;
+
+/// The name under which in memory sources are compiled.
+const SOURCE_NAME: &str = "schema.proto";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("malformed Protobuf definition")]
+    Malformed { source: protox::Error },
+
+    #[error("no message is defined")]
+    NoMessage,
+
+    #[error("several messages are defined ({}), select one by name", .messages.join(", "))]
+    AmbiguousMessage { messages: Vec<String> },
+
+    #[error("message `{name}` is not defined, expected one of: {}", .messages.join(", "))]
+    UnknownMessage { name: String, messages: Vec<String> },
+}
+
+/// Converts a `.proto` file defining a single message into
+/// a [Schema], see [import_message] for the supported constructs.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::import::protobuf;
+///
+/// let import = protobuf::import(r#"
+///     syntax = "proto3";
+///
+///     message User {
+///         string email = 1;
+///         optional int32 age = 2;
+///         repeated string tags = 3;
+///     }
+/// "#).unwrap();
+///
+/// assert!(import.warnings.is_empty());
+/// assert_eq!(3, import.schema.iter().count());
+/// ```
+pub fn import(source: &str) -> Result<Import, Error> {
+    import_message(source, None)
+}
+
+/// Converts the named message of a `.proto` file into a [Schema]. The
+/// message may be omitted when the file defines a single top level
+/// message. Only the well known `google/protobuf` files may be imported,
+/// use [compile_file] and [from_descriptor] for files with other imports.
+pub fn import_message(source: &str, message: Option<&str>) -> Result<Import, Error> {
+    let pool = compile(source)?;
+    let message = find_message(&pool, message)?;
+    Ok(from_descriptor(&message))
+}
+
+/// Compiles a `.proto` source, resolving imports of the
+/// well known types such as `google.protobuf.Timestamp`.
+pub fn compile(source: &str) -> Result<DescriptorPool, Error> {
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(SourceFileResolver(source.to_owned()));
+    resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = Compiler::with_file_resolver(resolver);
+    compiler
+        .open_file(SOURCE_NAME)
+        .map_err(|e| Error::Malformed { source: e })?;
+    Ok(compiler.descriptor_pool())
+}
+
+/// Compiles a `.proto` file, imports are resolved
+/// relative to the directory containing the file.
+pub fn compile_file(path: &Path) -> Result<DescriptorPool, Error> {
+    let include = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut compiler = Compiler::new([include]).map_err(|e| Error::Malformed { source: e })?;
+    compiler
+        .open_file(path)
+        .map_err(|e| Error::Malformed { source: e })?;
+    Ok(compiler.descriptor_pool())
+}
+
+struct SourceFileResolver(String);
+
+impl FileResolver for SourceFileResolver {
+    fn open_file(&self, name: &str) -> Result<File, protox::Error> {
+        if name == SOURCE_NAME {
+            File::from_source(name, &self.0)
+        } else {
+            Err(protox::Error::file_not_found(name))
+        }
+    }
+}
+
+/// Finds a message by its full or short name. When no name is
+/// given, the pool must define a single top level message outside
+/// of the well known types.
+pub fn find_message(pool: &DescriptorPool, name: Option<&str>) -> Result<MessageDescriptor, Error> {
+    let candidates: Vec<_> = pool
+        .all_messages()
+        .filter(|message| {
+            !message.is_map_entry() && !message.parent_file().name().starts_with("google/protobuf/")
+        })
+        .collect();
+
+    let top_level = || {
+        candidates
+            .iter()
+            .filter(|message| message.parent_message().is_none())
+            .map(|message| message.full_name().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    match name {
+        Some(name) => candidates
+            .iter()
+            .find(|message| message.full_name() == name)
+            .or_else(|| candidates.iter().find(|message| message.name() == name))
+            .cloned()
+            .ok_or_else(|| Error::UnknownMessage {
+                name: name.to_string(),
+                messages: top_level(),
+            }),
+        None => {
+            let mut messages = candidates
+                .iter()
+                .filter(|message| message.parent_message().is_none());
+            match (messages.next(), messages.next()) {
+                (None, _) => Err(Error::NoMessage),
+                (Some(message), None) => Ok(message.clone()),
+                (Some(_), Some(_)) => Err(Error::AmbiguousMessage {
+                    messages: top_level(),
+                }),
+            }
+        }
+    }
+}
+
+/// Converts a message descriptor into a [Schema], each of whose
+/// fields becomes a field of the [Schema].
+///
+/// | Protobuf                                 | DataType    |
+/// |------------------------------------------|-------------|
+/// | message                                  | `object`    |
+/// | `enum`                                   | `one_of`    |
+/// | `repeated T`                             | `array`     |
+/// | `map<K, V>`                              | `map`       |
+/// | `int32`, `sint32`, `sfixed32`            | `integer`   |
+/// | `int64`, `sint64`, `sfixed64`            | `big_int`   |
+/// | `uint32`, `uint64`, `fixed32`, `fixed64` | `range`     |
+/// | `float`, `double`                        | `float`     |
+/// | `bool`                                   | `boolean`   |
+/// | `string`, `bytes`                        | `regex`     |
+/// | `google.protobuf.Timestamp`              | `timestamp` |
+/// | `google.protobuf.*Value` wrappers        | `nullable`  |
+///
+/// Fields with explicit presence, such as proto3 `optional` fields,
+/// are `optional`. A `oneof` is a `union` of objects holding one of
+/// its fields each, named after the `oneof`, since at most one of its
+/// fields may be set. Values otherwise follow the
+/// [JSON mapping](https://protobuf.dev/programming-guides/proto3/#json)
+/// of Protobuf, and field names such as `email` or `created_at` are
+/// used to pick more realistic generators for strings.
+pub fn from_descriptor(message: &MessageDescriptor) -> Import {
+    let mut converter = Converter {
+        expanding: vec![message.full_name().to_string()],
+        warnings: Vec::new(),
+    };
+
+    let mut schema = Schema::default();
+    for (name, dt) in converter.fields("", message) {
+        schema.with_field(name, dt);
+    }
+
+    Import {
+        schema,
+        warnings: converter.warnings,
+    }
+}
+
+struct Converter {
+    /// Messages currently being converted,
+    /// used to detect recursive types.
+    expanding: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Converter {
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        self.warnings.push(Warning {
+            path: path.to_string(),
+            message: message.into(),
+        })
+    }
+
+    fn fields(
+        &mut self,
+        path: &str,
+        message: &MessageDescriptor,
+    ) -> HashMap<Cow<'static, str>, DataType<'static>> {
+        let mut fields = HashMap::new();
+        let mut oneofs: HashMap<String, Vec<DataType<'static>>> = HashMap::new();
+
+        for field in message.fields() {
+            let path = if path.is_empty() {
+                field.name().to_string()
+            } else {
+                format!("{}.{}", path, field.name())
+            };
+
+            let proto3_optional = field.field_descriptor_proto().proto3_optional();
+            if let Some(oneof) = field.containing_oneof().filter(|_| !proto3_optional) {
+                // Members are set on their own, so they are never optional
+                if let Some(dt) = self.kind(&path, &field) {
+                    oneofs
+                        .entry(oneof.name().to_string())
+                        .or_default()
+                        .push(DataType::Object {
+                            fields: HashMap::from([(Cow::Owned(field.name().to_string()), dt)]),
+                        });
+                }
+                continue;
+            }
+
+            if let Some(dt) = self.field(&path, &field) {
+                fields.insert(Cow::Owned(field.name().to_string()), dt);
+            }
+        }
+
+        for (name, variants) in oneofs {
+            fields.insert(Cow::Owned(name), DataType::Union { variants });
+        }
+
+        fields
+    }
+
+    fn field(&mut self, path: &str, field: &FieldDescriptor) -> Option<DataType<'static>> {
+        if field.is_map() {
+            let entry = field.kind().as_message()?.clone();
+            let keys = self.kind(path, &entry.map_entry_key_field())?;
+            let values = self.kind(path, &entry.map_entry_value_field())?;
+            return Some(DataType::Map {
+                keys: Box::new(keys),
+                values: Box::new(values),
+                size: COLLECTION_SIZE,
+            });
+        }
+
+        let dt = self.kind(path, field)?;
+        if field.is_list() {
+            Some(DataType::Array {
+                element: Box::new(dt),
+                size: COLLECTION_SIZE,
+                min_size: Some(0),
+            })
+        } else if field.supports_presence() && !matches!(field.kind(), Kind::Message(_)) {
+            Some(DataType::Optional {
+                element: Box::new(dt),
+                missing_rate: MISSING_RATE,
+            })
+        } else {
+            Some(dt)
+        }
+    }
+
+    fn kind(&mut self, path: &str, field: &FieldDescriptor) -> Option<DataType<'static>> {
+        let dt = match field.kind() {
+            Kind::Double | Kind::Float => DataType::Float {
+                from: -1_000_000.0,
+                to: 1_000_000.0,
+            },
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => DataType::Integer,
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => DataType::BigInt,
+            Kind::Uint32 | Kind::Fixed32 => DataType::Range {
+                from: 0,
+                to: 1 << 32,
+                selection: None,
+            },
+            Kind::Uint64 | Kind::Fixed64 => DataType::Range {
+                from: 0,
+                to: i64::MAX,
+                selection: None,
+            },
+            Kind::Bool => DataType::Boolean,
+            Kind::String => regex("[a-zA-Z]{5,15}"),
+            Kind::Bytes => regex("([A-Za-z0-9+/]{4}){2,8}"),
+            Kind::Enum(descriptor) => DataType::OneOf {
+                options: descriptor
+                    .values()
+                    .map(|value| Cow::Owned(value.name().to_string()))
+                    .collect(),
+                weights: None,
+                selection: None,
+            },
+            Kind::Message(message) => return self.message(path, &message),
+        };
+
+        Some(heuristic(field.name(), dt))
+    }
+
+    fn message(&mut self, path: &str, message: &MessageDescriptor) -> Option<DataType<'static>> {
+        let dt = match message.full_name() {
+            "google.protobuf.Timestamp" => DataType::Timestamp {
+                format: Some("%Y-%m-%dT%H:%M:%S%.3f%:z".to_owned()),
+            },
+            "google.protobuf.Duration" => regex("[0-9]{1,5}(\\.[0-9]{3})?s"),
+            "google.protobuf.FieldMask" => regex("[a-z]{3,8}(,[a-z]{3,8}){0,2}"),
+            "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue" => {
+                let value = message.get_field_by_name("value")?;
+                DataType::Nullable {
+                    element: Box::new(self.kind(path, &value)?),
+                    null_rate: NULL_RATE,
+                }
+            }
+            "google.protobuf.Empty" => DataType::Object {
+                fields: HashMap::new(),
+            },
+            "google.protobuf.Struct" => {
+                self.warn(
+                    path,
+                    "arbitrary JSON objects are generated as empty objects",
+                );
+                DataType::Object {
+                    fields: HashMap::new(),
+                }
+            }
+            name @ ("google.protobuf.Any"
+            | "google.protobuf.Value"
+            | "google.protobuf.ListValue") => {
+                self.warn(path, format!("unsupported type `{}` was dropped", name));
+                return None;
+            }
+            name => {
+                if self.expanding.iter().any(|expanding| expanding == name) {
+                    self.warn(path, format!("recursive message `{}` was truncated", name));
+                    return None;
+                }
+
+                self.expanding.push(name.to_string());
+                let fields = self.fields(path, message);
+                self.expanding.pop();
+                DataType::Object { fields }
+            }
+        };
+
+        Some(dt)
+    }
+}
+
+fn regex(pattern: &str) -> DataType<'static> {
+    DataType::Regex {
+        pattern: pattern.to_owned().try_into().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import, import_message, Error};
+    use serde_json::json;
+
+    #[test]
+    fn test_import() {
+        let import = import(
+            r#"
+            syntax = "proto3";
+            package shop;
+
+            import "google/protobuf/timestamp.proto";
+            import "google/protobuf/wrappers.proto";
+
+            enum Status {
+                STATUS_UNSPECIFIED = 0;
+                STATUS_PAID = 1;
+            }
+
+            message Order {
+                message Line {
+                    string sku = 1;
+                    uint32 quantity = 2;
+                }
+
+                int64 id = 1;
+                string email = 2;
+                Status status = 3;
+                repeated Line lines = 4;
+                map<string, double> discounts = 5;
+                google.protobuf.Timestamp created_at = 6;
+                google.protobuf.StringValue note = 7;
+                optional bool gift = 8;
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(import.warnings.is_empty());
+        let schema = serde_json::to_value(&import.schema).unwrap();
+        assert_eq!(json!({ "type": "big_int" }), schema["id"]);
+        assert_eq!(json!({ "type": "email" }), schema["email"]);
+        assert_eq!(
+            json!(["STATUS_UNSPECIFIED", "STATUS_PAID"]),
+            schema["status"]["options"]
+        );
+        assert_eq!(
+            json!({ "type": "range", "from": 0, "to": 4294967296u64 }),
+            schema["lines"]["element"]["fields"]["quantity"]
+        );
+        assert_eq!("map", schema["discounts"]["type"]);
+        assert_eq!("%Y-%m-%dT%H:%M:%S%.3f%:z", schema["created_at"]["format"]);
+        assert_eq!("nullable", schema["note"]["type"]);
+        assert_eq!("optional", schema["gift"]["type"]);
+    }
+
+    #[test]
+    fn test_select_message() {
+        let source = "syntax = \"proto3\"; message A { int32 x = 1; } message B { bool y = 1; }";
+
+        assert!(matches!(
+            import(source),
+            Err(Error::AmbiguousMessage { messages }) if messages == vec!["A", "B"]
+        ));
+        assert_eq!(
+            1,
+            import_message(source, Some("B"))
+                .unwrap()
+                .schema
+                .iter()
+                .count()
+        );
+        assert!(matches!(
+            import_message(source, Some("C")),
+            Err(Error::UnknownMessage { .. })
+        ));
+        assert!(matches!(
+            import("syntax = \"proto3\"; message {"),
+            Err(Error::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let import = import(
+            r#"
+            syntax = "proto3";
+
+            message Node {
+                oneof value {
+                    string text = 1;
+                    int32 number = 2;
+                }
+                Node parent = 3;
+            }
+            "#,
+        )
+        .unwrap();
+
+        let warnings: Vec<_> = import.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            vec!["parent: recursive message `Node` was truncated"],
+            warnings
+        );
+        let schema = serde_json::to_value(&import.schema).unwrap();
+        assert_eq!(None, schema.get("text"));
+        assert_eq!(
+            json!([
+                { "type": "object", "fields": { "text": { "type": "regex", "pattern": "[a-zA-Z]{5,15}" } } },
+                { "type": "object", "fields": { "number": { "type": "integer" } } },
+            ]),
+            schema["value"]["variants"]
+        );
+    }
+}
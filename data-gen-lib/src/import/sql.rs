@@ -0,0 +1,1075 @@
+use crate::data_type::DataType;
+use crate::import::{Import, Warning};
+use crate::physical::physical_types::decimal_range;
+use crate::schema::Schema;
+use sqlparser::ast::{
+    AlterTableOperation, BinaryOperator, CharacterLength, ColumnDef, ColumnOption,
+    DataType as SqlType, EnumMember, ExactNumberInfo, Expr, Ident, ObjectName, Statement,
+    TableConstraint, TimezoneInfo, UnaryOperator, UserDefinedTypeRepresentation, Value,
+};
+use sqlparser::dialect::{MySqlDialect, PostgreSqlDialect};
+use sqlparser::parser::{Parser, ParserError};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The maximum number of generated elements for array columns.
+const COLLECTION_SIZE: u32 = 5;
+
+/// The span of generated numbers when a check
+/// constraint only bounds one side of a range.
+const DEFAULT_SPAN: i64 = 1_000_000;
+
+/// The probability of a null value for columns
+/// that are not declared `NOT NULL`.
+const NULL_RATE: f64 = 0.1;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("malformed SQL")]
+    Malformed { source: ParserError },
+
+    #[error("no CREATE TABLE statement was found")]
+    NoTable,
+
+    #[error("several tables are defined ({}), select one by name", .tables.join(", "))]
+    AmbiguousTable { tables: Vec<String> },
+
+    #[error("table `{name}` is not defined, expected one of: {}", .tables.join(", "))]
+    UnknownTable { name: String, tables: Vec<String> },
+
+    #[error("{path}: {message}")]
+    Unsatisfiable { path: String, message: String },
+}
+
+/// Converts SQL DDL defining a single table into a [Schema],
+/// see [import_table] for the supported constructs.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::import::sql;
+///
+/// let import = sql::import(r#"
+///     CREATE TABLE users (
+///         id SERIAL PRIMARY KEY,
+///         email VARCHAR(255) NOT NULL,
+///         age INTEGER CHECK (age BETWEEN 18 AND 99),
+///         created_at TIMESTAMP NOT NULL DEFAULT now()
+///     );
+/// "#).unwrap();
+///
+/// assert!(import.warnings.is_empty());
+/// assert_eq!(4, import.schema.iter().count());
+/// ```
+pub fn import(source: &str) -> Result<Import, Error> {
+    import_table(source, None)
+}
+
+/// Converts the `CREATE TABLE` statement for the named table into
+/// a [Schema], each of whose columns becomes a field. The table may
+/// be omitted when the DDL only defines a single table. Both the
+/// PostgreSQL and MySQL dialects are accepted.
+///
+/// Statements are applied in order, so a migration file which later
+/// adds or drops columns with `ALTER TABLE` is imported as its final
+/// state. Columns are converted as follows.
+///
+/// | SQL                                   | DataType    |
+/// |---------------------------------------|-------------|
+/// | `SMALLINT`, `INTEGER`, `BIGINT`       | `small_int`, `integer`, `big_int` |
+/// | `SERIAL`, `BIGSERIAL`                 | `serial`    |
+/// | `NUMERIC(p, s)`, `DECIMAL(p, s)`      | `decimal`   |
+/// | `REAL`, `DOUBLE PRECISION`            | `float`     |
+/// | `BOOLEAN`                             | `boolean`   |
+/// | `CHAR(n)`, `VARCHAR(n)`, `TEXT`       | `regex`     |
+/// | `DATE`, `TIME`, `TIMESTAMP`           | `timestamp` |
+/// | `UUID`                                | `uuid`      |
+/// | `ENUM(..)`, `CREATE TYPE .. AS ENUM`  | `one_of`    |
+/// | `T[]`                                 | `array`     |
+///
+/// Identity and `AUTO_INCREMENT` columns are generated as a `serial`,
+/// while computed columns are dropped. Columns which are not `NOT NULL`
+/// or part of the primary key are `nullable`. `CHECK` constraints bounding a column, or listing its
+/// values with `IN`, narrow the generated values. Columns with a string
+/// or integer constant `DEFAULT` produce the default half of the time.
+/// Finally, column names such as `email`, `phone` or `created_at` are
+/// used to pick more realistic generators for otherwise generic types.
+pub fn import_table(source: &str, table: Option<&str>) -> Result<Import, Error> {
+    let statements = Parser::parse_sql(&PostgreSqlDialect {}, source)
+        .or_else(|e| Parser::parse_sql(&MySqlDialect {}, source).map_err(|_| e))
+        .map_err(|e| Error::Malformed { source: e })?;
+
+    let mut converter = Converter {
+        enums: HashMap::new(),
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let mut tables: Vec<Table> = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::CreateType {
+                name,
+                representation: UserDefinedTypeRepresentation::Enum { labels },
+            } => {
+                let labels = labels.into_iter().map(|label| label.value).collect();
+                converter.enums.insert(object_name(&name), labels);
+            }
+            Statement::CreateTable(create) => {
+                let name = object_name(&create.name);
+                tables.retain(|table| table.name != name);
+
+                let mut primary_key = Vec::new();
+                let mut checks = Vec::new();
+                for constraint in create.constraints {
+                    match constraint {
+                        TableConstraint::PrimaryKey { columns, .. } => {
+                            primary_key.extend(columns.into_iter().map(|column| column.value))
+                        }
+                        TableConstraint::Check { expr, .. } => checks.push(*expr),
+                        _ => {}
+                    }
+                }
+
+                tables.push(Table {
+                    name,
+                    columns: create.columns,
+                    primary_key,
+                    checks,
+                });
+            }
+            Statement::AlterTable {
+                name, operations, ..
+            } => {
+                let name = object_name(&name);
+                if let Some(table) = tables.iter_mut().find(|table| table.name == name) {
+                    table.alter(operations);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let names: Vec<String> = tables.iter().map(|table| table.name.clone()).collect();
+    let table = match (table, tables.len()) {
+        (_, 0) => return Err(Error::NoTable),
+        (None, 1) => tables.remove(0),
+        (None, _) => return Err(Error::AmbiguousTable { tables: names }),
+        (Some(name), _) => match tables.into_iter().find(|table| table.name == name) {
+            Some(table) => table,
+            None => {
+                return Err(Error::UnknownTable {
+                    name: name.to_string(),
+                    tables: names,
+                })
+            }
+        },
+    };
+
+    let mut schema = Schema::default();
+    for column in &table.columns {
+        if let Some(dt) = converter.column(&table, column) {
+            schema.with_field(column.name.value.clone(), dt);
+        }
+    }
+    if let Some(error) = converter.error {
+        return Err(error);
+    }
+
+    Ok(Import {
+        schema,
+        warnings: converter.warnings,
+    })
+}
+
+/// The final definition of a table after
+/// applying every statement which alters it.
+struct Table {
+    name: String,
+    columns: Vec<ColumnDef>,
+    primary_key: Vec<String>,
+    checks: Vec<Expr>,
+}
+
+impl Table {
+    fn alter(&mut self, operations: Vec<AlterTableOperation>) {
+        for operation in operations {
+            match operation {
+                AlterTableOperation::AddColumn { column_def, .. } => self.columns.push(column_def),
+                AlterTableOperation::DropColumn { column_name, .. } => self
+                    .columns
+                    .retain(|column| column.name.value != column_name.value),
+                AlterTableOperation::AddConstraint(TableConstraint::Check { expr, .. }) => {
+                    self.checks.push(*expr)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A restriction on the values of a single
+/// column, taken from a `CHECK` constraint.
+enum Check {
+    Lower { value: f64, inclusive: bool },
+    Upper { value: f64, inclusive: bool },
+    Options(Vec<Value>),
+}
+
+struct Converter {
+    /// Enumerated types declared with `CREATE TYPE`.
+    enums: HashMap<String, Vec<String>>,
+    warnings: Vec<Warning>,
+    /// The first column whose constraints no value satisfies.
+    error: Option<Error>,
+}
+
+impl Converter {
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        self.warnings.push(Warning {
+            path: path.to_string(),
+            message: message.into(),
+        })
+    }
+
+    fn fail(&mut self, path: &str, message: impl Into<String>) {
+        if self.error.is_none() {
+            self.error = Some(Error::Unsatisfiable {
+                path: path.to_string(),
+                message: message.into(),
+            })
+        }
+    }
+
+    fn column(&mut self, table: &Table, column: &ColumnDef) -> Option<DataType<'static>> {
+        let name = &column.name.value;
+        let path = format!("{}.{}", table.name, name);
+
+        let mut not_null = table.primary_key.contains(name);
+        let mut identity = false;
+        let mut default = None;
+        let mut checks = Vec::new();
+        for option in &column.options {
+            match &option.option {
+                ColumnOption::Generated {
+                    generation_expr: Some(_),
+                    ..
+                } => {
+                    self.warn(&path, "generated column was dropped");
+                    return None;
+                }
+                ColumnOption::Generated { .. } => identity = true,
+                ColumnOption::DialectSpecific(tokens)
+                    if tokens
+                        .iter()
+                        .any(|token| token.to_string().eq_ignore_ascii_case("AUTO_INCREMENT")) =>
+                {
+                    identity = true
+                }
+                ColumnOption::NotNull => not_null = true,
+                ColumnOption::Unique {
+                    is_primary: true, ..
+                } => not_null = true,
+                ColumnOption::Default(expr) => default = Some(expr),
+                ColumnOption::Check(expr) => self.checks(&path, name, expr, &mut checks),
+                _ => {}
+            }
+        }
+
+        for expr in &table.checks {
+            if references(expr, name) {
+                self.checks(&path, name, expr, &mut checks);
+            }
+        }
+
+        let mut dt = self.data_type(&path, &column.data_type)?;
+        if identity {
+            return Some(DataType::Serial);
+        } else if checks.is_empty() {
+            dt = heuristic(&name.to_lowercase(), dt);
+        } else {
+            dt = self.constrain(&path, dt, checks);
+        }
+
+        if let Some(default) = default {
+            dt = self.default(dt, default);
+        }
+
+        if not_null {
+            Some(dt)
+        } else {
+            Some(DataType::Nullable {
+                element: Box::new(dt),
+                null_rate: NULL_RATE,
+            })
+        }
+    }
+
+    fn data_type(&mut self, path: &str, data_type: &SqlType) -> Option<DataType<'static>> {
+        let dt = match data_type {
+            SqlType::TinyInt(Some(1)) | SqlType::Bool | SqlType::Boolean => DataType::Boolean,
+            SqlType::TinyInt(_) => DataType::Range { from: -128, to: 128, selection: None },
+            SqlType::UnsignedTinyInt(_) | SqlType::UInt8 => DataType::Range { from: 0, to: 256, selection: None },
+            SqlType::SmallInt(_) | SqlType::Int2(_) | SqlType::Int16 => DataType::SmallInt,
+            SqlType::UnsignedSmallInt(_) | SqlType::UnsignedInt2(_) | SqlType::UInt16 => {
+                DataType::Range { from: 0, to: 65536, selection: None }
+            }
+            SqlType::MediumInt(_) => DataType::Range {
+                from: -8388608,
+                to: 8388608,
+                selection: None,
+            },
+            SqlType::UnsignedMediumInt(_) => DataType::Range {
+                from: 0,
+                to: 16777216,
+                selection: None,
+            },
+            SqlType::Int(_) | SqlType::Int4(_) | SqlType::Integer(_) | SqlType::Int32 => {
+                DataType::Integer
+            }
+            SqlType::UnsignedInt(_)
+            | SqlType::UnsignedInt4(_)
+            | SqlType::UnsignedInteger(_)
+            | SqlType::UInt32 => DataType::Range {
+                from: 0,
+                to: 4294967296,
+                selection: None,
+            },
+            SqlType::BigInt(_) | SqlType::Int8(_) | SqlType::Int64 => DataType::BigInt,
+            SqlType::UnsignedBigInt(_) | SqlType::UnsignedInt8(_) | SqlType::UInt64 => {
+                DataType::Range {
+                    from: 0,
+                    to: i64::MAX,
+                    selection: None,
+                }
+            }
+            SqlType::Numeric(info)
+            | SqlType::Decimal(info)
+            | SqlType::Dec(info)
+            | SqlType::BigNumeric(info)
+            | SqlType::BigDecimal(info) => self.decimal(path, info),
+            SqlType::Float(_)
+            | SqlType::Float4
+            | SqlType::Float32
+            | SqlType::Float64
+            | SqlType::Float8
+            | SqlType::Real
+            | SqlType::Double
+            | SqlType::DoublePrecision => DataType::Float {
+                from: -(DEFAULT_SPAN as f64),
+                to: DEFAULT_SPAN as f64,
+            },
+            SqlType::Character(length) | SqlType::Char(length) => match length {
+                Some(CharacterLength::IntegerLength { length, .. }) if *length <= 16 => {
+                    regex(&format!("[A-Z]{{{}}}", length))
+                }
+                length => string(length),
+            },
+            SqlType::CharacterVarying(length)
+            | SqlType::CharVarying(length)
+            | SqlType::Varchar(length)
+            | SqlType::Nvarchar(length) => string(length),
+            SqlType::Text
+            | SqlType::TinyText
+            | SqlType::MediumText
+            | SqlType::LongText
+            | SqlType::String(_)
+            | SqlType::Clob(_)
+            | SqlType::CharacterLargeObject(_)
+            | SqlType::CharLargeObject(_) => string(&None),
+            SqlType::Bytea
+            | SqlType::Binary(_)
+            | SqlType::Varbinary(_)
+            | SqlType::Blob(_)
+            | SqlType::TinyBlob
+            | SqlType::MediumBlob
+            | SqlType::LongBlob
+            | SqlType::Bytes(_) => regex("\\\\x[0-9a-f]{32}"),
+            SqlType::Uuid => DataType::Uuid,
+            SqlType::Date => timestamp("%F"),
+            SqlType::Time(_, _) => timestamp("%T"),
+            SqlType::Datetime(_) => timestamp("%F %T"),
+            SqlType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                timestamp("%F %T%:z")
+            }
+            SqlType::Timestamp(_, _) => timestamp("%F %T"),
+            SqlType::Enum(members, _) => DataType::OneOf {
+                options: members
+                    .iter()
+                    .map(|member| match member {
+                        EnumMember::Name(name) | EnumMember::NamedValue(name, _) => {
+                            Cow::Owned(name.clone())
+                        }
+                    })
+                    .collect(),
+                weights: None,
+                selection: None,
+            },
+            SqlType::Array(element) => {
+                let element = match element {
+                    sqlparser::ast::ArrayElemTypeDef::AngleBracket(element)
+                    | sqlparser::ast::ArrayElemTypeDef::SquareBracket(element, _)
+                    | sqlparser::ast::ArrayElemTypeDef::Parenthesis(element) => element,
+                    sqlparser::ast::ArrayElemTypeDef::None => {
+                        self.warn(path, "array without an element type was dropped");
+                        return None;
+                    }
+                };
+
+                DataType::Array {
+                    element: Box::new(self.data_type(&format!("{}[]", path), element)?),
+                    size: COLLECTION_SIZE,
+                    min_size: Some(0),
+                }
+            }
+            SqlType::JSON | SqlType::JSONB => {
+                self.warn(path, "JSON columns are generated as empty objects");
+                DataType::Object {
+                    fields: HashMap::new(),
+                }
+            }
+            SqlType::Custom(name, _) => match object_name(name).to_lowercase().as_str() {
+                "serial" | "serial4" | "bigserial" | "serial8" | "smallserial" | "serial2" => {
+                    DataType::Serial
+                }
+                "citext" => string(&None),
+                "inet" => regex("((25[0-5]|(2[0-4]|1[0-9]|[1-9]|)[0-9])\\.){3}(25[0-5]|(2[0-4]|1[0-9]|[1-9]|)[0-9])"),
+                "money" => DataType::Decimal {
+                    precision: 12,
+                    scale: 2,
+                    min: None,
+                    max: None,
+                },
+                _ => match self.enums.get(&object_name(name)) {
+                    Some(labels) => DataType::OneOf {
+                        options: labels.iter().cloned().map(Cow::Owned).collect(),
+                        weights: None,
+                        selection: None,
+                    },
+                    None => {
+                        self.warn(path, format!("unsupported type `{}` was dropped", name));
+                        return None;
+                    }
+                },
+            },
+            other => {
+                self.warn(path, format!("unsupported type `{}` was dropped", other));
+                return None;
+            }
+        };
+
+        Some(dt)
+    }
+
+    fn decimal(&mut self, path: &str, info: &ExactNumberInfo) -> DataType<'static> {
+        let (precision, scale) = match *info {
+            ExactNumberInfo::None => (10, 2),
+            ExactNumberInfo::Precision(precision) => (precision, 0),
+            ExactNumberInfo::PrecisionAndScale(precision, scale) => (precision, scale),
+        };
+
+        if precision > 38 {
+            self.warn(
+                path,
+                format!("precision {} was reduced to the maximum of 38", precision),
+            );
+        }
+
+        let precision = precision.clamp(1, 38) as u32;
+        DataType::Decimal {
+            precision,
+            scale: (scale as u32).min(precision),
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Collects the restrictions a check constraint places on a
+    /// column, warning about any part which cannot be represented.
+    fn checks(&mut self, path: &str, column: &str, expr: &Expr, checks: &mut Vec<Check>) {
+        match expr {
+            Expr::Nested(expr) => self.checks(path, column, expr, checks),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
+                self.checks(path, column, left, checks);
+                self.checks(path, column, right, checks);
+            }
+            Expr::Between {
+                expr: operand,
+                negated: false,
+                low,
+                high,
+            } if is_column(operand, column) => match (number(low), number(high)) {
+                (Some(low), Some(high)) => {
+                    checks.push(Check::Lower {
+                        value: low,
+                        inclusive: true,
+                    });
+                    checks.push(Check::Upper {
+                        value: high,
+                        inclusive: true,
+                    });
+                }
+                _ => self.unsupported_check(path, expr),
+            },
+            Expr::InList {
+                expr: operand,
+                list,
+                negated: false,
+            } if is_column(operand, column) => {
+                let options: Option<Vec<Value>> = list.iter().map(literal).collect();
+                match options {
+                    Some(options) => checks.push(Check::Options(options)),
+                    None => self.unsupported_check(path, expr),
+                }
+            }
+            Expr::BinaryOp { left, op, right } => {
+                let (op, value) = if is_column(left, column) {
+                    (op.clone(), number(right))
+                } else if is_column(right, column) {
+                    (flip(op), number(left))
+                } else {
+                    (op.clone(), None)
+                };
+
+                let check = value.and_then(|value| match op {
+                    BinaryOperator::Gt => Some(Check::Lower {
+                        value,
+                        inclusive: false,
+                    }),
+                    BinaryOperator::GtEq => Some(Check::Lower {
+                        value,
+                        inclusive: true,
+                    }),
+                    BinaryOperator::Lt => Some(Check::Upper {
+                        value,
+                        inclusive: false,
+                    }),
+                    BinaryOperator::LtEq => Some(Check::Upper {
+                        value,
+                        inclusive: true,
+                    }),
+                    _ => None,
+                });
+
+                match check {
+                    Some(check) => checks.push(check),
+                    None => self.unsupported_check(path, expr),
+                }
+            }
+            _ => self.unsupported_check(path, expr),
+        }
+    }
+
+    fn unsupported_check(&mut self, path: &str, expr: &Expr) {
+        self.warn(path, format!("check constraint `{}` was ignored", expr))
+    }
+
+    /// Narrows a data type to the values permitted by check constraints.
+    fn constrain(
+        &mut self,
+        path: &str,
+        dt: DataType<'static>,
+        checks: Vec<Check>,
+    ) -> DataType<'static> {
+        let mut lower: Option<(f64, bool)> = None;
+        let mut upper: Option<(f64, bool)> = None;
+        let mut options: Option<Vec<Value>> = None;
+        for check in checks {
+            match check {
+                Check::Lower { value, inclusive } => {
+                    if lower.is_none_or(|(lower, _)| value >= lower) {
+                        lower = Some((value, inclusive));
+                    }
+                }
+                Check::Upper { value, inclusive } => {
+                    if upper.is_none_or(|(upper, _)| value <= upper) {
+                        upper = Some((value, inclusive));
+                    }
+                }
+                Check::Options(values) => options = Some(values),
+            }
+        }
+
+        if let Some(options) = options {
+            return match dt {
+                DataType::SmallInt
+                | DataType::Integer
+                | DataType::BigInt
+                | DataType::Range { .. } => {
+                    let variants: Option<Vec<_>> = options
+                        .iter()
+                        .map(|option| match option {
+                            Value::Number(n, _) => n.parse::<i64>().ok().map(|n| DataType::Range {
+                                from: n,
+                                to: n + 1,
+                                selection: None,
+                            }),
+                            _ => None,
+                        })
+                        .collect();
+
+                    match variants {
+                        Some(variants) => DataType::Union { variants },
+                        None => {
+                            self.warn(path, "check constraint values are not integers");
+                            dt
+                        }
+                    }
+                }
+                _ => DataType::OneOf {
+                    options: options
+                        .into_iter()
+                        .map(|option| match option {
+                            Value::SingleQuotedString(s) | Value::DoubleQuotedString(s) => {
+                                Cow::Owned(s)
+                            }
+                            other => Cow::Owned(other.to_string()),
+                        })
+                        .collect(),
+                    weights: None,
+                    selection: None,
+                },
+            };
+        }
+
+        if lower.is_none() && upper.is_none() {
+            return dt;
+        }
+
+        match dt {
+            DataType::SmallInt
+            | DataType::Integer
+            | DataType::BigInt
+            | DataType::Range { .. }
+            | DataType::Serial => {
+                let (min, max) = match dt {
+                    DataType::SmallInt => (-32768, 32768),
+                    DataType::Integer => (-2147483648, 2147483648),
+                    DataType::Range { from, to, .. } => (from, to),
+                    DataType::Serial => (1, 2147483648),
+                    _ => (i64::MIN, i64::MAX),
+                };
+
+                let from = lower.map(|(value, inclusive)| match inclusive {
+                    true => value.ceil() as i64,
+                    false => (value.floor() as i64).saturating_add(1),
+                });
+                let to = upper.map(|(value, inclusive)| match inclusive {
+                    true => (value.floor() as i64).saturating_add(1),
+                    false => value.ceil() as i64,
+                });
+
+                let (from, to) = match (from, to) {
+                    (Some(from), Some(to)) => (from.max(min), to.min(max)),
+                    (Some(from), None) => {
+                        (from.max(min), from.saturating_add(DEFAULT_SPAN).min(max))
+                    }
+                    (None, Some(to)) => (to.saturating_sub(DEFAULT_SPAN).max(min), to.min(max)),
+                    (None, None) => (min, max),
+                };
+
+                if from >= to {
+                    self.fail(path, "check constraints do not permit any values");
+                }
+
+                DataType::Range {
+                    from,
+                    to,
+                    selection: None,
+                }
+            }
+            DataType::Decimal {
+                precision, scale, ..
+            } => {
+                // exclusive bounds are replaced by the next decimal of the scale
+                let factor = 10f64.powi(scale as i32);
+                let unscaled = |value: f64| {
+                    let value = value * factor;
+                    match (value - value.round()).abs() < 1e-6 {
+                        true => value.round(),
+                        false => value,
+                    }
+                };
+                let min = lower.map(|(value, inclusive)| match inclusive {
+                    true => value,
+                    false => (unscaled(value).floor() + 1.0) / factor,
+                });
+                let max = upper.map(|(value, inclusive)| match inclusive {
+                    true => value,
+                    false => (unscaled(value).ceil() - 1.0) / factor,
+                });
+
+                if decimal_range(precision, scale, min, max).is_empty() {
+                    self.fail(path, "check constraints do not permit any values");
+                }
+
+                DataType::Decimal {
+                    precision,
+                    scale,
+                    min,
+                    max,
+                }
+            }
+            DataType::Float { .. } => {
+                let span = DEFAULT_SPAN as f64;
+                let (from, to) = match (lower, upper) {
+                    // the upper bound of a float is exclusive, which only
+                    // matters when both inclusive bounds are the same number
+                    (Some((from, true)), Some((to, true))) if from == to => (from, to.next_up()),
+                    (Some((from, _)), Some((to, _))) => (from, to),
+                    (Some((from, _)), None) => (from, from + span),
+                    (None, Some((to, _))) => (to - span, to),
+                    (None, None) => (-span, span),
+                };
+
+                if from >= to {
+                    self.fail(path, "check constraints do not permit any values");
+                }
+
+                DataType::Float { from, to }
+            }
+            dt => {
+                self.warn(
+                    path,
+                    "range check constraints on non-numeric columns are ignored",
+                );
+                dt
+            }
+        }
+    }
+
+    /// Applies a column default. Sequence, UUID and clock functions select
+    /// the matching generator, while string and integer constants are
+    /// produced half of the time.
+    fn default(&mut self, dt: DataType<'static>, default: &Expr) -> DataType<'static> {
+        let constant = match default {
+            Expr::Function(function) => {
+                return match object_name(&function.name).to_lowercase().as_str() {
+                    "nextval" => DataType::Serial,
+                    "gen_random_uuid" | "uuid_generate_v4" | "uuid" => DataType::Uuid,
+                    _ => dt,
+                };
+            }
+            Expr::Cast { expr, .. } => literal(expr),
+            expr => literal(expr),
+        };
+
+        let constant = match (constant, &dt) {
+            (
+                Some(Value::SingleQuotedString(s)),
+                DataType::Regex { .. } | DataType::OneOf { .. },
+            ) => DataType::Literal {
+                value: Cow::Owned(s),
+            },
+            (
+                Some(Value::Number(n, _)),
+                DataType::SmallInt | DataType::Integer | DataType::BigInt | DataType::Range { .. },
+            ) => match n.parse::<i64>() {
+                Ok(n) => DataType::Range {
+                    from: n,
+                    to: n + 1,
+                    selection: None,
+                },
+                Err(_) => return dt,
+            },
+            _ => return dt,
+        };
+
+        DataType::Union {
+            variants: vec![constant, dt],
+        }
+    }
+}
+
+/// Picks a more realistic data type for well known column names.
+pub(super) fn heuristic(name: &str, dt: DataType<'static>) -> DataType<'static> {
+    let ends_with = |suffixes: &[&str]| suffixes.iter().any(|suffix| name.ends_with(suffix));
+
+    match dt {
+        DataType::Regex { .. } => {
+            if name.contains("email") {
+                DataType::Email
+            } else if name.contains("phone") || name.contains("mobile") || name.contains("fax") {
+                DataType::PhoneNumber
+            } else if ends_with(&["uuid", "guid"]) {
+                DataType::Uuid
+            } else if ends_with(&["_at", "_on", "_date", "_time"])
+                || name == "created"
+                || name == "updated"
+                || name == "timestamp"
+            {
+                timestamp("%F %T")
+            } else if ends_with(&["url", "website", "homepage"]) {
+                regex("https://www\\.[a-z]{5,10}\\.(com|org|net)/[a-z]{3,8}")
+            } else if name == "ip" || ends_with(&["_ip", "ip_address"]) {
+                regex("((25[0-5]|(2[0-4]|1[0-9]|[1-9]|)[0-9])\\.){3}(25[0-5]|(2[0-4]|1[0-9]|[1-9]|)[0-9])")
+            } else if ends_with(&["zip", "zip_code", "postal_code", "postcode"]) {
+                regex("[0-9]{5}")
+            } else if ends_with(&["currency", "currency_code"]) {
+                DataType::OneOf {
+                    options: vec!["USD".into(), "EUR".into(), "GBP".into(), "JPY".into()],
+                    weights: None,
+                    selection: None,
+                }
+            } else if name == "username" || name == "login" {
+                regex("[a-z]{3,8}[0-9]{0,3}")
+            } else if name == "name" || name == "full_name" {
+                regex("[A-Z][a-z]{2,9} [A-Z][a-z]{2,11}")
+            } else if ends_with(&["_name"]) {
+                regex("[A-Z][a-z]{2,11}")
+            } else {
+                dt
+            }
+        }
+        DataType::SmallInt | DataType::Integer | DataType::BigInt => match name {
+            "age" => DataType::Range {
+                from: 18,
+                to: 90,
+                selection: None,
+            },
+            "year" => DataType::Range {
+                from: 1970,
+                to: 2031,
+                selection: None,
+            },
+            "quantity" | "qty" => DataType::Range {
+                from: 1,
+                to: 100,
+                selection: None,
+            },
+            _ => dt,
+        },
+        dt => dt,
+    }
+}
+
+/// Whether an expression refers to the given column.
+fn references(expr: &Expr, column: &str) -> bool {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => is_column(expr, column),
+        Expr::Nested(expr) | Expr::UnaryOp { expr, .. } => references(expr, column),
+        Expr::BinaryOp { left, right, .. } => references(left, column) || references(right, column),
+        Expr::Between { expr, .. } | Expr::InList { expr, .. } => references(expr, column),
+        _ => false,
+    }
+}
+
+fn is_column(expr: &Expr, column: &str) -> bool {
+    match expr {
+        Expr::Identifier(ident) => ident.value == column,
+        Expr::CompoundIdentifier(idents) => idents.last().is_some_and(|i| i.value == column),
+        _ => false,
+    }
+}
+
+fn literal(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Value(value) => Some(value.clone()),
+        Expr::Nested(expr) | Expr::Cast { expr, .. } => literal(expr),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match literal(expr)? {
+            Value::Number(n, long) => Some(Value::Number(format!("-{}", n), long)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn number(expr: &Expr) -> Option<f64> {
+    match literal(expr)? {
+        Value::Number(n, _) => n.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Mirrors a comparison so that the column is on the left hand side.
+fn flip(op: &BinaryOperator) -> BinaryOperator {
+    match op {
+        BinaryOperator::Gt => BinaryOperator::Lt,
+        BinaryOperator::GtEq => BinaryOperator::LtEq,
+        BinaryOperator::Lt => BinaryOperator::Gt,
+        BinaryOperator::LtEq => BinaryOperator::GtEq,
+        op => op.clone(),
+    }
+}
+
+/// The unqualified name of a table or type.
+fn object_name(name: &ObjectName) -> String {
+    name.0
+        .last()
+        .map(|ident: &Ident| ident.value.clone())
+        .unwrap_or_default()
+}
+
+fn regex(pattern: &str) -> DataType<'static> {
+    DataType::Regex {
+        pattern: pattern.to_owned().try_into().unwrap(),
+    }
+}
+
+fn string(length: &Option<CharacterLength>) -> DataType<'static> {
+    let max = match length {
+        Some(CharacterLength::IntegerLength { length, .. }) => (*length).min(15),
+        _ => 15,
+    };
+
+    regex(&format!("[a-zA-Z]{{{},{}}}", max.min(5), max))
+}
+
+fn timestamp(format: &str) -> DataType<'static> {
+    DataType::Timestamp {
+        format: Some(format.to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import, import_table, Error};
+    use serde_json::json;
+
+    #[test]
+    fn test_import() {
+        let import = import(
+            r#"
+            CREATE TYPE status AS ENUM ('active', 'suspended');
+
+            CREATE TABLE users (
+                id BIGSERIAL PRIMARY KEY,
+                seq INT GENERATED ALWAYS AS IDENTITY,
+                email VARCHAR(255) NOT NULL,
+                phone TEXT,
+                code CHAR(2) NOT NULL,
+                age INTEGER NOT NULL CHECK (age >= 18 AND age < 65),
+                balance NUMERIC(10, 2) NOT NULL,
+                price NUMERIC(5, 2) NOT NULL CHECK (price > 0),
+                score REAL NOT NULL,
+                status status NOT NULL DEFAULT 'active',
+                tier TEXT NOT NULL CHECK (tier IN ('free', 'pro')),
+                retries SMALLINT NOT NULL DEFAULT 3,
+                tags TEXT[] NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                CONSTRAINT positive CHECK (score > 0)
+            );
+
+            ALTER TABLE users ADD COLUMN token UUID NOT NULL DEFAULT gen_random_uuid();
+            ALTER TABLE users DROP COLUMN code;
+        "#,
+        )
+        .unwrap();
+
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        let expected = json!({
+            "id": { "type": "serial" },
+            "seq": { "type": "serial" },
+            "email": { "type": "email" },
+            "phone": { "type": "nullable", "element": { "type": "phone_number" }, "null_rate": 0.1 },
+            "age": { "type": "range", "from": 18, "to": 65 },
+            "balance": { "type": "decimal", "precision": 10, "scale": 2 },
+            "price": { "type": "decimal", "precision": 5, "scale": 2, "min": 0.01 },
+            "score": { "type": "float", "from": 0.0, "to": 1000000.0 },
+            "status": {
+                "type": "union",
+                "variants": [
+                    { "type": "literal", "value": "active" },
+                    { "type": "one_of", "options": ["active", "suspended"] }
+                ]
+            },
+            "tier": { "type": "one_of", "options": ["free", "pro"] },
+            "retries": {
+                "type": "union",
+                "variants": [{ "type": "range", "from": 3, "to": 4 }, { "type": "small_int" }]
+            },
+            "tags": {
+                "type": "array",
+                "element": { "type": "regex", "pattern": "[a-zA-Z]{5,15}" },
+                "size": 5,
+                "min_size": 0
+            },
+            "created_at": { "type": "timestamp", "format": "%F %T%:z" },
+            "token": { "type": "uuid" }
+        });
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_select_table() {
+        let source = "CREATE TABLE a (x INT NOT NULL); CREATE TABLE b (y BOOLEAN NOT NULL);";
+
+        assert!(matches!(
+            import(source),
+            Err(Error::AmbiguousTable { tables }) if tables == ["a", "b"]
+        ));
+        assert!(matches!(
+            import_table(source, Some("c")),
+            Err(Error::UnknownTable { .. })
+        ));
+
+        let import = import_table(source, Some("b")).unwrap();
+        let actual = serde_json::to_value(&import.schema).unwrap();
+        assert_eq!(json!({ "y": { "type": "boolean" } }), actual);
+    }
+
+    #[test]
+    fn test_closed_and_unsatisfiable_checks() {
+        let closed = import(
+            "CREATE TABLE t (
+                ratio REAL NOT NULL CHECK (ratio BETWEEN 1 AND 1),
+                small SMALLINT NOT NULL CHECK (small BETWEEN 0 AND 100000),
+                price NUMERIC(6, 2) NOT NULL CHECK (price > 0 AND price < 10)
+            )",
+        )
+        .unwrap();
+
+        let actual = serde_json::to_value(&closed.schema).unwrap();
+        let expected = json!({
+            "ratio": { "type": "float", "from": 1.0, "to": 1.0_f64.next_up() },
+            "small": { "type": "range", "from": 0, "to": 32768 },
+            "price": { "type": "decimal", "precision": 6, "scale": 2, "min": 0.01, "max": 9.99 }
+        });
+        assert_eq!(expected, actual);
+
+        for column in [
+            "x SMALLINT CHECK (x < -40000)",
+            "x REAL CHECK (x > 1 AND x < 1)",
+            "x NUMERIC(3, 0) CHECK (x > 5000)",
+            "x NUMERIC(4, 2) CHECK (x > 0.001 AND x < 0.01)",
+        ] {
+            let source = format!("CREATE TABLE t ({})", column);
+            assert!(
+                matches!(import(&source), Err(Error::Unsatisfiable { .. })),
+                "{}",
+                column
+            );
+        }
+    }
+
+    #[test]
+    fn test_warnings() {
+        let import = import(
+            "CREATE TABLE t (
+                shape POLYGON NOT NULL,
+                name TEXT NOT NULL CHECK (length(name) > 3)
+            )",
+        )
+        .unwrap();
+
+        let warnings: Vec<_> = import.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            vec![
+                "t.shape: unsupported type `POLYGON` was dropped",
+                "t.name: check constraint `length(name) > 3` was ignored",
+            ],
+            warnings
+        );
+    }
+}
@@ -0,0 +1,732 @@
+//! Infers a [Schema] from sample data, so that schemas
+//! for wide records do not have to be written by hand.
+
+use crate::data_type::DataType;
+use crate::schema::Schema;
+use chrono::format::{parse, Parsed, StrftimeItems};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use thiserror::Error;
+
+/// The default maximum number of distinct values of a
+/// string field for it to be treated as categorical.
+const MAX_CATEGORIES: usize = 20;
+
+/// The maximum number of tokens in the shape of a string,
+/// beyond which strings are treated as free text.
+const MAX_SHAPE_TOKENS: usize = 16;
+
+/// The maximum number of distinct punctuation characters
+/// included in the character class of free text.
+const MAX_PUNCTUATION: usize = 16;
+
+/// Timestamp formats recognized in string values, tried in order.
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%dT%H:%M:%S%.fZ",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %I:%M:%S %p",
+    "%Y-%m-%d",
+    "%H:%M:%S%.f",
+    "%m/%d/%Y",
+    "%d/%m/%Y",
+    "%a, %d %b %Y %H:%M:%S %z",
+];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("sample {index} is not a JSON object")]
+    NotAnObject { index: usize },
+
+    #[error("no samples to infer a schema from")]
+    NoSamples,
+}
+
+/// Infers a [Schema] from sample records.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::infer::infer;
+/// use serde_json::json;
+///
+/// let samples = vec![
+///     json!({ "id": 1, "status": "active" }),
+///     json!({ "id": 7, "status": "active" }),
+///     json!({ "id": 3, "status": "inactive" }),
+/// ];
+///
+/// let schema = infer(&samples).unwrap();
+/// assert_eq!(2, schema.iter().count());
+/// ```
+pub fn infer<'v>(samples: impl IntoIterator<Item = &'v Value>) -> Result<Schema<'static>, Error> {
+    let mut inferrer = Inferrer::default();
+    for sample in samples {
+        inferrer.add(sample)?;
+    }
+    inferrer.infer()
+}
+
+/// Accumulates statistics about sample records one at a time,
+/// and infers the [Schema] which best describes them.
+///
+/// * Numbers become a `range` or `float` spanning the samples.
+/// * Strings with few distinct values become a weighted `one_of`.
+/// * Timestamps, emails, UUIDs and phone numbers are recognized.
+/// * Other strings become a `regex` matching their shape, for
+///   example `[A-Z]{3}-[0-9]{4}` for identifiers like `ORD-0042`.
+/// * Nested objects and arrays are inferred recursively.
+/// * Null values and missing fields become `nullable`
+///   and `optional` with the observed rates.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::infer::Inferrer;
+/// use serde_json::json;
+///
+/// let mut inferrer = Inferrer::default().max_categories(5);
+/// inferrer.add(&json!({ "email": "alice@example.com" })).unwrap();
+/// inferrer.add(&json!({ "email": "bob@example.org" })).unwrap();
+///
+/// let schema = inferrer.infer().unwrap();
+/// assert_eq!(
+///     r#"{"email":{"type":"email"}}"#,
+///     serde_json::to_string(&schema).unwrap()
+/// );
+/// ```
+pub struct Inferrer {
+    root: Objects,
+    max_categories: usize,
+}
+
+impl Default for Inferrer {
+    fn default() -> Self {
+        Inferrer {
+            root: Objects::default(),
+            max_categories: MAX_CATEGORIES,
+        }
+    }
+}
+
+impl Inferrer {
+    /// Sets the maximum number of distinct values of a string
+    /// field for it to be inferred as a `one_of`.
+    pub fn max_categories(mut self, max_categories: usize) -> Self {
+        self.max_categories = max_categories;
+        self
+    }
+
+    /// Adds a sample record, which must be a JSON object.
+    pub fn add(&mut self, sample: &Value) -> Result<(), Error> {
+        match sample {
+            Value::Object(fields) => {
+                self.root.add(fields, self.max_categories);
+                Ok(())
+            }
+            _ => Err(Error::NotAnObject {
+                index: self.root.count as usize,
+            }),
+        }
+    }
+
+    /// Infers a [Schema] describing every sample added so far.
+    pub fn infer(&self) -> Result<Schema<'static>, Error> {
+        if self.root.count == 0 {
+            return Err(Error::NoSamples);
+        }
+
+        let mut schema = Schema::default();
+        for (name, dt) in self.root.fields(self.max_categories) {
+            schema.with_field(name, dt);
+        }
+        Ok(schema)
+    }
+}
+
+/// Statistics about every value observed at one location.
+#[derive(Default)]
+struct Stats {
+    /// The number of values, including nulls.
+    count: u64,
+    nulls: u64,
+    booleans: u64,
+    integers: Option<(i64, i64)>,
+    floats: Option<(f64, f64)>,
+    strings: Option<Strings>,
+    arrays: Option<Arrays>,
+    objects: Option<Objects>,
+}
+
+impl Stats {
+    fn add(&mut self, value: &Value, max_categories: usize) {
+        self.count += 1;
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => self.integers = Some(widen(self.integers, n)),
+                None => {
+                    let n = n.as_f64().unwrap_or_default();
+                    self.floats = Some(widen(self.floats, n))
+                }
+            },
+            Value::String(s) => self
+                .strings
+                .get_or_insert_with(Strings::default)
+                .add(s, max_categories),
+            Value::Array(elements) => self
+                .arrays
+                .get_or_insert_with(Arrays::default)
+                .add(elements, max_categories),
+            Value::Object(fields) => self
+                .objects
+                .get_or_insert_with(Objects::default)
+                .add(fields, max_categories),
+        }
+    }
+
+    fn data_type(&self, max_categories: usize) -> DataType<'static> {
+        let mut variants = Vec::new();
+        if self.booleans > 0 {
+            variants.push(DataType::Boolean);
+        }
+        match (self.integers, self.floats) {
+            (Some((min, max)), None) => variants.push(DataType::Range {
+                from: min,
+                to: max.saturating_add(1),
+                selection: None,
+            }),
+            (integers, Some(floats)) => {
+                let (from, to) = match integers {
+                    Some((min, max)) => widen(Some(widen(Some(floats), min as f64)), max as f64),
+                    None => floats,
+                };
+                let to = if from < to { to } else { from + 1.0 };
+                variants.push(DataType::Float { from, to });
+            }
+            (None, None) => {}
+        }
+        if let Some(strings) = &self.strings {
+            variants.push(strings.data_type(max_categories));
+        }
+        if let Some(arrays) = &self.arrays {
+            variants.push(arrays.data_type(max_categories));
+        }
+        if let Some(objects) = &self.objects {
+            variants.push(DataType::Object {
+                fields: objects.fields(max_categories).into_iter().collect(),
+            });
+        }
+
+        let dt = match variants.len() {
+            0 => {
+                return DataType::Nullable {
+                    element: Box::new(DataType::Boolean),
+                    null_rate: 1.0,
+                }
+            }
+            1 => variants.pop().unwrap(),
+            _ => DataType::Union { variants },
+        };
+
+        if self.nulls > 0 {
+            DataType::Nullable {
+                element: Box::new(dt),
+                null_rate: rate(self.nulls, self.count),
+            }
+        } else {
+            dt
+        }
+    }
+}
+
+/// Statistics about the fields of object values.
+#[derive(Default)]
+struct Objects {
+    count: u64,
+    fields: BTreeMap<String, Stats>,
+}
+
+impl Objects {
+    fn add(&mut self, fields: &Map<String, Value>, max_categories: usize) {
+        self.count += 1;
+        for (name, value) in fields {
+            self.fields
+                .entry(name.clone())
+                .or_default()
+                .add(value, max_categories);
+        }
+    }
+
+    fn fields(&self, max_categories: usize) -> Vec<(Cow<'static, str>, DataType<'static>)> {
+        self.fields
+            .iter()
+            .map(|(name, stats)| {
+                let dt = stats.data_type(max_categories);
+                let dt = if stats.count < self.count {
+                    DataType::Optional {
+                        element: Box::new(dt),
+                        missing_rate: rate(self.count - stats.count, self.count),
+                    }
+                } else {
+                    dt
+                };
+                (Cow::Owned(name.clone()), dt)
+            })
+            .collect()
+    }
+}
+
+/// Statistics about the lengths and elements of array values.
+#[derive(Default)]
+struct Arrays {
+    lengths: Option<(usize, usize)>,
+    elements: Box<Stats>,
+}
+
+impl Arrays {
+    fn add(&mut self, elements: &[Value], max_categories: usize) {
+        self.lengths = Some(widen(self.lengths, elements.len()));
+        for element in elements {
+            self.elements.add(element, max_categories);
+        }
+    }
+
+    fn data_type(&self, max_categories: usize) -> DataType<'static> {
+        let (min, max) = self.lengths.unwrap_or_default();
+        DataType::Array {
+            element: Box::new(self.elements.data_type(max_categories)),
+            size: max as u32,
+            min_size: if min == 1 { None } else { Some(min as u32) },
+        }
+    }
+}
+
+/// Statistics about string values, each of which is narrowed
+/// as more samples disagree with the previous ones.
+struct Strings {
+    count: u64,
+    /// The number of occurrences of each distinct value, until
+    /// there are too many for the strings to be categorical.
+    categories: Option<HashMap<String, u64>>,
+    /// The timestamp formats matched by every value.
+    timestamps: Vec<&'static str>,
+    emails: bool,
+    uuids: bool,
+    phone_numbers: bool,
+    /// The shape shared by every value, if any.
+    shape: Option<Vec<Token>>,
+    lengths: Option<(usize, usize)>,
+    classes: Classes,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Strings {
+            count: 0,
+            categories: Some(HashMap::new()),
+            timestamps: TIMESTAMP_FORMATS.to_vec(),
+            emails: true,
+            uuids: true,
+            phone_numbers: true,
+            shape: None,
+            lengths: None,
+            classes: Classes::default(),
+        }
+    }
+}
+
+impl Strings {
+    fn add(&mut self, s: &str, max_categories: usize) {
+        if let Some(categories) = self.categories.as_mut() {
+            *categories.entry(s.to_owned()).or_default() += 1;
+            if categories.len() > max_categories {
+                self.categories = None;
+            }
+        }
+
+        self.timestamps.retain(|format| is_timestamp(s, format));
+        self.emails &= is_email(s);
+        self.uuids &= is_uuid(s);
+        self.phone_numbers &= is_phone_number(s);
+
+        let shape = tokenize(s);
+        self.shape = match (self.count, self.shape.take()) {
+            (0, _) => shape,
+            (_, Some(previous)) => shape.and_then(|shape| merge(previous, shape)),
+            (_, None) => None,
+        };
+
+        self.count += 1;
+        self.lengths = Some(widen(self.lengths, s.chars().count()));
+        self.classes.add(s);
+    }
+
+    fn data_type(&self, max_categories: usize) -> DataType<'static> {
+        if let Some(format) = self.timestamps.first() {
+            return DataType::Timestamp {
+                format: Some(format.to_string()),
+            };
+        }
+        if self.uuids {
+            return DataType::Uuid;
+        }
+        if self.emails {
+            return DataType::Email;
+        }
+        if self.phone_numbers {
+            return DataType::PhoneNumber;
+        }
+
+        if let Some(categories) = &self.categories {
+            let distinct = categories.len();
+            if distinct <= max_categories && self.count >= 2 * distinct as u64 {
+                let mut categories: Vec<_> = categories.iter().collect();
+                categories.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+
+                return DataType::OneOf {
+                    options: categories
+                        .iter()
+                        .map(|(option, _)| Cow::Owned(option.to_string()))
+                        .collect(),
+                    weights: Some(
+                        categories
+                            .iter()
+                            .map(|(_, count)| rate(**count, self.count))
+                            .collect(),
+                    ),
+                    selection: None,
+                };
+            }
+        }
+
+        let pattern = match &self.shape {
+            Some(shape) => shape.iter().map(Token::pattern).collect(),
+            None => {
+                let (min, max) = self.lengths.unwrap_or_default();
+                format!("[{}]{}", self.classes.pattern(), repetition(min, max))
+            }
+        };
+
+        DataType::Regex {
+            pattern: pattern.try_into().unwrap(),
+        }
+    }
+}
+
+/// A run of similar characters within a string.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Digits {
+        lengths: (usize, usize),
+    },
+    Letters {
+        lengths: (usize, usize),
+        classes: Classes,
+    },
+    Literal(char),
+}
+
+impl Token {
+    fn pattern(&self) -> String {
+        match self {
+            Token::Digits {
+                lengths: (min, max),
+            } => format!("[0-9]{}", repetition(*min, *max)),
+            Token::Letters {
+                lengths: (min, max),
+                classes,
+            } => format!("[{}]{}", classes.pattern(), repetition(*min, *max)),
+            Token::Literal(c) => regex_syntax::escape(&c.to_string()),
+        }
+    }
+}
+
+/// Splits a string into runs of digits, runs of letters and
+/// individual punctuation characters. Returns nothing for
+/// strings with too many runs to have a meaningful shape.
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for c in s.chars() {
+        match (tokens.last_mut(), c) {
+            (Some(Token::Digits { lengths }), '0'..='9') => {
+                *lengths = (lengths.0 + 1, lengths.1 + 1)
+            }
+            (_, '0'..='9') => tokens.push(Token::Digits { lengths: (1, 1) }),
+            (Some(Token::Letters { lengths, classes }), c) if c.is_alphabetic() => {
+                *lengths = (lengths.0 + 1, lengths.1 + 1);
+                classes.add_char(c);
+            }
+            (_, c) if c.is_alphabetic() => {
+                let mut classes = Classes::default();
+                classes.add_char(c);
+                tokens.push(Token::Letters {
+                    lengths: (1, 1),
+                    classes,
+                })
+            }
+            (_, c) => tokens.push(Token::Literal(c)),
+        }
+
+        if tokens.len() > MAX_SHAPE_TOKENS {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+/// Merges two shapes into one which matches both, if possible.
+fn merge(a: Vec<Token>, b: Vec<Token>) -> Option<Vec<Token>> {
+    if a.len() != b.len() {
+        return None;
+    }
+
+    a.into_iter()
+        .zip(b)
+        .map(|pair| match pair {
+            (Token::Digits { lengths: a }, Token::Digits { lengths: b }) => Some(Token::Digits {
+                lengths: (a.0.min(b.0), a.1.max(b.1)),
+            }),
+            (
+                Token::Letters {
+                    lengths: a,
+                    classes: x,
+                },
+                Token::Letters {
+                    lengths: b,
+                    classes: y,
+                },
+            ) => Some(Token::Letters {
+                lengths: (a.0.min(b.0), a.1.max(b.1)),
+                classes: x.union(&y),
+            }),
+            (Token::Literal(a), Token::Literal(b)) if a == b => Some(Token::Literal(a)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The classes of characters observed within strings.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Classes {
+    lower: bool,
+    upper: bool,
+    digits: bool,
+    punctuation: BTreeSet<char>,
+}
+
+impl Classes {
+    fn add(&mut self, s: &str) {
+        s.chars().for_each(|c| self.add_char(c));
+    }
+
+    fn add_char(&mut self, c: char) {
+        match c {
+            '0'..='9' => self.digits = true,
+            c if c.is_uppercase() => self.upper = true,
+            c if c.is_alphabetic() => self.lower = true,
+            c if self.punctuation.len() < MAX_PUNCTUATION && !c.is_control() => {
+                self.punctuation.insert(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn union(&self, other: &Classes) -> Classes {
+        Classes {
+            lower: self.lower || other.lower,
+            upper: self.upper || other.upper,
+            digits: self.digits || other.digits,
+            punctuation: self
+                .punctuation
+                .union(&other.punctuation)
+                .copied()
+                .collect(),
+        }
+    }
+
+    fn pattern(&self) -> String {
+        let mut pattern = String::new();
+        if self.lower || !(self.upper || self.digits || !self.punctuation.is_empty()) {
+            pattern.push_str("a-z");
+        }
+        if self.upper {
+            pattern.push_str("A-Z");
+        }
+        if self.digits {
+            pattern.push_str("0-9");
+        }
+        for c in &self.punctuation {
+            match c {
+                ' ' => pattern.push(' '),
+                c => pattern.push_str(&regex_syntax::escape(&c.to_string())),
+            }
+        }
+        pattern
+    }
+}
+
+fn repetition(min: usize, max: usize) -> String {
+    if min == max {
+        format!("{{{}}}", min)
+    } else {
+        format!("{{{},{}}}", min, max)
+    }
+}
+
+fn is_timestamp(s: &str, format: &str) -> bool {
+    parse(&mut Parsed::new(), s, StrftimeItems::new(format)).is_ok()
+}
+
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !s.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_phone_number(s: &str) -> bool {
+    s.len() == 12
+        && s.char_indices().all(|(i, c)| match i {
+            3 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+fn widen<T: PartialOrd + Copy>(bounds: Option<(T, T)>, value: T) -> (T, T) {
+    match bounds {
+        Some((min, max)) => (
+            if value < min { value } else { min },
+            if value > max { value } else { max },
+        ),
+        None => (value, value),
+    }
+}
+
+/// The fraction of `n` out of `total`, rounded
+/// to a readable number of decimal places.
+fn rate(n: u64, total: u64) -> f64 {
+    (n as f64 / total as f64 * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer, Error, Inferrer};
+    use crate::schema::Schema;
+    use serde_json::json;
+
+    #[test]
+    fn test_infer() {
+        let samples = vec![
+            json!({
+                "id": "ORD-0001", "quantity": 3, "price": 9.5, "status": "paid",
+                "created": "2024-01-05T10:00:00Z", "phone": "555-123-4567",
+                "customer": { "email": "alice@example.com", "vip": true },
+                "tags": ["a", "b"], "note": null
+            }),
+            json!({
+                "id": "ORD-0042", "quantity": 1, "price": 20, "status": "paid",
+                "created": "2024-02-11T08:30:00.125Z", "phone": "555-987-6543",
+                "customer": { "email": "bob@example.org", "vip": false },
+                "tags": [], "note": "leave at door"
+            }),
+            json!({
+                "id": "ORD-1337", "quantity": 12, "price": 4.25, "status": "refunded",
+                "created": "2024-03-30T23:59:59Z", "phone": "555-000-1111",
+                "customer": { "email": "carol@example.net", "vip": false },
+                "tags": ["c"], "note": null, "coupon": "SPRING"
+            }),
+            json!({
+                "id": "ORD-2048", "quantity": 7, "price": 15.0, "status": "paid",
+                "created": "2024-04-01T12:00:00Z", "phone": "555-222-3333",
+                "customer": { "email": "dave@example.com", "vip": true },
+                "tags": ["d", "e", "f"], "note": null
+            }),
+        ];
+
+        let schema = infer(&samples).unwrap();
+        let actual = serde_json::to_value(&schema).unwrap();
+        let expected = json!({
+            "id": { "type": "regex", "pattern": "[A-Z]{3}\\-[0-9]{4}" },
+            "quantity": { "type": "range", "from": 1, "to": 13 },
+            "price": { "type": "float", "from": 4.25, "to": 20.0 },
+            "status": { "type": "one_of", "options": ["paid", "refunded"], "weights": [0.75, 0.25] },
+            "created": { "type": "timestamp", "format": "%Y-%m-%dT%H:%M:%S%.fZ" },
+            "phone": { "type": "phone_number" },
+            "customer": {
+                "type": "object",
+                "fields": {
+                    "email": { "type": "email" },
+                    "vip": { "type": "boolean" }
+                }
+            },
+            "tags": {
+                "type": "array",
+                "element": { "type": "regex", "pattern": "[a-z]{1}" },
+                "size": 3,
+                "min_size": 0
+            },
+            "note": {
+                "type": "nullable",
+                "element": { "type": "regex", "pattern": "[a-z]{5} [a-z]{2} [a-z]{4}" },
+                "null_rate": 0.75
+            },
+            "coupon": {
+                "type": "optional",
+                "element": { "type": "regex", "pattern": "[A-Z]{6}" },
+                "missing_rate": 0.75
+            }
+        });
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let samples = vec![
+            json!({ "a": 1, "b": "x-1", "c": [{ "d": null }] }),
+            json!({ "a": 2.5, "b": true, "c": [] }),
+        ];
+
+        let schema = infer(&samples).unwrap();
+        let serialized = serde_json::to_string(&schema).unwrap();
+        let deserialized: Schema = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&schema).unwrap(),
+            serde_json::to_value(&deserialized).unwrap()
+        );
+        assert!(crate::validate::validate(&serialized).is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(infer(&[]), Err(Error::NoSamples)));
+
+        let mut inferrer = Inferrer::default();
+        inferrer.add(&json!({})).unwrap();
+        assert!(matches!(
+            inferrer.add(&json!([1, 2])),
+            Err(Error::NotAnObject { index: 1 })
+        ));
+    }
+}
@@ -14,7 +14,6 @@ fn load() -> Result<HashMap<String, Vec<&'static str>>, Error> {
     let dataset = PROJECT_DIR
         .find("*.json")
         .unwrap()
-        .into_iter()
         .filter_map(|entry| match entry {
             DirEntry::Dir(_) => None,
             DirEntry::File(f) => Some(f),
@@ -11,7 +11,11 @@ use crate::interpolator::dataset::FULL_DATA_SET;
 use nom::sequence::delimited;
 use nom::IResult;
 
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use thiserror::Error;
 
@@ -131,6 +135,33 @@ impl Interpolator {
     }
 }
 
+impl JsonSchema for Interpolator {
+    fn schema_name() -> String {
+        "Interpolator".to_owned()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let mut tags: Vec<_> = FULL_DATA_SET.keys().cloned().collect();
+        tags.sort();
+
+        let description = format!(
+            "A format string which may contain the interpolation tags: {}",
+            tags.join(", ")
+        );
+
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some(description),
+                examples: tags.into_iter().map(Value::String).collect(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl Distribution<String> for Interpolator {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
         let result = self.interpolate(rng);
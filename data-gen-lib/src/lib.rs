@@ -1,9 +1,17 @@
+#[cfg(feature = "arrow")]
+pub mod columnar;
 pub mod data_type;
+pub mod dataset;
 pub mod generator;
+pub mod import;
+pub mod infer;
 pub mod interpolator;
 mod physical;
+pub mod pool;
 pub mod regex_pattern;
 pub mod schema;
+pub mod selection;
+pub mod validate;
 
 #[cfg(test)]
 mod tests {
@@ -14,7 +22,8 @@ mod tests {
     use crate::schema::Schema;
     use rand::prelude::*;
     use rand::rngs::mock::StepRng;
-    use serde_json::json;
+    use serde_json::{json, Value};
+    use std::collections::HashSet;
 
     #[test]
     fn it_generates() {
@@ -23,8 +32,9 @@ mod tests {
             .with_field(
                 "array",
                 Array {
-                    element: Box::new(Literal { value: "a" }),
+                    element: Box::new(Literal { value: "a".into() }),
                     size: 2,
+                    min_size: None,
                 },
             )
             .with_field("boolean", Boolean)
@@ -36,8 +46,20 @@ mod tests {
                         .unwrap(),
                 },
             )
-            .with_field("literal", Literal { value: "hello" })
-            .with_field("range", Range { from: 1, to: 10 })
+            .with_field(
+                "literal",
+                Literal {
+                    value: "hello".into(),
+                },
+            )
+            .with_field(
+                "range",
+                Range {
+                    from: 1,
+                    to: 10,
+                    selection: None,
+                },
+            )
             .with_field(
                 "regex",
                 Regex {
@@ -47,7 +69,7 @@ mod tests {
             .with_field(
                 "object",
                 Object {
-                    fields: vec![("field", Literal { value: "b" })]
+                    fields: vec![("field".into(), Literal { value: "b".into() })]
                         .into_iter()
                         .collect(),
                 },
@@ -55,7 +77,9 @@ mod tests {
             .with_field(
                 "one_of",
                 OneOf {
-                    options: vec!["coffee", "tea", "milk"],
+                    options: vec!["coffee".into(), "tea".into(), "milk".into()],
+                    weights: None,
+                    selection: None,
                 },
             );
 
@@ -79,4 +103,137 @@ mod tests {
 
         assert_eq!(expected, value)
     }
+
+    #[test]
+    fn it_generates_unique_values() {
+        let schema: Schema = serde_json::from_str(
+            r#"{ "id": { "type": "range", "from": 0, "to": 100, "unique": true } }"#,
+        )
+        .unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = thread_rng();
+
+        let ids: HashSet<_> = (0..100)
+            .map(|_| rng.sample(&gen)["id"].to_string())
+            .collect();
+        assert_eq!(100, ids.len());
+        assert!(gen.check().is_ok());
+
+        assert_eq!(Value::Null, rng.sample(&gen)["id"]);
+        assert_eq!(
+            "the values of field `id` are exhausted after 100 distinct values",
+            gen.check().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn it_draws_every_value_of_a_finite_domain() {
+        let domains = [
+            (
+                r#"{ "type": "range", "from": 0, "to": 20000, "unique": true }"#,
+                20000,
+            ),
+            (r#"{ "type": "boolean", "unique": true }"#, 2),
+            (
+                r#"{ "type": "one_of", "options": ["a", "b", "c"], "unique": true }"#,
+                3,
+            ),
+            (
+                r#"{ "type": "decimal", "precision": 3, "scale": 1, "unique": true }"#,
+                1999,
+            ),
+            (
+                r#"{ "type": "regex", "pattern": "[a-z]{8}", "cardinality": 5000, "unique": true }"#,
+                5000,
+            ),
+        ];
+
+        for (field, size) in domains {
+            let source = format!(r#"{{ "x": {} }}"#, field);
+            let schema: Schema = serde_json::from_str(&source).unwrap();
+            let gen = DataGenerator::new(&schema);
+            let mut rng = thread_rng();
+
+            let values: HashSet<_> = (0..size)
+                .map(|_| rng.sample(&gen)["x"].to_string())
+                .collect();
+            assert_eq!(size, values.len(), "{}", field);
+            assert!(gen.check().is_ok(), "{}", field);
+
+            let _: Value = rng.sample(&gen);
+            assert!(gen.check().is_err(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn it_generates_values_of_a_cardinality() {
+        let schema: Schema = serde_json::from_str(
+            r#"{ "user": { "type": "regex", "pattern": "[a-z]{8}", "cardinality": 5 } }"#,
+        )
+        .unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = thread_rng();
+
+        let users: HashSet<_> = (0..1000)
+            .map(|_| rng.sample(&gen)["user"].to_string())
+            .collect();
+        assert_eq!(5, users.len());
+        assert!(gen.check().is_ok());
+    }
+
+    #[test]
+    fn it_validates_schemas_against_the_spec() {
+        let spec = serde_json::to_value(Schema::spec()).unwrap();
+        let validator = jsonschema::validator_for(&spec).unwrap();
+
+        let example: Value = serde_json::from_str(include_str!("../../schema.json")).unwrap();
+        assert!(validator.is_valid(&example));
+        let pooled = json!({
+            "$pools": { "users": { "count": 10, "schema": { "id": { "type": "serial" } } } },
+            "id": { "type": "range", "from": 0, "to": 100, "unique": true },
+            "user": { "type": "member", "pool": "users", "field": "id" },
+            "store": { "type": "regex", "pattern": "S[0-9]{2}", "cardinality": 5 }
+        });
+        assert!(validator.is_valid(&pooled));
+
+        assert!(!validator.is_valid(&json!({ "x": { "type": "strng" } })));
+        assert!(!validator.is_valid(&json!({ "x": { "type": "range", "from": "a", "to": 1 } })));
+        assert!(!validator.is_valid(&json!({ "x": { "type": "uuid", "cardinality": 0 } })));
+    }
+
+    #[test]
+    fn it_exhausts_a_cardinality_larger_than_the_values() {
+        let schema: Schema =
+            serde_json::from_str(r#"{ "flag": { "type": "boolean", "cardinality": 3 } }"#).unwrap();
+        let gen = DataGenerator::new(&schema);
+
+        let _: Value = thread_rng().sample(&gen);
+        assert!(gen.check().is_err());
+    }
+
+    #[test]
+    fn it_generates_exact_decimals() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "small": { "type": "decimal", "precision": 5, "scale": 2 },
+                "large": { "type": "decimal", "precision": 30, "scale": 10 }
+            }"#,
+        )
+        .unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = thread_rng();
+
+        for _ in 0..100 {
+            let value: Value = rng.sample(&gen);
+            let small = value["small"].as_f64().unwrap();
+            assert!(small.abs() < 1000.0);
+            assert_eq!(small, (small * 100.0).round() / 100.0);
+
+            // every digit of large decimals is kept in a string
+            let large = value["large"].as_str().unwrap();
+            let (integer, fraction) = large.trim_start_matches('-').split_once('.').unwrap();
+            assert!(integer.len() <= 20);
+            assert_eq!(10, fraction.len());
+        }
+    }
 }
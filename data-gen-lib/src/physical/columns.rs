@@ -0,0 +1,269 @@
+use std::iter::repeat_n;
+use std::sync::Arc;
+
+use arrow::array::{
+    make_array, new_null_array, Array, ArrayRef, BooleanArray, Date32Array, Decimal128Array,
+    Float64Array, Int64Array, ListArray, MapArray, StringArray, StructArray,
+    Time64MicrosecondArray, TimestampMicrosecondArray,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::compute::{can_cast_types, cast, interleave};
+use arrow::datatypes::DataType as ArrowType;
+use arrow::error::ArrowError;
+use rand::distributions::Distribution;
+use rand::prelude::*;
+use serde_json::Value;
+
+use crate::columnar::{temporal, Temporal};
+use crate::physical::physical_types::{timestamp, PhysicalDataType};
+
+impl PhysicalDataType<'_> {
+    /// Samples a column of `rows` values of the given Arrow type. Values
+    /// are generated directly into Arrow arrays instead of first being
+    /// sampled as [Value]s, except for proxied and mixed union types.
+    pub fn sample_column<R: Rng + ?Sized>(
+        &self,
+        tpe: &ArrowType,
+        rows: usize,
+        rng: &mut R,
+    ) -> Result<ArrayRef, ArrowError> {
+        let column = self.column(tpe, rows, rng)?;
+        if column.data_type() == tpe {
+            Ok(column)
+        } else {
+            without_empty_nulls(cast(&column, tpe)?)
+        }
+    }
+
+    /// Samples a column whose type is either the given
+    /// type, or a type which can be cast to it.
+    fn column<R: Rng + ?Sized>(
+        &self,
+        tpe: &ArrowType,
+        rows: usize,
+        rng: &mut R,
+    ) -> Result<ArrayRef, ArrowError> {
+        let column: ArrayRef = match (self, tpe) {
+            (
+                PhysicalDataType::Array {
+                    element,
+                    min_size,
+                    size,
+                },
+                ArrowType::List(item),
+            ) => {
+                let lengths: Vec<usize> = (0..rows)
+                    .map(|_| rng.gen_range(*min_size..=*size) as usize)
+                    .collect();
+                let values = element.sample_column(item.data_type(), lengths.iter().sum(), rng)?;
+
+                Arc::new(ListArray::try_new(
+                    item.clone(),
+                    OffsetBuffer::from_lengths(lengths),
+                    values,
+                    None,
+                )?)
+            }
+            (PhysicalDataType::Boolean, _) => Arc::new(BooleanArray::from_iter(
+                (0..rows).map(|_| Some(rng.gen::<bool>())),
+            )),
+            (PhysicalDataType::Decimal { r, .. }, ArrowType::Decimal128(precision, scale)) => {
+                let values = (0..rows).map(|_| rng.gen_range(r.clone()));
+
+                Arc::new(
+                    Decimal128Array::from_iter_values(values)
+                        .with_precision_and_scale(*precision, *scale)?,
+                )
+            }
+            (PhysicalDataType::Float { r }, _) => Arc::new(Float64Array::from_iter_values(
+                (0..rows).map(|_| rng.gen_range(r.clone())),
+            )),
+            (PhysicalDataType::Generator { format }, _) => Arc::new(StringArray::from_iter_values(
+                (0..rows).map(|_| format.sample(rng)),
+            )),
+            (PhysicalDataType::Map { keys, values, size }, ArrowType::Map(entries, sorted)) => {
+                let fields = match entries.data_type() {
+                    ArrowType::Struct(fields) if fields.len() == 2 => fields,
+                    other => {
+                        return Err(ArrowError::SchemaError(format!(
+                            "expected map entries to be a struct of a key and value, found {}",
+                            other
+                        )))
+                    }
+                };
+
+                let lengths: Vec<usize> = (0..rows)
+                    .map(|_| rng.gen_range(1..=*size) as usize)
+                    .collect();
+                let length = lengths.iter().sum();
+                let keys = keys.sample_column(fields[0].data_type(), length, rng)?;
+                let values = values.sample_column(fields[1].data_type(), length, rng)?;
+
+                Arc::new(MapArray::try_new(
+                    entries.clone(),
+                    OffsetBuffer::from_lengths(lengths),
+                    StructArray::try_new(fields.clone(), vec![keys, values], None)?,
+                    None,
+                    *sorted,
+                )?)
+            }
+            (PhysicalDataType::Nullable { element, null_rate }, _) => {
+                let column = element.sample_column(tpe, rows, rng)?;
+                with_nulls(
+                    column,
+                    (0..rows).map(|_| !rng.gen_bool(*null_rate)).collect(),
+                )?
+            }
+            (
+                PhysicalDataType::Optional {
+                    element,
+                    missing_rate,
+                },
+                _,
+            ) => {
+                let column = element.sample_column(tpe, rows, rng)?;
+                with_nulls(
+                    column,
+                    (0..rows).map(|_| !rng.gen_bool(*missing_rate)).collect(),
+                )?
+            }
+            (PhysicalDataType::OneOf { options, weights }, _) => {
+                Arc::new(StringArray::from_iter((0..rows).map(|_| match weights {
+                    Some(weights) => options.get(weights.sample(rng)).copied(),
+                    None => options.choose(rng).copied(),
+                })))
+            }
+            (PhysicalDataType::Range { r }, _) => Arc::new(Int64Array::from_iter_values(
+                (0..rows).map(|_| rng.gen_range(r.clone())),
+            )),
+            (PhysicalDataType::Regex { pattern }, _) => Arc::new(StringArray::from_iter_values(
+                (0..rows).map(|_| pattern.sample(rng)),
+            )),
+            (PhysicalDataType::Object { fields }, ArrowType::Struct(struct_fields)) => {
+                let columns = struct_fields
+                    .iter()
+                    .map(|field| match fields.get(field.name().as_str()) {
+                        Some(dt) => dt.sample_column(field.data_type(), rows, rng),
+                        None => Ok(new_null_array(field.data_type(), rows)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if struct_fields.is_empty() {
+                    Arc::new(StructArray::new_empty_fields(rows, None))
+                } else {
+                    Arc::new(StructArray::try_new(struct_fields.clone(), columns, None)?)
+                }
+            }
+            (PhysicalDataType::Timestamp { format }, _) => {
+                // Timestamps are the current time, so a
+                // single value is repeated for each row.
+                let now = match timestamp(format) {
+                    Value::String(now) => now,
+                    now => now.to_string(),
+                };
+
+                let kind = match tpe {
+                    ArrowType::Timestamp(..) => Temporal::Timestamp,
+                    ArrowType::Date32 => Temporal::Date,
+                    ArrowType::Time64(_) => Temporal::Time,
+                    _ => return Ok(Arc::new(StringArray::from_iter_values(repeat_n(now, rows)))),
+                };
+
+                match (temporal(&now, format, kind), tpe) {
+                    (None, _) => new_null_array(tpe, rows),
+                    (Some(now), ArrowType::Timestamp(_, tz)) => Arc::new(
+                        TimestampMicrosecondArray::from_value(now, rows)
+                            .with_timezone_opt(tz.clone()),
+                    ),
+                    (Some(now), ArrowType::Date32) => {
+                        Arc::new(Date32Array::from_value(now as i32, rows))
+                    }
+                    (Some(now), _) => Arc::new(Time64MicrosecondArray::from_value(now, rows)),
+                }
+            }
+            (PhysicalDataType::Union { variants }, _) if !variants.is_empty() => {
+                let choices: Vec<usize> = (0..rows)
+                    .map(|_| rng.gen_range(0..variants.len()))
+                    .collect();
+
+                let mut counts = vec![0; variants.len()];
+                let indices: Vec<_> = choices
+                    .iter()
+                    .map(|choice| {
+                        counts[*choice] += 1;
+                        (*choice, counts[*choice] - 1)
+                    })
+                    .collect();
+
+                let columns = variants
+                    .iter()
+                    .zip(counts)
+                    .map(|(variant, count)| variant.column(tpe, count, rng))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                // Variants of differing types, such as objects or arrays
+                // in a union written as text, are sampled as values.
+                if columns
+                    .iter()
+                    .all(|column| can_cast_types(column.data_type(), tpe))
+                {
+                    let columns = columns
+                        .iter()
+                        .map(|column| cast(column, tpe))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let columns: Vec<_> = columns.iter().map(|column| column.as_ref()).collect();
+                    interleave(&columns, &indices)?
+                } else {
+                    values_column(tpe, (0..rows).map(|_| self.sample(rng)))
+                }
+            }
+            _ => values_column(tpe, (0..rows).map(|_| self.sample(rng))),
+        };
+
+        Ok(column)
+    }
+}
+
+/// Sets the rows whose validity is false to null, in
+/// addition to any rows of the column which are null.
+fn with_nulls(column: ArrayRef, valid: Vec<bool>) -> Result<ArrayRef, ArrowError> {
+    let nulls = NullBuffer::union(column.nulls(), Some(&NullBuffer::from(valid)));
+    let data = column.into_data().into_builder().nulls(nulls).build()?;
+    Ok(make_array(data))
+}
+
+/// Removes the null buffer of a column without nulls, as casts may add
+/// one, which is not allowed for non-nullable fields of structs.
+fn without_empty_nulls(column: ArrayRef) -> Result<ArrayRef, ArrowError> {
+    match column.nulls() {
+        Some(nulls) if nulls.null_count() == 0 => {
+            let data = column.into_data().into_builder().nulls(None).build()?;
+            Ok(make_array(data))
+        }
+        _ => Ok(column),
+    }
+}
+
+/// Builds a column from sampled values, numbers and booleans are
+/// kept as such while any other values are written as text.
+fn values_column(tpe: &ArrowType, values: impl Iterator<Item = Value>) -> ArrayRef {
+    match tpe {
+        ArrowType::Boolean => Arc::new(BooleanArray::from_iter(values.map(|v| v.as_bool()))),
+        ArrowType::Int8
+        | ArrowType::Int16
+        | ArrowType::Int32
+        | ArrowType::Int64
+        | ArrowType::UInt8
+        | ArrowType::UInt16
+        | ArrowType::UInt32
+        | ArrowType::UInt64 => Arc::new(Int64Array::from_iter(values.map(|v| v.as_i64()))),
+        ArrowType::Float32 | ArrowType::Float64 => {
+            Arc::new(Float64Array::from_iter(values.map(|v| v.as_f64())))
+        }
+        _ => Arc::new(StringArray::from_iter(values.map(|v| match v {
+            Value::Null => None,
+            Value::String(s) => Some(s),
+            v => Some(v.to_string()),
+        }))),
+    }
+}
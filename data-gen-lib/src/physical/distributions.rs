@@ -1,6 +1,9 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 use rand::distributions::Distribution;
+use rand::seq::SliceRandom;
 use rand::{Rng, RngCore};
 use serde_json::Value;
 
@@ -45,27 +48,6 @@ impl Distribution<Value> for Static {
     }
 }
 
-/// A distribution that proxies to a
-/// function and does not rely on a [Rng]
-/// instance to compute its values.
-pub struct Supplier(Box<dyn Fn() -> Value>);
-
-impl Supplier {
-    pub fn new<T>(supplier: T) -> Self
-    where
-        T: Fn() -> Value,
-        T: 'static,
-    {
-        Supplier(Box::new(supplier))
-    }
-}
-
-impl Distribution<Value> for Supplier {
-    fn sample<R: Rng + ?Sized>(&self, _: &mut R) -> Value {
-        self.0()
-    }
-}
-
 /// A distribution that pulls elements
 /// from an underlying [Iterator]. When
 /// the iterator is exausted the distribution
@@ -73,9 +55,10 @@ impl Distribution<Value> for Supplier {
 pub struct Iter(RefCell<Box<dyn Iterator<Item = Value>>>);
 
 impl Iter {
-    pub fn new<T: 'static>(iterator: T) -> Iter
+    pub fn new<T>(iterator: T) -> Iter
     where
         T: IntoIterator<Item = Value>,
+        T: 'static,
     {
         let boxed = Box::new(iterator.into_iter());
         Iter(RefCell::new(boxed))
@@ -92,13 +75,71 @@ impl Distribution<Value> for Iter {
     }
 }
 
+/// A distribution that chooses uniformly from a shared
+/// list of values, or returns [Value::Null] if it is empty.
+pub struct Choose(Rc<RefCell<Vec<Value>>>);
+
+impl Choose {
+    pub fn new(values: Rc<RefCell<Vec<Value>>>) -> Choose {
+        Choose(values)
+    }
+}
+
+impl Distribution<Value> for Choose {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Value {
+        self.0.borrow().choose(rng).cloned().unwrap_or(Value::Null)
+    }
+}
+
+/// Draws the indexes of a fixed number of values in a random order,
+/// each exactly once, as a Fisher-Yates shuffle which only stores
+/// the indexes it moved, so drawing from a large range is cheap.
+pub struct Shuffle {
+    size: u64,
+    drawn: u64,
+    moved: HashMap<u64, u64>,
+}
+
+impl Shuffle {
+    pub fn new(size: u64) -> Self {
+        Shuffle {
+            size,
+            drawn: 0,
+            moved: HashMap::new(),
+        }
+    }
+
+    /// The number of indexes drawn so far.
+    pub fn drawn(&self) -> u64 {
+        self.drawn
+    }
+
+    /// Draws an index which was not drawn before,
+    /// or returns `None` once every index was drawn.
+    pub fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<u64> {
+        if self.drawn >= self.size {
+            return None;
+        }
+
+        let position = rng.gen_range(self.drawn..self.size);
+        let index = self.moved.remove(&position).unwrap_or(position);
+        if position != self.drawn {
+            let first = self.moved.remove(&self.drawn).unwrap_or(self.drawn);
+            self.moved.insert(position, first);
+        }
+        self.drawn += 1;
+        Some(index)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use rand::{thread_rng, Rng};
     use serde_json::{json, to_value, Value};
+    use std::collections::HashSet;
     use std::vec;
 
-    use super::{Iter, Static, Supplier};
+    use super::{Iter, Shuffle, Static};
 
     #[test]
     fn test_static_distribution() {
@@ -108,14 +149,6 @@ mod test {
         assert_eq!(vec!["hello", "hello"], results)
     }
 
-    #[test]
-    fn test_supplier_distribution() {
-        let distribution = Supplier(Box::new(|| Value::String("hello".to_string())));
-        let results: Vec<_> = thread_rng().sample_iter(distribution).take(2).collect();
-
-        assert_eq!(vec!["hello", "hello"], results)
-    }
-
     #[test]
     fn test_iterable_distribution() {
         let mut expected: Vec<_> = vec![1, 2, 3]
@@ -131,4 +164,15 @@ mod test {
 
         assert_eq!(expected, results)
     }
+
+    #[test]
+    fn test_shuffle_draws_each_index_once() {
+        let mut shuffle = Shuffle::new(1000);
+        let mut rng = thread_rng();
+        let drawn: HashSet<_> = (0..1000).map(|_| shuffle.draw(&mut rng).unwrap()).collect();
+
+        assert_eq!((0..1000).collect::<HashSet<_>>(), drawn);
+        assert_eq!(None, shuffle.draw(&mut rng));
+        assert_eq!(1000, shuffle.drawn());
+    }
 }
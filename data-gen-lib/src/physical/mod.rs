@@ -1,2 +1,4 @@
-mod distributions;
+#[cfg(feature = "arrow")]
+mod columns;
+pub(crate) mod distributions;
 pub mod physical_types;
@@ -1,16 +1,28 @@
 use crate::data_type::DataType;
 use crate::interpolator::Interpolator;
-use crate::physical::distributions::{DynDistribution, Static, Supplier};
+use crate::physical::distributions::{DynDistribution, Shuffle, Static};
+use crate::pool::Members;
 use crate::regex_pattern::RegexPattern;
+use crate::selection::{KeyFrequencies, Selector};
 use chrono::Local;
-use rand::distributions::Distribution;
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::prelude::*;
-use serde_json::{json, Number, Value};
-use std::collections::HashMap;
+use serde_json::{json, Map, Number, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
-use std::ops::{Deref, Range};
+use std::ops::{Deref, Range, RangeInclusive};
+use std::rc::Rc;
 
-use super::distributions::Iter;
+/// The number of attempts to sample a value which was not sampled
+/// before, after which the values are exhausted, for elements whose
+/// values are not drawn from a shuffle of every value.
+const UNIQUE_ATTEMPTS: usize = 1000;
+
+/// The largest precision of decimals which are JSON numbers, as they
+/// have fewer digits than are exact in a double. Decimals of a larger
+/// precision are strings, so that they keep every digit.
+const EXACT_PRECISION: u32 = 15;
 
 /// The physical representation of a [DataType], this enum
 /// defines how fields are generated. Many different logical
@@ -18,14 +30,64 @@ use super::distributions::Iter;
 pub enum PhysicalDataType<'a> {
     Array {
         element: Box<PhysicalDataType<'a>>,
+        min_size: i32,
         size: i32,
     },
     Boolean,
+    Cardinality {
+        element: Box<PhysicalDataType<'a>>,
+        count: usize,
+        /// The distinct values, sampled once when first sampling.
+        pool: RefCell<Vec<Value>>,
+        exhausted: Cell<bool>,
+    },
     Generator {
         format: Interpolator,
     },
+    /// Keys chosen by a selection, such as a range or
+    /// options with hot keys. Without keys it is null.
+    Keys {
+        keys: KeySpace<'a>,
+        selector: Option<Selector>,
+    },
+    /// Decimals sampled as unscaled integers, such
+    /// as `-12345` at scale 2 for `-123.45`.
+    Decimal {
+        r: RangeInclusive<i128>,
+        precision: u32,
+        scale: u32,
+    },
+    Float {
+        r: Range<f64>,
+    },
+    Map {
+        keys: Box<PhysicalDataType<'a>>,
+        values: Box<PhysicalDataType<'a>>,
+        size: i32,
+    },
+    /// A reference to a member of a pool, which is set by the
+    /// generator of the schema defining the pool. References
+    /// which are shared select the member selected for the
+    /// value being generated, others select their own member.
+    Member {
+        pool: &'a str,
+        field: Option<&'a str>,
+        members: Option<Rc<Members<'a>>>,
+        shared: bool,
+        cursor: Cell<usize>,
+        generation: Cell<u64>,
+    },
+    Nullable {
+        element: Box<PhysicalDataType<'a>>,
+        null_rate: f64,
+    },
     OneOf {
         options: Vec<&'a str>,
+        weights: Option<WeightedIndex<f64>>,
+    },
+    Optional {
+        element: Box<PhysicalDataType<'a>>,
+        missing_rate: f64,
     },
     Proxy {
         f: Box<dyn DynDistribution>,
@@ -33,27 +95,120 @@ pub enum PhysicalDataType<'a> {
     Range {
         r: Range<i64>,
     },
+    /// Records the non-null values sampled from the element,
+    /// such as the keys of a table referenced by other tables.
+    Recorded {
+        element: Box<PhysicalDataType<'a>>,
+        values: Rc<RefCell<Vec<Value>>>,
+    },
     Regex {
         pattern: RegexPattern,
     },
+    /// Numbers counting up from 1 to the largest 4 byte
+    /// integer, of which `next` is sampled next.
+    Serial {
+        next: Cell<u32>,
+    },
     Object {
-        fields: HashMap<&'a str, PhysicalDataType<'a>>,
+        fields: BTreeMap<&'a str, PhysicalDataType<'a>>,
+    },
+    Timestamp {
+        format: String,
+    },
+    Union {
+        variants: Vec<PhysicalDataType<'a>>,
+    },
+    Unique {
+        element: Box<PhysicalDataType<'a>>,
+        /// The values of an element with a finite number of values,
+        /// drawn by index without replacement.
+        shuffle: Option<RefCell<Shuffle>>,
+        /// The JSON text of every value sampled so far otherwise.
+        seen: RefCell<HashSet<String>>,
+        exhausted: Cell<bool>,
     },
 }
 
-impl<'a> From<&DataType<'a>> for PhysicalDataType<'a> {
-    fn from(dt: &DataType<'a>) -> Self {
+impl<'a> From<&'a DataType<'a>> for PhysicalDataType<'a> {
+    fn from(dt: &'a DataType<'a>) -> Self {
         match dt {
-            DataType::Array { element, size } => PhysicalDataType::Array {
+            DataType::Array {
+                element,
+                size,
+                min_size,
+            } => PhysicalDataType::Array {
                 element: Box::new(element.deref().into()),
+                min_size: min_size.unwrap_or(1) as i32,
                 size: *size as i32,
             },
             DataType::Boolean => PhysicalDataType::Boolean,
+            DataType::Cardinality { element, count } => PhysicalDataType::Cardinality {
+                element: Box::new(element.deref().into()),
+                count: *count as usize,
+                pool: RefCell::default(),
+                exhausted: Cell::new(false),
+            },
+            DataType::Email => PhysicalDataType::Regex {
+                pattern:
+                    r"[a-z]{3,10}(\.[a-z]{3,10})?@(gmail|yahoo|outlook|example)\.(com|org|net)"
+                        .to_owned()
+                        .try_into()
+                        .unwrap(),
+            },
+            DataType::Decimal {
+                precision,
+                scale,
+                min,
+                max,
+            } => PhysicalDataType::Decimal {
+                r: decimal_range(*precision, *scale, *min, *max),
+                precision: *precision,
+                scale: *scale,
+            },
+            DataType::Float { from, to } => PhysicalDataType::Float { r: *from..*to },
             DataType::Literal { value } => PhysicalDataType::Proxy {
-                f: Box::new(Static::new(*value)),
+                f: Box::new(Static::new(value)),
+            },
+            DataType::Map { keys, values, size } => PhysicalDataType::Map {
+                keys: Box::new(keys.deref().into()),
+                values: Box::new(values.deref().into()),
+                size: *size as i32,
+            },
+            DataType::Member { pool, field, .. } => PhysicalDataType::Member {
+                pool,
+                field: field.as_deref(),
+                members: None,
+                shared: false,
+                cursor: Cell::new(0),
+                generation: Cell::new(0),
             },
-            DataType::OneOf { options } => PhysicalDataType::OneOf {
-                options: options.clone(),
+            DataType::Nullable { element, null_rate } => PhysicalDataType::Nullable {
+                element: Box::new(element.deref().into()),
+                null_rate: *null_rate,
+            },
+            DataType::OneOf {
+                options,
+                selection: Some(selection),
+                ..
+            } => PhysicalDataType::Keys {
+                keys: KeySpace::Options(options.iter().map(|option| option.as_ref()).collect()),
+                selector: Selector::new(selection, options.len() as u64),
+            },
+            DataType::OneOf {
+                options, weights, ..
+            } => PhysicalDataType::OneOf {
+                options: options.iter().map(|option| option.as_ref()).collect(),
+                weights: weights
+                    .as_ref()
+                    .filter(|weights| weights.len() == options.len())
+                    .and_then(|weights| WeightedIndex::new(weights).ok()),
+            },
+            DataType::Optional {
+                element,
+                missing_rate,
+            } => PhysicalDataType::Optional {
+                element: Box::new(element.deref().into()),
+                missing_rate: *missing_rate,
             },
             DataType::PhoneNumber => PhysicalDataType::Regex {
                 pattern: r"\d{3}-\d{3}-\d{4}".to_owned().try_into().unwrap(),
@@ -62,7 +217,18 @@ impl<'a> From<&DataType<'a>> for PhysicalDataType<'a> {
             DataType::Integer => PhysicalDataType::Range {
                 r: -2147483648..2147483648,
             },
-            DataType::Range { from, to } => PhysicalDataType::Range { r: *from..*to },
+            DataType::BigInt => PhysicalDataType::Range {
+                r: i64::MIN..i64::MAX,
+            },
+            DataType::Range {
+                from,
+                to,
+                selection: Some(selection),
+            } => PhysicalDataType::Keys {
+                keys: KeySpace::Range(*from),
+                selector: Selector::new(selection, if from < to { to.abs_diff(*from) } else { 0 }),
+            },
+            DataType::Range { from, to, .. } => PhysicalDataType::Range { r: *from..*to },
             DataType::Regex { pattern } => PhysicalDataType::Regex {
                 pattern: pattern.clone(),
             },
@@ -70,52 +236,545 @@ impl<'a> From<&DataType<'a>> for PhysicalDataType<'a> {
                 format: format.clone(),
             },
             DataType::Object { fields } => PhysicalDataType::Object {
-                fields: fields.iter().map(|(name, dt)| (*name, dt.into())).collect(),
+                fields: fields
+                    .iter()
+                    .map(|(name, dt)| (name.as_ref(), dt.into()))
+                    .collect(),
+            },
+            DataType::Serial => PhysicalDataType::Serial { next: Cell::new(1) },
+            DataType::Timestamp { format } => PhysicalDataType::Timestamp {
+                format: format.clone().unwrap_or_else(|| "%F %r".to_owned()),
             },
-            DataType::Serial => PhysicalDataType::Proxy {
-                f: Box::new(Iter::new((1..=2147483647).map(|id| json!(id)))),
+            DataType::Union { variants } => PhysicalDataType::Union {
+                variants: variants.iter().map(|dt| dt.into()).collect(),
             },
-            DataType::Timestamp => PhysicalDataType::Proxy {
-                f: Box::new(Supplier::new(timestamp)),
+            DataType::Unique { element } => {
+                let element: PhysicalDataType = element.deref().into();
+                PhysicalDataType::Unique {
+                    shuffle: element.size().map(|size| RefCell::new(Shuffle::new(size))),
+                    element: Box::new(element),
+                    seen: RefCell::default(),
+                    exhausted: Cell::new(false),
+                }
+            }
+            DataType::Uuid => PhysicalDataType::Regex {
+                pattern: r"[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}"
+                    .to_owned()
+                    .try_into()
+                    .unwrap(),
+            },
+        }
+    }
+}
+
+impl<'a> PhysicalDataType<'a> {
+    /// Sets the members of the pools referenced by this type. References
+    /// are shared when they are sampled exactly once per value, which is
+    /// when they are only nested within nullable, optional, object
+    /// and recorded types, so that they select the member selected for the value.
+    pub(crate) fn resolve(&mut self, pools: &HashMap<&str, Rc<Members<'a>>>, shared: bool) {
+        match self {
+            PhysicalDataType::Member {
+                pool,
+                members,
+                shared: is_shared,
+                ..
+            } => {
+                *members = pools.get(pool).cloned();
+                *is_shared = shared;
+            }
+            PhysicalDataType::Nullable { element, .. }
+            | PhysicalDataType::Optional { element, .. }
+            | PhysicalDataType::Recorded { element, .. } => element.resolve(pools, shared),
+            PhysicalDataType::Object { fields } => {
+                for dt in fields.values_mut() {
+                    dt.resolve(pools, shared);
+                }
+            }
+            PhysicalDataType::Array { element, .. }
+            | PhysicalDataType::Cardinality { element, .. }
+            | PhysicalDataType::Unique { element, .. } => element.resolve(pools, false),
+            PhysicalDataType::Map { keys, values, .. } => {
+                keys.resolve(pools, false);
+                values.resolve(pools, false);
+            }
+            PhysicalDataType::Union { variants } => {
+                for dt in variants {
+                    dt.resolve(pools, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The number of values sampled before the values of a unique
+    /// type, or of the pool of a cardinality, were exhausted.
+    pub fn exhausted(&self) -> Option<usize> {
+        match self {
+            PhysicalDataType::Unique {
+                shuffle: Some(shuffle),
+                exhausted,
+                ..
+            } if exhausted.get() => Some(shuffle.borrow().drawn() as usize),
+            PhysicalDataType::Unique {
+                seen, exhausted, ..
+            } if exhausted.get() => Some(seen.borrow().len()),
+            PhysicalDataType::Cardinality {
+                pool, exhausted, ..
+            } if exhausted.get() => Some(pool.borrow().len()),
+            PhysicalDataType::Array { element, .. }
+            | PhysicalDataType::Cardinality { element, .. }
+            | PhysicalDataType::Nullable { element, .. }
+            | PhysicalDataType::Optional { element, .. }
+            | PhysicalDataType::Recorded { element, .. }
+            | PhysicalDataType::Unique { element, .. } => element.exhausted(),
+            PhysicalDataType::Map { keys, values, .. } => {
+                keys.exhausted().or_else(|| values.exhausted())
+            }
+            PhysicalDataType::Object { fields } => fields.values().find_map(|dt| dt.exhausted()),
+            PhysicalDataType::Union { variants } => variants.iter().find_map(|dt| dt.exhausted()),
+            _ => None,
+        }
+    }
+
+    /// Whether values of this type are unique, or contain unique values.
+    pub(crate) fn has_unique(&self) -> bool {
+        match self {
+            PhysicalDataType::Unique { .. } => true,
+            PhysicalDataType::Array { element, .. }
+            | PhysicalDataType::Cardinality { element, .. }
+            | PhysicalDataType::Nullable { element, .. }
+            | PhysicalDataType::Optional { element, .. }
+            | PhysicalDataType::Recorded { element, .. } => element.has_unique(),
+            PhysicalDataType::Map { keys, values, .. } => keys.has_unique() || values.has_unique(),
+            PhysicalDataType::Object { fields } => fields.values().any(|dt| dt.has_unique()),
+            PhysicalDataType::Union { variants } => variants.iter().any(|dt| dt.has_unique()),
+            _ => false,
+        }
+    }
+
+    /// Continues serials after the given number of values, as if each
+    /// serial was sampled once per value, and fills the pools of
+    /// cardinalities with the given generator if they are empty.
+    pub(crate) fn seek<R: Rng + ?Sized>(&self, offset: u64, rng: &mut R) {
+        match self {
+            PhysicalDataType::Serial { next } => {
+                next.set(offset.saturating_add(1).min(u32::MAX as u64) as u32)
+            }
+            PhysicalDataType::Cardinality {
+                element,
+                count,
+                pool,
+                exhausted,
+            } => {
+                element.seek(offset, rng);
+                fill(element, *count, &mut pool.borrow_mut(), exhausted, rng);
+            }
+            PhysicalDataType::Array { element, .. }
+            | PhysicalDataType::Nullable { element, .. }
+            | PhysicalDataType::Optional { element, .. }
+            | PhysicalDataType::Recorded { element, .. }
+            | PhysicalDataType::Unique { element, .. } => element.seek(offset, rng),
+            PhysicalDataType::Map { keys, values, .. } => {
+                keys.seek(offset, rng);
+                values.seek(offset, rng);
+            }
+            PhysicalDataType::Object { fields } => {
+                for dt in fields.values() {
+                    dt.seek(offset, rng);
+                }
+            }
+            PhysicalDataType::Union { variants } => {
+                for dt in variants {
+                    dt.seek(offset, rng);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The number of distinct values of this type, if they can be
+    /// enumerated by index with [PhysicalDataType::nth].
+    fn size(&self) -> Option<u64> {
+        match self {
+            PhysicalDataType::Boolean => Some(2),
+            PhysicalDataType::Cardinality { count, .. } => Some(*count as u64),
+            PhysicalDataType::Decimal { r, .. } => match r.end().checked_sub(*r.start()) {
+                Some(n) if n < 0 => Some(0),
+                Some(n) => u64::try_from(n).ok().and_then(|n| n.checked_add(1)),
+                None => None,
             },
+            PhysicalDataType::Keys { selector, .. } => {
+                Some(selector.as_ref().map_or(0, Selector::count))
+            }
+            PhysicalDataType::OneOf { options, .. } => {
+                // repeated options are not distinct values
+                let distinct: HashSet<_> = options.iter().collect();
+                (distinct.len() == options.len()).then_some(options.len() as u64)
+            }
+            PhysicalDataType::Range { r } if r.start < r.end => Some(r.end.abs_diff(r.start)),
+            PhysicalDataType::Range { .. } => Some(0),
+            _ => None,
+        }
+    }
+
+    /// The value of the given index among the distinct values of
+    /// this type, if it is below the [PhysicalDataType::size].
+    fn nth<R: Rng + ?Sized>(&self, index: u64, rng: &mut R) -> Value {
+        match self {
+            PhysicalDataType::Boolean => Value::Bool(index == 1),
+            PhysicalDataType::Cardinality {
+                element,
+                count,
+                pool,
+                exhausted,
+            } => {
+                let mut pool = pool.borrow_mut();
+                fill(element, *count, &mut pool, exhausted, rng);
+                pool.get(index as usize).cloned().unwrap_or(Value::Null)
+            }
+            PhysicalDataType::Decimal {
+                r,
+                precision,
+                scale,
+            } => decimal(r.start() + index as i128, *precision, *scale),
+            PhysicalDataType::Keys {
+                keys,
+                selector: Some(selector),
+            } => {
+                selector.record(index);
+                keys.key(index)
+            }
+            PhysicalDataType::OneOf { options, .. } => json!(options[index as usize]),
+            PhysicalDataType::Range { r } => json!(r.start.wrapping_add_unsigned(index)),
+            _ => self.sample(rng),
+        }
+    }
+
+    /// Collects how often each key of the selections of this type, at
+    /// the given path, was used. Elements of arrays are at `path[]`.
+    pub fn frequencies(&self, path: &str, frequencies: &mut Vec<KeyFrequencies>) {
+        match self {
+            PhysicalDataType::Keys {
+                keys,
+                selector: Some(selector),
+            } => frequencies.push(KeyFrequencies {
+                field: path.to_owned(),
+                keys: selector
+                    .frequencies()
+                    .into_iter()
+                    .map(|(index, n)| (keys.key(index), n))
+                    .collect(),
+            }),
+            PhysicalDataType::Array { element, .. } => {
+                element.frequencies(&format!("{}[]", path), frequencies)
+            }
+            PhysicalDataType::Cardinality { element, .. }
+            | PhysicalDataType::Nullable { element, .. }
+            | PhysicalDataType::Optional { element, .. }
+            | PhysicalDataType::Recorded { element, .. }
+            | PhysicalDataType::Unique { element, .. } => element.frequencies(path, frequencies),
+            PhysicalDataType::Map { keys, values, .. } => {
+                keys.frequencies(path, frequencies);
+                values.frequencies(path, frequencies);
+            }
+            PhysicalDataType::Object { fields } => {
+                for (name, dt) in fields {
+                    dt.frequencies(&format!("{}.{}", path, name), frequencies);
+                }
+            }
+            PhysicalDataType::Union { variants } => {
+                for dt in variants {
+                    dt.frequencies(path, frequencies);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The keys of a [PhysicalDataType::Keys], by index.
+pub enum KeySpace<'a> {
+    /// The numbers of a range, from its lower bound.
+    Range(i64),
+    Options(Vec<&'a str>),
+}
+
+impl KeySpace<'_> {
+    fn key(&self, index: u64) -> Value {
+        match self {
+            KeySpace::Range(from) => json!(from.wrapping_add_unsigned(index)),
+            KeySpace::Options(options) => json!(options[index as usize]),
+        }
+    }
+}
+
+/// Fills an empty pool of a cardinality with `count` distinct
+/// values of the element, or as many as are found.
+fn fill<R: Rng + ?Sized>(
+    element: &PhysicalDataType<'_>,
+    count: usize,
+    pool: &mut Vec<Value>,
+    exhausted: &Cell<bool>,
+    rng: &mut R,
+) {
+    if !pool.is_empty() {
+        return;
+    }
+
+    let mut seen = HashSet::new();
+    while pool.len() < count {
+        match sample_unseen(element, &mut seen, rng) {
+            Some(value) => pool.push(value),
+            None => {
+                exhausted.set(true);
+                break;
+            }
+        }
+    }
+}
+
+/// Samples a value of the element which is not in the set of values
+/// seen before, or returns `None` if every attempt finds a seen value.
+/// Nulls are never seen, as they are not values.
+fn sample_unseen<R: Rng + ?Sized>(
+    element: &PhysicalDataType<'_>,
+    seen: &mut HashSet<String>,
+    rng: &mut R,
+) -> Option<Value> {
+    (0..UNIQUE_ATTEMPTS).find_map(|_| {
+        let value = element.sample(rng);
+        (value.is_null() || seen.insert(value.to_string())).then_some(value)
+    })
+}
+
+/// The unscaled values of decimals of the given precision and scale
+/// between the inclusive bounds, which is empty if no decimal is.
+pub(crate) fn decimal_range(
+    precision: u32,
+    scale: u32,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> RangeInclusive<i128> {
+    // bounds which are a decimal of the scale, such as 0.29, are
+    // not rounded past it when they are not exact as a double
+    let unscaled = |bound: f64, round: fn(f64) -> f64| {
+        let bound = bound * 10f64.powi(scale as i32);
+        if (bound - bound.round()).abs() < 1e-6 {
+            bound.round() as i128
+        } else {
+            round(bound) as i128
         }
+    };
+
+    let bound = 10i128.pow(precision) - 1;
+    let from = min.map_or(-bound, |min| unscaled(min, f64::ceil).max(-bound));
+    let to = max.map_or(bound, |max| unscaled(max, f64::floor).min(bound));
+    from..=to
+}
+
+/// The value of an unscaled decimal of the given precision and scale.
+fn decimal(unscaled: i128, precision: u32, scale: u32) -> Value {
+    if precision <= EXACT_PRECISION && scale == 0 {
+        return Value::from(unscaled as i64);
     }
+
+    let digits = format!(
+        "{:0width$}",
+        unscaled.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let text = match scale {
+        0 => format!("{}{}", sign, integer),
+        _ => format!("{}{}.{}", sign, integer, fraction),
+    };
+
+    if precision <= EXACT_PRECISION {
+        text.parse()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    } else {
+        Value::String(text)
+    }
+}
+
+pub(crate) fn timestamp(format: &str) -> Value {
+    Value::String(Local::now().format(format).to_string())
 }
 
-fn timestamp() -> Value {
-    Value::String(Local::now().format("%F %r").to_string())
+/// Samples each field of an object, omitting [PhysicalDataType::Optional]
+/// fields whenever they are missing.
+pub fn sample_fields<R: Rng + ?Sized>(
+    fields: &BTreeMap<&str, PhysicalDataType<'_>>,
+    rng: &mut R,
+) -> Value {
+    let mut components = Map::new();
+    for (name, dt) in fields.iter() {
+        match dt {
+            PhysicalDataType::Optional {
+                element,
+                missing_rate,
+            } => {
+                if !rng.gen_bool(*missing_rate) {
+                    components.insert(name.to_string(), element.sample(rng));
+                }
+            }
+            _ => {
+                components.insert(name.to_string(), dt.sample(rng));
+            }
+        }
+    }
+
+    Value::Object(components)
 }
 
 impl Distribution<Value> for PhysicalDataType<'_> {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Value {
         match self {
-            PhysicalDataType::Array { element, size } => {
-                let length: i32 = rng.gen_range(1..=*size);
-                let elements = (0..length)
-                    .into_iter()
-                    .map(move |_| element.sample(rng))
-                    .collect();
+            PhysicalDataType::Array {
+                element,
+                min_size,
+                size,
+            } => {
+                let length: i32 = rng.gen_range(*min_size..=*size);
+                let elements = (0..length).map(move |_| element.sample(rng)).collect();
 
                 Value::Array(elements)
             }
             PhysicalDataType::Boolean => Value::Bool(rng.gen()),
+            PhysicalDataType::Cardinality {
+                element,
+                count,
+                pool,
+                exhausted,
+            } => {
+                let mut pool = pool.borrow_mut();
+                fill(element, *count, &mut pool, exhausted, rng);
+                pool.choose(rng).cloned().unwrap_or(Value::Null)
+            }
+            PhysicalDataType::Decimal {
+                r,
+                precision,
+                scale,
+            } => decimal(rng.gen_range(r.clone()), *precision, *scale),
+            PhysicalDataType::Float { r } => Number::from_f64(rng.gen_range(r.clone()))
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            PhysicalDataType::Map { keys, values, size } => {
+                let length: i32 = rng.gen_range(1..=*size);
+                let entries = (0..length)
+                    .map(|_| {
+                        let key = match keys.sample(rng) {
+                            Value::String(key) => key,
+                            key => key.to_string(),
+                        };
+                        (key, values.sample(rng))
+                    })
+                    .collect();
+
+                Value::Object(entries)
+            }
+            PhysicalDataType::Member {
+                field,
+                members,
+                shared,
+                cursor,
+                generation,
+                ..
+            } => {
+                let member = match members {
+                    Some(members) if *shared => members.next(cursor, generation, rng),
+                    Some(members) => members.sample(rng),
+                    None => Value::Null,
+                };
+                match field {
+                    Some(field) => member.get(field).cloned().unwrap_or(Value::Null),
+                    None => member,
+                }
+            }
+            PhysicalDataType::Nullable { element, null_rate } => {
+                if rng.gen_bool(*null_rate) {
+                    Value::Null
+                } else {
+                    element.sample(rng)
+                }
+            }
+            PhysicalDataType::Optional {
+                element,
+                missing_rate,
+            } => {
+                if rng.gen_bool(*missing_rate) {
+                    Value::Null
+                } else {
+                    element.sample(rng)
+                }
+            }
             PhysicalDataType::Generator { format } => Value::String(format.sample(rng)),
+            PhysicalDataType::Keys { keys, selector } => match selector {
+                Some(selector) => {
+                    let index = selector.sample(rng);
+                    selector.record(index);
+                    keys.key(index)
+                }
+                None => Value::Null,
+            },
             PhysicalDataType::Proxy { f } => f.sample(rng),
+            PhysicalDataType::Recorded { element, values } => {
+                let value = element.sample(rng);
+                if !value.is_null() {
+                    values.borrow_mut().push(value.clone());
+                }
+                value
+            }
             PhysicalDataType::Regex { pattern } => Value::String(pattern.sample(rng)),
+            PhysicalDataType::Serial { next } => match next.get() {
+                n if n > i32::MAX as u32 => Value::Null,
+                n => {
+                    next.set(n + 1);
+                    json!(n)
+                }
+            },
             PhysicalDataType::Range { ref r } => {
                 Value::Number(Number::from(rng.gen_range(r.clone())))
             }
-            PhysicalDataType::OneOf { options } => match options.choose(rng) {
+            PhysicalDataType::OneOf { options, weights } => {
+                let choice = match weights {
+                    Some(weights) => options.get(weights.sample(rng)),
+                    None => options.choose(rng),
+                };
+
+                match choice {
+                    None => Value::Null,
+                    Some(choice) => Value::String(choice.to_string()),
+                }
+            }
+            PhysicalDataType::Object { fields } => sample_fields(fields, rng),
+            PhysicalDataType::Timestamp { format } => timestamp(format),
+            PhysicalDataType::Union { variants } => match variants.choose(rng) {
                 None => Value::Null,
-                Some(choice) => Value::String(choice.to_string()),
+                Some(variant) => variant.sample(rng),
             },
-            PhysicalDataType::Object { fields } => {
-                let components = fields
-                    .iter()
-                    .map(move |(name, dt)| (name.to_string(), dt.sample(rng)))
-                    .collect();
-
-                Value::Object(components)
+            PhysicalDataType::Unique {
+                element,
+                shuffle,
+                seen,
+                exhausted,
+            } => {
+                let value = match shuffle {
+                    Some(shuffle) => {
+                        let index = shuffle.borrow_mut().draw(rng);
+                        index.map(|index| element.nth(index, rng))
+                    }
+                    None => sample_unseen(element, &mut seen.borrow_mut(), rng),
+                };
+                value.unwrap_or_else(|| {
+                    exhausted.set(true);
+                    Value::Null
+                })
             }
         }
     }
@@ -0,0 +1,265 @@
+//! Pools of entities, such as users or devices, which are generated
+//! once and referenced by many values, so that an entity has the
+//! same attributes in every value referencing it.
+
+use crate::generator::DataGenerator;
+use crate::schema::Schema;
+use crate::selection::{KeyFrequencies, Selection, Selector};
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+/// A [Pool] of a fixed number of members, each generated from
+/// the schema of the pool when it is first selected.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Pool<'a> {
+    /// The number of members of the pool.
+    pub count: u32,
+
+    /// The fields of each member.
+    #[serde(borrow)]
+    pub schema: Schema<'a>,
+
+    /// (optional) How members are selected, uniformly by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<Selection>,
+}
+
+/// The members of a [Pool] while generating values.
+pub(crate) struct Members<'a> {
+    gen: DataGenerator<'a>,
+    members: RefCell<HashMap<u64, Value>>,
+    selector: Option<Selector>,
+    /// Whether the selection is reported in the key frequencies.
+    reported: bool,
+    /// The members selected for the values being generated, one per
+    /// value, and whether the member was used by the value.
+    selected: RefCell<Vec<(u64, bool)>>,
+    /// Counts the selections, so that references know when to start over.
+    generation: Cell<u64>,
+    /// The seed each member is generated from along with its index,
+    /// instead of the generator of the value first selecting it.
+    seed: Cell<Option<u64>>,
+}
+
+impl<'a> Members<'a> {
+    pub(crate) fn new(pool: &'a Pool<'a>) -> Self {
+        let selection = pool.selection.clone().unwrap_or_default();
+        Members {
+            gen: DataGenerator::new(&pool.schema),
+            members: RefCell::default(),
+            selector: Selector::new(&selection, pool.count as u64),
+            reported: pool.selection.is_some(),
+            selected: RefCell::default(),
+            generation: Cell::new(0),
+            seed: Cell::new(None),
+        }
+    }
+
+    /// The generator of the fields of each member.
+    pub(crate) fn generator(&self) -> &DataGenerator<'a> {
+        &self.gen
+    }
+
+    /// Generates each member from the seed and its index, so that
+    /// members are the same whichever values select them first.
+    pub(crate) fn seed(&self, seed: u64) {
+        self.seed.set(Some(seed));
+    }
+
+    /// Selects the members of the next `rows` values.
+    pub(crate) fn select<R: Rng + ?Sized>(&self, rows: usize, rng: &mut R) {
+        let mut selected = self.selected.borrow_mut();
+        selected.clear();
+        if let Some(selector) = &self.selector {
+            selected.extend((0..rows).map(|_| (selector.sample(rng), false)));
+        }
+        self.generation.set(self.generation.get() + 1);
+    }
+
+    /// The member selected for the next value of a reference, given the
+    /// position of the reference within the selection and the generation
+    /// of the selection it was last used with.
+    pub(crate) fn next<R: Rng + ?Sized>(
+        &self,
+        cursor: &Cell<usize>,
+        generation: &Cell<u64>,
+        rng: &mut R,
+    ) -> Value {
+        if generation.get() != self.generation.get() {
+            generation.set(self.generation.get());
+            cursor.set(0);
+        }
+        let index = match self.selected.borrow_mut().get_mut(cursor.get()) {
+            Some((index, used)) => {
+                // A member is counted once per value, however many
+                // fields of the value reference it.
+                if !*used {
+                    *used = true;
+                    self.record(*index);
+                }
+                Some(*index)
+            }
+            None => None,
+        };
+        cursor.set(cursor.get() + 1);
+        match index {
+            Some(index) => self.member(index, rng),
+            None => self.sample(rng),
+        }
+    }
+
+    /// A member selected independently of the selection of the next values.
+    pub(crate) fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Value {
+        match &self.selector {
+            Some(selector) => {
+                let index = selector.sample(rng);
+                self.record(index);
+                self.member(index, rng)
+            }
+            None => Value::Null,
+        }
+    }
+
+    /// How often each member was used, if the selection of the pool is
+    /// set. Members are reported by their values, which are generated
+    /// for every member that was used.
+    pub(crate) fn frequencies(&self, pool: &str) -> Option<KeyFrequencies> {
+        let selector = self.selector.as_ref().filter(|_| self.reported)?;
+        let members = self.members.borrow();
+        Some(KeyFrequencies {
+            field: format!("$pools.{}", pool),
+            keys: selector
+                .frequencies()
+                .into_iter()
+                .map(|(index, n)| (members.get(&index).cloned().unwrap_or_default(), n))
+                .collect(),
+        })
+    }
+
+    fn record(&self, index: u64) {
+        if let Some(selector) = &self.selector {
+            selector.record(index);
+        }
+    }
+
+    fn member<R: Rng + ?Sized>(&self, index: u64, rng: &mut R) -> Value {
+        self.members
+            .borrow_mut()
+            .entry(index)
+            .or_insert_with(|| match self.seed.get() {
+                Some(seed) => {
+                    // serials of members count their index
+                    self.gen.seek(seed, index);
+                    StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+                        .sample(&self.gen)
+                }
+                None => rng.sample(&self.gen),
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::DataGenerator;
+    use crate::schema::Schema;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde_json::Value;
+    use std::collections::{HashMap, HashSet};
+
+    const EVENTS: &str = r#"{
+        "$pools": {
+            "users": {
+                "count": 50,
+                "schema": {
+                    "id": { "type": "range", "from": 0, "to": 1000000, "unique": true },
+                    "name": { "type": "regex", "pattern": "[a-z]{12}" },
+                    "country": { "type": "one_of", "options": ["DE", "FR", "US"] }
+                },
+                "selection": { "type": "zipf", "exponent": 1.2 }
+            }
+        },
+        "user_id": { "type": "member", "pool": "users", "field": "id" },
+        "user_name": { "type": "member", "pool": "users", "field": "name" },
+        "user": { "type": "optional", "element": { "type": "member", "pool": "users" }, "missing_rate": 0.5 },
+        "friends": { "type": "array", "element": { "type": "member", "pool": "users", "field": "id" }, "size": 3 }
+    }"#;
+
+    #[test]
+    fn test_members_are_consistent() {
+        let schema: Schema = serde_json::from_str(EVENTS).unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut names = HashMap::new();
+        let mut ids = HashSet::new();
+        for _ in 0..5000 {
+            let value: Value = rng.sample(&gen);
+            let id = value["user_id"].as_i64().unwrap();
+            let name = value["user_name"].as_str().unwrap().to_owned();
+            assert_eq!(&name, names.entry(id).or_insert_with(|| name.clone()));
+            if let Some(user) = value.get("user") {
+                assert_eq!(id, user["id"].as_i64().unwrap());
+                assert_eq!(name, user["name"].as_str().unwrap());
+            }
+            for friend in value["friends"].as_array().unwrap() {
+                ids.insert(friend.as_i64().unwrap());
+            }
+        }
+
+        // Friends are selected on their own, from the same members
+        assert!(names.len() <= 50);
+        assert!(ids.len() <= 50);
+        assert!(gen.check().is_ok());
+    }
+
+    #[test]
+    fn test_zipf_selection() {
+        let schema: Schema = serde_json::from_str(EVENTS).unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut counts: HashMap<i64, usize> = HashMap::new();
+        for _ in 0..10000 {
+            let value: Value = rng.sample(&gen);
+            *counts
+                .entry(value["user_id"].as_i64().unwrap())
+                .or_default() += 1;
+        }
+
+        // The hottest of 50 members is selected far more often than 1 in 50
+        let hottest = counts.values().max().unwrap();
+        assert!(*hottest > 2000, "{:?}", counts);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_members_of_record_batches_are_consistent() {
+        use arrow::array::{AsArray, StringArray};
+        use arrow::compute::cast;
+        use arrow::datatypes::{DataType, Int64Type};
+
+        let schema: Schema = serde_json::from_str(EVENTS).unwrap();
+        let gen = DataGenerator::new(&schema);
+        let batch = gen
+            .record_batch(&mut StdRng::seed_from_u64(7), 1000)
+            .unwrap();
+
+        let ids = cast(batch.column_by_name("user_id").unwrap(), &DataType::Int64).unwrap();
+        let ids = ids.as_primitive::<Int64Type>();
+        let names = batch.column_by_name("user_name").unwrap();
+        let names = names.as_any().downcast_ref::<StringArray>().unwrap();
+
+        let mut members = HashMap::new();
+        for (id, name) in ids.iter().zip(names.iter()) {
+            assert_eq!(&name, members.entry(id).or_insert(name));
+        }
+    }
+}
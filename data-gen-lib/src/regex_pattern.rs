@@ -2,6 +2,9 @@ use rand::prelude::Distribution;
 use rand::Rng;
 use rand_regex::Regex;
 use regex_syntax;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, Serializer};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{Debug, Formatter};
@@ -45,7 +48,7 @@ impl Debug for RegexPattern {
     }
 }
 
-impl<'a> Distribution<String> for RegexPattern {
+impl Distribution<String> for RegexPattern {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
         rng.sample(&self.regex)
     }
@@ -57,10 +60,10 @@ impl TryFrom<String> for RegexPattern {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let mut parser = regex_syntax::ParserBuilder::new().unicode(false).build();
 
-        let regex = parser
-            .parse(&value)
-            .map_err(rand_regex::Error::Syntax)
-            .and_then(move |hir| rand_regex::Regex::with_hir(hir, 100));
+        let regex = match parser.parse(&value) {
+            Ok(hir) => rand_regex::Regex::with_hir(hir, 100),
+            Err(err) => Err(rand_regex::Error::Syntax(err)),
+        };
 
         match regex {
             Ok(regex) => Ok(RegexPattern {
@@ -75,10 +78,25 @@ impl TryFrom<String> for RegexPattern {
     }
 }
 
+impl JsonSchema for RegexPattern {
+    fn schema_name() -> String {
+        "RegexPattern".to_owned()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            format: Some("regex".to_owned()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 #[derive(Deserialize)]
 struct IntermediateRegexPattern(String);
 
-impl<'a> TryFrom<IntermediateRegexPattern> for RegexPattern {
+impl TryFrom<IntermediateRegexPattern> for RegexPattern {
     type Error = Error;
 
     fn try_from(value: IntermediateRegexPattern) -> Result<Self, Self::Error> {
@@ -86,7 +104,7 @@ impl<'a> TryFrom<IntermediateRegexPattern> for RegexPattern {
     }
 }
 
-impl<'a> Serialize for RegexPattern {
+impl Serialize for RegexPattern {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -1,17 +1,118 @@
 use crate::data_type::DataType;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::pool::Pool;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, RootSchema, Schema as JsonSchemaObject, SchemaObject};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 /// A [Schema] defines a type and how to generate
 /// the values for each field based on the given
-/// [DataType].
+/// [DataType]. The pools of entities referenced
+/// by [DataType::Member] fields are defined by
+/// name under the `$pools` key.
 #[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(remote = "Self")]
 pub struct Schema<'a> {
+    #[serde(
+        rename = "$pools",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        borrow
+    )]
+    pools: BTreeMap<Cow<'a, str>, Pool<'a>>,
     #[serde(flatten, borrow)]
-    fields: HashMap<&'a str, DataType<'a>>,
+    fields: HashMap<Cow<'a, str>, DataType<'a>>,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Schema<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut schema = Schema::deserialize(deserializer)?;
+        schema.resolve();
+        Ok(schema)
+    }
+}
+
+impl Serialize for Schema<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Schema::serialize(self, serializer)
+    }
+}
+
+impl JsonSchema for Schema<'_> {
+    fn schema_name() -> String {
+        "Schema".to_owned()
+    }
+
+    /// Describes the fields, which are flattened into the schema,
+    /// as its additional properties next to the `$pools`.
+    fn json_schema(gen: &mut SchemaGenerator) -> JsonSchemaObject {
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::Object.into()),
+            ..Default::default()
+        };
+        schema.metadata().description = Some(
+            "The fields of a data-gen schema by name, along with the pools of entities \
+             referenced by `member` fields under the `$pools` key."
+                .to_owned(),
+        );
+
+        let object = schema.object();
+        object.properties.insert(
+            "$pools".to_owned(),
+            gen.subschema_for::<BTreeMap<String, Pool>>(),
+        );
+        object.additional_properties = Some(Box::new(gen.subschema_for::<DataType>()));
+        schema.into()
+    }
 }
 
 impl<'a> Schema<'a> {
+    /// Returns a [JSON Schema](https://json-schema.org) describing
+    /// the format of data-gen schema files, for use with editors
+    /// that support autocompletion and inline validation.
+    pub fn spec() -> RootSchema {
+        let mut spec = schema_for!(Schema);
+        spec.schema.metadata().title = Some("data-gen schema".to_owned());
+
+        // every type may set `unique` or `cardinality`, which are
+        // read as the unique and cardinality types wrapping it
+        let options = [
+            (
+                "unique",
+                InstanceType::Boolean,
+                "(optional) Whether values are never repeated within a run.",
+            ),
+            (
+                "cardinality",
+                InstanceType::Integer,
+                "(optional) The number of distinct values, drawn from a pool sampled once.",
+            ),
+        ];
+        if let Some(JsonSchemaObject::Object(data_type)) = spec.definitions.get_mut("DataType") {
+            for variant in data_type.subschemas().one_of.iter_mut().flatten() {
+                if let JsonSchemaObject::Object(variant) = variant {
+                    for (name, tpe, description) in options {
+                        let mut option = SchemaObject {
+                            instance_type: Some(tpe.into()),
+                            ..Default::default()
+                        };
+                        option.metadata().description = Some(description.to_owned());
+                        if tpe == InstanceType::Integer {
+                            option.number().minimum = Some(1.0);
+                        }
+                        variant
+                            .object()
+                            .properties
+                            .insert(name.to_owned(), option.into());
+                    }
+                }
+            }
+        }
+        spec
+    }
+
     /// An iterator visiting all field-datatype pairs in arbitrary order.
     /// The iterator element type is `(&'a str, &'a DataType<'a>)`.
     ///
@@ -24,7 +125,7 @@ impl<'a> Schema<'a> {
     /// let mut schema = Schema::default();
     /// schema
     ///     .with_field("bool_field", DataType::Boolean)
-    ///     .with_field("int_field", DataType::Range { from: 1, to: 3 });
+    ///     .with_field("int_field", DataType::Range { from: 1, to: 3, selection: None });
     ///
     /// for (field, data_type) in schema.iter() {
     ///     println!("field: {} data_type: {}", field, data_type);
@@ -32,8 +133,8 @@ impl<'a> Schema<'a> {
     /// ```
     pub fn iter(&'a self) -> impl Iterator<Item = (&'a str, &'a DataType<'a>)> {
         let mut v = Vec::from_iter(self.fields.iter());
-        v.sort_by(|(left, _), (right, _)| left.cmp(right));
-        v.into_iter().map(|(name, tpe)| (*name, tpe))
+        v.sort_by_key(|(name, _)| *name);
+        v.into_iter().map(|(name, tpe)| (name.as_ref(), tpe))
     }
 
     /// Adds a new field to the [Schema]. If a field
@@ -49,8 +150,94 @@ impl<'a> Schema<'a> {
     /// let mut schema = Schema::default();
     /// schema.with_field("bool_field", DataType::Boolean);
     ///```
-    pub fn with_field(&mut self, name: &'a str, dt: DataType<'a>) -> &mut Self {
-        self.fields.insert(name, dt);
+    pub fn with_field<N>(&mut self, name: N, dt: DataType<'a>) -> &mut Self
+    where
+        N: Into<Cow<'a, str>>,
+    {
+        self.fields.insert(name.into(), dt);
+        self.resolve();
+        self
+    }
+
+    /// Adds a new pool to the [Schema], whose members are referenced
+    /// by [DataType::Member] fields. If a pool with the given name
+    /// already exists it will be overridden.
+    ///
+    /// # Examples
+    ///
+    ///```
+    /// use data_gen_lib::data_type::DataType;
+    /// use data_gen_lib::pool::Pool;
+    /// use data_gen_lib::schema::Schema;
+    ///
+    /// let mut users = Schema::default();
+    /// users.with_field("id", DataType::Uuid);
+    ///
+    /// let mut schema = Schema::default();
+    /// schema
+    ///     .with_pool("users", Pool { count: 100, schema: users, selection: None })
+    ///     .with_field("user_id", DataType::Member { pool: "users".into(), field: Some("id".into()), element: None });
+    ///```
+    pub fn with_pool<N>(&mut self, name: N, pool: Pool<'a>) -> &mut Self
+    where
+        N: Into<Cow<'a, str>>,
+    {
+        self.pools.insert(name.into(), pool);
+        self.resolve();
         self
     }
+
+    /// An iterator visiting all pools in order of their names.
+    pub fn pools(&self) -> impl Iterator<Item = (&str, &Pool<'a>)> {
+        self.pools.iter().map(|(name, pool)| (name.as_ref(), pool))
+    }
+
+    /// Sets the data types of the values referenced by member fields.
+    fn resolve(&mut self) {
+        for dt in self.fields.values_mut() {
+            resolve(dt, &self.pools);
+        }
+    }
+}
+
+fn resolve<'a>(dt: &mut DataType<'a>, pools: &BTreeMap<Cow<'a, str>, Pool<'a>>) {
+    match dt {
+        DataType::Member {
+            pool,
+            field,
+            element,
+        } => {
+            // Members of unknown pools keep their type, such
+            // as when copied to the schema of another table.
+            if let Some(pool) = pools.get(pool) {
+                *element = match field {
+                    Some(field) => pool.schema.fields.get(field).cloned(),
+                    None => Some(DataType::Object {
+                        fields: pool.schema.fields.clone(),
+                    }),
+                }
+                .map(Box::new);
+            }
+        }
+        DataType::Array { element, .. }
+        | DataType::Cardinality { element, .. }
+        | DataType::Nullable { element, .. }
+        | DataType::Optional { element, .. }
+        | DataType::Unique { element } => resolve(element, pools),
+        DataType::Map { keys, values, .. } => {
+            resolve(keys, pools);
+            resolve(values, pools);
+        }
+        DataType::Object { fields } => {
+            for dt in fields.values_mut() {
+                resolve(dt, pools);
+            }
+        }
+        DataType::Union { variants } => {
+            for dt in variants {
+                resolve(dt, pools);
+            }
+        }
+        _ => {}
+    }
 }
@@ -0,0 +1,273 @@
+//! Skewed selections of keys, such as the values of a range, the
+//! options of a one of or the members of a pool, to simulate hot keys.
+
+use rand::distributions::Distribution;
+use rand::Rng;
+use rand_distr::Zipf;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// How keys are selected. Keys are ranked from the hottest, which
+/// is the first key, unless the hot keys shift during the run.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Selection {
+    /// Every key is equally likely.
+    #[default]
+    Uniform,
+
+    /// The `n`th key is selected with a probability proportional
+    /// to `1 / n^exponent`, so that a few hot keys are selected
+    /// far more often than the rest.
+    Zipf {
+        /// The skew of the selection, which is uniform when 0.
+        exponent: f64,
+        /// (optional) The number of values after which the hot keys
+        /// shift to other keys. By default the hot keys never shift.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        shift: Option<u64>,
+    },
+
+    /// A share of the values is spread evenly over the top keys,
+    /// and the remaining values over the other keys.
+    TopK {
+        /// The number of hot keys.
+        keys: u64,
+        /// The share of the values, between 0 and 1, of the hot keys.
+        share: f64,
+        /// (optional) The number of values after which the hot keys
+        /// shift to other keys. By default the hot keys never shift.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        shift: Option<u64>,
+    },
+}
+
+/// A sampler of the rank of a key.
+enum Ranks {
+    Uniform,
+    Zipf(Zipf<f64>),
+    TopK { keys: u64, share: f64 },
+}
+
+/// A sampler of the index of a key among `count` keys, which
+/// also counts how often each key is used by the values.
+pub(crate) struct Selector {
+    count: u64,
+    ranks: Ranks,
+    shift: Option<u64>,
+    /// The index of the hottest key.
+    offset: Cell<u64>,
+    selected: Cell<u64>,
+    frequencies: RefCell<HashMap<u64, u64>>,
+}
+
+impl Selector {
+    /// Creates a selector of `count` keys, or `None` without keys.
+    pub(crate) fn new(selection: &Selection, count: u64) -> Option<Selector> {
+        if count == 0 {
+            return None;
+        }
+
+        let (ranks, shift) = match selection {
+            Selection::Uniform => (Ranks::Uniform, None),
+            Selection::Zipf { exponent, shift } => (
+                Zipf::new(count, exponent.max(0.0))
+                    .map(Ranks::Zipf)
+                    .unwrap_or(Ranks::Uniform),
+                *shift,
+            ),
+            Selection::TopK { keys, share, shift } => (
+                Ranks::TopK {
+                    keys: (*keys).clamp(1, count),
+                    share: share.clamp(0.0, 1.0),
+                },
+                *shift,
+            ),
+        };
+
+        Some(Selector {
+            count,
+            ranks,
+            shift: shift.filter(|shift| *shift > 0),
+            offset: Cell::new(0),
+            selected: Cell::new(0),
+            frequencies: RefCell::default(),
+        })
+    }
+
+    /// The number of keys.
+    pub(crate) fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Counts a value using the key of the given index.
+    pub(crate) fn record(&self, index: u64) {
+        *self.frequencies.borrow_mut().entry(index).or_default() += 1;
+    }
+
+    /// The number of values using each key, by index, from
+    /// the most to the least used key.
+    pub(crate) fn frequencies(&self) -> Vec<(u64, u64)> {
+        let mut frequencies: Vec<_> = self
+            .frequencies
+            .borrow()
+            .iter()
+            .map(|(index, n)| (*index, *n))
+            .collect();
+        frequencies.sort_by(|(a, n), (b, m)| m.cmp(n).then(a.cmp(b)));
+        frequencies
+    }
+}
+
+impl Distribution<u64> for Selector {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        let selected = self.selected.get();
+        if self
+            .shift
+            .is_some_and(|shift| selected > 0 && selected.is_multiple_of(shift))
+        {
+            self.offset.set(rng.gen_range(0..self.count));
+        }
+        self.selected.set(selected + 1);
+
+        let rank = match &self.ranks {
+            Ranks::Uniform => rng.gen_range(0..self.count),
+            Ranks::Zipf(zipf) => zipf.sample(rng) as u64 - 1,
+            Ranks::TopK { keys, share } => {
+                if *keys == self.count || rng.gen_bool(*share) {
+                    rng.gen_range(0..*keys)
+                } else {
+                    rng.gen_range(*keys..self.count)
+                }
+            }
+        };
+        (rank + self.offset.get()) % self.count
+    }
+}
+
+/// The keys selected for a field, and how often each key was selected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyFrequencies {
+    /// The path of the field, such as `user_id` or `order.customer_id`.
+    pub field: String,
+    /// The keys and their number of values, from the most selected key.
+    pub keys: Vec<(Value, u64)>,
+}
+
+impl KeyFrequencies {
+    /// The total number of values.
+    pub fn values(&self) -> u64 {
+        self.keys.iter().map(|(_, n)| n).sum()
+    }
+}
+
+impl Display for KeyFrequencies {
+    /// A summary of the 10 most selected keys.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let values = self.values();
+        let share = |n: u64| 100.0 * n as f64 / values.max(1) as f64;
+
+        write!(
+            f,
+            "{}: {} values of {} distinct keys",
+            self.field,
+            values,
+            self.keys.len()
+        )?;
+        let top = &self.keys[..self.keys.len().min(10)];
+        for (key, n) in top {
+            write!(f, "\n  {}: {} ({:.1}%)", key, n, share(*n))?;
+        }
+        if self.keys.len() > top.len() {
+            let n = top.iter().map(|(_, n)| n).sum();
+            write!(
+                f,
+                "\n  the top {} keys have {:.1}% of the values",
+                top.len(),
+                share(n)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::DataGenerator;
+    use crate::schema::Schema;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde_json::{json, Value};
+
+    fn frequencies(source: &str, values: usize) -> Vec<(Value, u64)> {
+        let schema: Schema = serde_json::from_str(source).unwrap();
+        let gen = DataGenerator::new(&schema);
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..values {
+            let _: Value = rng.sample(&gen);
+        }
+
+        let mut frequencies = gen.key_frequencies();
+        assert_eq!(1, frequencies.len());
+        assert_eq!("key", frequencies[0].field);
+        assert_eq!(values as u64, frequencies[0].values());
+        frequencies.remove(0).keys
+    }
+
+    #[test]
+    fn test_zipf_selection() {
+        let keys = frequencies(
+            r#"{ "key": { "type": "range", "from": 10, "to": 1010, "selection": { "type": "zipf", "exponent": 1.5 } } }"#,
+            10000,
+        );
+
+        // The first key is the hottest, and P(1) = 1 / zeta(1.5) ~ 38%
+        assert_eq!(json!(10), keys[0].0);
+        assert!((3400..4300).contains(&keys[0].1), "{:?}", keys[0]);
+        assert!(keys
+            .iter()
+            .all(|(key, _)| (10..1010).contains(&key.as_i64().unwrap())));
+    }
+
+    #[test]
+    fn test_top_k_selection() {
+        let keys = frequencies(
+            r#"{ "key": { "type": "one_of", "options": ["a", "b", "c", "d", "e"], "selection": { "type": "top_k", "keys": 2, "share": 0.8 } } }"#,
+            10000,
+        );
+
+        let hot: u64 = keys
+            .iter()
+            .filter(|(key, _)| *key == "a" || *key == "b")
+            .map(|(_, n)| n)
+            .sum();
+        assert!((7700..8300).contains(&hot), "{:?}", keys);
+        assert_eq!(5, keys.len());
+    }
+
+    #[test]
+    fn test_shifting_hot_keys() {
+        let keys = frequencies(
+            r#"{ "key": { "type": "range", "from": 0, "to": 1000000, "selection": { "type": "top_k", "keys": 1, "share": 1.0, "shift": 100 } } }"#,
+            1000,
+        );
+
+        // Every 100 values a single hot key takes all of them
+        assert_eq!(10, keys.len());
+        assert!(keys.iter().all(|(_, n)| *n == 100));
+    }
+
+    #[test]
+    fn test_fields_without_selection_are_not_reported() {
+        let schema: Schema =
+            serde_json::from_str(r#"{ "key": { "type": "range", "from": 0, "to": 10 } }"#).unwrap();
+        let gen = DataGenerator::new(&schema);
+        let _: Value = rand::thread_rng().sample(&gen);
+
+        assert!(gen.key_frequencies().is_empty());
+    }
+}
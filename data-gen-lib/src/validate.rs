@@ -0,0 +1,965 @@
+use crate::data_type::DataType;
+use crate::interpolator::Interpolator;
+use crate::physical::physical_types::decimal_range;
+use crate::regex_pattern::RegexPattern;
+use crate::schema::Schema;
+use chrono::format::{Item, StrftimeItems};
+use schemars::schema_for;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// The names of all valid values for the `type` tag of a [DataType],
+/// read from its JSON Schema so that they follow the variants.
+fn types() -> Vec<String> {
+    let spec = schema_for!(DataType);
+    let mut types: Vec<String> = spec
+        .schema
+        .subschemas
+        .iter()
+        .flat_map(|subschemas| subschemas.one_of.iter().flatten())
+        .filter_map(|variant| {
+            let tag = variant
+                .clone()
+                .into_object()
+                .object?
+                .properties
+                .remove("type")?;
+            let tag = tag.into_object().enum_values?.pop()?;
+            tag.as_str().map(str::to_owned)
+        })
+        .collect();
+    types.sort();
+    types
+}
+
+/// The keys of each variant of a [DataType] by its `type` tag, read
+/// from its JSON Schema, along with `unique` and `cardinality` which
+/// every type accepts.
+fn keys() -> HashMap<String, Vec<String>> {
+    let spec = schema_for!(DataType);
+    spec.schema
+        .subschemas
+        .iter()
+        .flat_map(|subschemas| subschemas.one_of.iter().flatten())
+        .filter_map(|variant| {
+            let properties = variant.clone().into_object().object?.properties;
+            let tag = properties
+                .get("type")?
+                .clone()
+                .into_object()
+                .enum_values?
+                .pop()?;
+            let mut keys: Vec<String> = properties
+                .into_keys()
+                .chain(["cardinality".to_owned(), "unique".to_owned()])
+                .collect();
+            keys.sort();
+            Some((tag.as_str()?.to_owned(), keys))
+        })
+        .collect()
+}
+
+/// A single problem found within a schema, along with
+/// the location of the offending value in the source text.
+/// Lines and columns are 1-based.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}:{}: {}", self.line, self.column, self.message)
+        } else {
+            write!(
+                f,
+                "{}:{}: {}: {}",
+                self.line, self.column, self.path, self.message
+            )
+        }
+    }
+}
+
+/// Validates the source text of a [Schema](crate::schema::Schema)
+/// and returns every problem found. Unlike deserialization, which
+/// stops at the first error, validation keeps going so that all
+/// issues can be fixed at once. An empty result means the schema
+/// is valid.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::validate::validate;
+///
+/// let diagnostics = validate(r#"{ "id": { "type": "range", "from": 10, "to": 1 } }"#);
+/// assert_eq!(1, diagnostics.len());
+/// assert_eq!("id", diagnostics[0].path);
+/// ```
+pub fn validate(source: &str) -> Vec<Diagnostic> {
+    let value: Value = match serde_json::from_str(source) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![Diagnostic {
+                path: String::new(),
+                line: e.line(),
+                column: e.column(),
+                message: format!("malformed JSON: {}", e),
+            }]
+        }
+    };
+
+    let mut validator = Validator {
+        positions: Positions::index(source),
+        diagnostics: Vec::new(),
+        pools: HashMap::new(),
+        keys: keys(),
+    };
+
+    match &value {
+        Value::Object(fields) => validator.schema("", fields),
+        _ => validator.report("", "a schema must be a JSON object of field names to types"),
+    }
+
+    validator.diagnostics
+}
+
+/// Validates a [Schema] that was already deserialized or imported, such
+/// as a schema imported from another format or the schema of a table of
+/// a dataset. The locations of the diagnostics refer to the schema
+/// printed as pretty JSON.
+///
+/// # Examples
+///
+/// ```
+/// use data_gen_lib::data_type::DataType;
+/// use data_gen_lib::schema::Schema;
+/// use data_gen_lib::validate::validate_schema;
+///
+/// let mut schema = Schema::default();
+/// schema.with_field("id", DataType::Range { from: 10, to: 1, selection: None });
+///
+/// let diagnostics = validate_schema(&schema);
+/// assert_eq!("id", diagnostics[0].path);
+/// ```
+pub fn validate_schema(schema: &Schema) -> Vec<Diagnostic> {
+    match serde_json::to_string_pretty(schema) {
+        Ok(source) => validate(&source),
+        Err(e) => vec![Diagnostic {
+            path: String::new(),
+            line: 1,
+            column: 1,
+            message: format!("failed to serialize schema: {}", e),
+        }],
+    }
+}
+
+struct Validator {
+    positions: Positions,
+    diagnostics: Vec<Diagnostic>,
+    /// The field names of the members of the pools of the schema being validated.
+    pools: HashMap<String, Vec<String>>,
+    /// The keys of each type, see [keys].
+    keys: HashMap<String, Vec<String>>,
+}
+
+impl Validator {
+    fn report(&mut self, path: &str, message: impl Into<String>) {
+        let (line, column) = self.positions.get(path);
+        self.diagnostics.push(Diagnostic {
+            path: path.to_string(),
+            line,
+            column,
+            message: message.into(),
+        })
+    }
+
+    /// Reports every key of a type which is not one of the keys of
+    /// the type, such as a misspelled key, at the position of the key.
+    fn unknown_keys(&mut self, path: &str, fields: &Map<String, Value>, keys: &[String]) {
+        for key in fields.keys().filter(|key| !keys.contains(key)) {
+            let path = child(path, key);
+            let (line, column) = self.positions.key(&path);
+            self.diagnostics.push(Diagnostic {
+                path,
+                line,
+                column,
+                message: format!(
+                    "unknown key `{}`, expected one of: {}",
+                    key,
+                    keys.join(", ")
+                ),
+            })
+        }
+    }
+
+    /// Validates the fields of a schema, along with its pools, whose
+    /// members are only referenced by the fields of the schema.
+    fn schema(&mut self, path: &str, fields: &Map<String, Value>) {
+        let mut pools = HashMap::new();
+        match fields.get("$pools") {
+            Some(Value::Object(definitions)) => {
+                let path = child(path, "$pools");
+                for (name, pool) in definitions {
+                    let members = self.pool(&child(&path, name), pool);
+                    pools.insert(name.clone(), members);
+                }
+            }
+            Some(_) => self.report(&child(path, "$pools"), "`$pools` must be an object"),
+            None => {}
+        }
+
+        let outer = std::mem::replace(&mut self.pools, pools);
+        for (name, dt) in fields {
+            if name != "$pools" {
+                self.data_type(&child(path, name), dt);
+            }
+        }
+        self.pools = outer;
+    }
+
+    /// Validates a pool and returns the field names of its members.
+    fn pool(&mut self, path: &str, value: &Value) -> Vec<String> {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => {
+                self.report(path, "expected a pool definition object");
+                return Vec::new();
+            }
+        };
+
+        let keys = ["count", "schema", "selection"].map(str::to_owned);
+        self.unknown_keys(path, fields, &keys);
+        self.cardinality(path, fields, "count");
+        self.selection(path, fields);
+
+        match self.required(path, fields, "schema") {
+            Some(Value::Object(schema)) => {
+                self.schema(&child(path, "schema"), schema);
+                schema
+                    .keys()
+                    .filter(|name| *name != "$pools")
+                    .cloned()
+                    .collect()
+            }
+            Some(_) => {
+                self.report(&child(path, "schema"), "`schema` must be an object");
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Validates the optional selection of keys of a type or pool.
+    fn selection(&mut self, path: &str, fields: &Map<String, Value>) {
+        let selection = match fields.get("selection") {
+            None | Some(Value::Null) => return,
+            Some(Value::Object(selection)) => selection,
+            Some(_) => {
+                return self.report(&child(path, "selection"), "`selection` must be an object")
+            }
+        };
+
+        let path = child(path, "selection");
+        match selection.get("type").and_then(Value::as_str) {
+            Some("uniform") => return,
+            Some("zipf") => {
+                if let Some(exponent) = self.float(&path, selection, "exponent") {
+                    if exponent < 0.0 {
+                        self.report(
+                            &child(&path, "exponent"),
+                            "`exponent` must be a non-negative number",
+                        );
+                    }
+                }
+            }
+            Some("top_k") => {
+                self.cardinality(&path, selection, "keys");
+                self.probability(&path, selection, "share");
+            }
+            _ => {
+                return self.report(
+                    &child(&path, "type"),
+                    "expected a selection of type `uniform`, `zipf` or `top_k`",
+                )
+            }
+        }
+        if selection.contains_key("shift") {
+            self.cardinality(&path, selection, "shift");
+        }
+    }
+
+    fn data_type(&mut self, path: &str, value: &Value) {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => return self.report(path, "expected a type definition object"),
+        };
+
+        let tpe = match fields.get("type") {
+            Some(Value::String(tpe)) => tpe.as_str(),
+            Some(_) => return self.report(&child(path, "type"), "`type` must be a string"),
+            None => return self.report(path, "missing field `type`"),
+        };
+        if let Some(keys) = self.keys.get(tpe).cloned() {
+            self.unknown_keys(path, fields, &keys);
+        }
+
+        match fields.get("unique") {
+            None | Some(Value::Bool(_)) => {}
+            Some(_) => self.report(&child(path, "unique"), "`unique` must be a boolean"),
+        }
+        if fields.contains_key("cardinality") {
+            self.cardinality(path, fields, "cardinality");
+        }
+
+        match tpe {
+            "array" => {
+                if let Some(element) = self.required(path, fields, "element") {
+                    self.data_type(&child(path, "element"), element);
+                }
+                let min_size = match fields.get("min_size") {
+                    None | Some(Value::Null) => Some(1),
+                    Some(_) => self.unsigned(path, fields, "min_size"),
+                };
+                if let (Some(min_size), Some(size)) =
+                    (min_size, self.unsigned(path, fields, "size"))
+                {
+                    if min_size > size {
+                        self.report(
+                            &child(path, "size"),
+                            format!("array size must be at least {}", min_size),
+                        );
+                    }
+                }
+            }
+            "decimal" => {
+                let precision = self.unsigned(path, fields, "precision");
+                let scale = self.unsigned(path, fields, "scale");
+                if let (Some(precision), Some(scale)) = (precision, scale) {
+                    if precision == 0 || precision > 38 {
+                        self.report(
+                            &child(path, "precision"),
+                            "`precision` must be between 1 and 38",
+                        );
+                    } else if scale > precision {
+                        self.report(
+                            &child(path, "scale"),
+                            "`scale` must not be greater than `precision`",
+                        );
+                    } else {
+                        let min = self.bound(path, fields, "min");
+                        let max = self.bound(path, fields, "max");
+                        if decimal_range(precision, scale, min, max).is_empty() {
+                            self.report(
+                                path,
+                                format!(
+                                    "no decimal of precision {} and scale {} is within the bounds",
+                                    precision, scale
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+            "float" => {
+                let from = self.float(path, fields, "from");
+                let to = self.float(path, fields, "to");
+                if let (Some(from), Some(to)) = (from, to) {
+                    if from >= to {
+                        self.report(
+                            path,
+                            format!(
+                                "range lower bound {} must be less than upper bound {}",
+                                from, to
+                            ),
+                        );
+                    }
+                }
+            }
+            "generator" => {
+                if let Some(format) = self.string(path, fields, "format") {
+                    if let Err(e) = Interpolator::try_from(format) {
+                        self.report(&child(path, "format"), e);
+                    }
+                }
+            }
+            "literal" => {
+                self.string(path, fields, "value");
+            }
+            "map" => {
+                if let Some(keys) = self.required(path, fields, "keys") {
+                    self.data_type(&child(path, "keys"), keys);
+                }
+                if let Some(values) = self.required(path, fields, "values") {
+                    self.data_type(&child(path, "values"), values);
+                }
+                if let Some(0) = self.unsigned(path, fields, "size") {
+                    self.report(&child(path, "size"), "map size must be at least 1");
+                }
+            }
+            "member" => {
+                if let Some(pool) = self.string(path, fields, "pool") {
+                    match self.pools.get(pool) {
+                        Some(members) => match fields.get("field") {
+                            None | Some(Value::Null) => {}
+                            Some(Value::String(field)) if members.contains(field) => {}
+                            Some(Value::String(field)) => self.report(
+                                &child(path, "field"),
+                                format!("the members of pool `{}` have no field `{}`", pool, field),
+                            ),
+                            Some(_) => {
+                                self.report(&child(path, "field"), "`field` must be a string")
+                            }
+                        },
+                        None => {
+                            self.report(&child(path, "pool"), format!("unknown pool `{}`", pool))
+                        }
+                    }
+                }
+            }
+            "nullable" => {
+                if let Some(element) = self.required(path, fields, "element") {
+                    self.data_type(&child(path, "element"), element);
+                }
+                self.probability(path, fields, "null_rate");
+            }
+            "optional" => {
+                if let Some(element) = self.required(path, fields, "element") {
+                    self.data_type(&child(path, "element"), element);
+                }
+                self.probability(path, fields, "missing_rate");
+            }
+            "object" => match self.required(path, fields, "fields") {
+                Some(Value::Object(inner)) => {
+                    let path = child(path, "fields");
+                    for (name, dt) in inner {
+                        self.data_type(&child(&path, name), dt);
+                    }
+                }
+                Some(_) => self.report(&child(path, "fields"), "`fields` must be an object"),
+                None => {}
+            },
+            "one_of" => match self.required(path, fields, "options") {
+                Some(Value::Array(options)) => {
+                    if options.is_empty() {
+                        self.report(&child(path, "options"), "`options` must not be empty");
+                    }
+                    let options_path = child(path, "options");
+                    for (i, option) in options.iter().enumerate() {
+                        if !option.is_string() {
+                            self.report(&index(&options_path, i), "option must be a string");
+                        }
+                    }
+
+                    match fields.get("weights") {
+                        Some(Value::Array(weights)) => {
+                            let weights_path = child(path, "weights");
+                            if weights.len() != options.len() {
+                                self.report(
+                                    &weights_path,
+                                    format!(
+                                        "expected {} weights, one for each option, found {}",
+                                        options.len(),
+                                        weights.len()
+                                    ),
+                                );
+                            }
+                            for (i, weight) in weights.iter().enumerate() {
+                                if !weight.as_f64().is_some_and(|weight| weight >= 0.0) {
+                                    self.report(
+                                        &index(&weights_path, i),
+                                        "weight must be a non-negative number",
+                                    );
+                                }
+                            }
+                            if weights.iter().all(|weight| weight.as_f64() == Some(0.0)) {
+                                self.report(&weights_path, "at least one weight must be positive");
+                            }
+                        }
+                        Some(_) => {
+                            self.report(&child(path, "weights"), "`weights` must be an array")
+                        }
+                        None => {}
+                    }
+                    if fields.contains_key("selection") && fields.contains_key("weights") {
+                        self.report(
+                            &child(path, "selection"),
+                            "`selection` and `weights` can't both be set",
+                        );
+                    }
+                    self.selection(path, fields);
+                }
+                Some(_) => self.report(&child(path, "options"), "`options` must be an array"),
+                None => {}
+            },
+            "range" => {
+                self.selection(path, fields);
+                let from = self.integer(path, fields, "from");
+                let to = self.integer(path, fields, "to");
+                if let (Some(from), Some(to)) = (from, to) {
+                    if from >= to {
+                        self.report(
+                            path,
+                            format!(
+                                "range lower bound {} must be less than upper bound {}",
+                                from, to
+                            ),
+                        );
+                    }
+                }
+            }
+            "regex" => {
+                if let Some(pattern) = self.string(path, fields, "pattern") {
+                    if let Err(e) = RegexPattern::try_from(pattern.to_string()) {
+                        self.report(&child(path, "pattern"), e.to_string());
+                    }
+                }
+            }
+            "timestamp" => match fields.get("format") {
+                None | Some(Value::Null) => {}
+                Some(Value::String(format)) => {
+                    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                        self.report(
+                            &child(path, "format"),
+                            format!("invalid timestamp format `{}`", format),
+                        );
+                    }
+                }
+                Some(_) => self.report(&child(path, "format"), "`format` must be a string"),
+            },
+            "union" => match self.required(path, fields, "variants") {
+                Some(Value::Array(variants)) => {
+                    if variants.is_empty() {
+                        self.report(&child(path, "variants"), "`variants` must not be empty");
+                    }
+                    let path = child(path, "variants");
+                    for (i, variant) in variants.iter().enumerate() {
+                        self.data_type(&index(&path, i), variant);
+                    }
+                }
+                Some(_) => self.report(&child(path, "variants"), "`variants` must be an array"),
+                None => {}
+            },
+            "big_int" | "boolean" | "email" | "integer" | "phone_number" | "serial"
+            | "small_int" | "uuid" => {}
+            unknown => self.report(
+                &child(path, "type"),
+                format!(
+                    "unknown type `{}`, expected one of: {}",
+                    unknown,
+                    types().join(", ")
+                ),
+            ),
+        }
+    }
+
+    fn required<'v>(
+        &mut self,
+        path: &str,
+        fields: &'v Map<String, Value>,
+        name: &str,
+    ) -> Option<&'v Value> {
+        let value = fields.get(name);
+        if value.is_none() {
+            self.report(path, format!("missing field `{}`", name));
+        }
+        value
+    }
+
+    fn string<'v>(
+        &mut self,
+        path: &str,
+        fields: &'v Map<String, Value>,
+        name: &str,
+    ) -> Option<&'v str> {
+        match self.required(path, fields, name)? {
+            Value::String(s) => Some(s),
+            _ => {
+                self.report(&child(path, name), format!("`{}` must be a string", name));
+                None
+            }
+        }
+    }
+
+    fn integer(&mut self, path: &str, fields: &Map<String, Value>, name: &str) -> Option<i64> {
+        let value = self.required(path, fields, name)?.as_i64();
+        if value.is_none() {
+            self.report(&child(path, name), format!("`{}` must be an integer", name));
+        }
+        value
+    }
+
+    fn float(&mut self, path: &str, fields: &Map<String, Value>, name: &str) -> Option<f64> {
+        let value = self.required(path, fields, name)?.as_f64();
+        if value.is_none() {
+            self.report(&child(path, name), format!("`{}` must be a number", name));
+        }
+        value
+    }
+
+    /// An optional number, such as the bound of a range.
+    fn bound(&mut self, path: &str, fields: &Map<String, Value>, name: &str) -> Option<f64> {
+        match fields.get(name) {
+            None | Some(Value::Null) => None,
+            Some(_) => self.float(path, fields, name),
+        }
+    }
+
+    fn probability(&mut self, path: &str, fields: &Map<String, Value>, name: &str) {
+        if let Some(rate) = self.float(path, fields, name) {
+            if !(0.0..=1.0).contains(&rate) {
+                self.report(
+                    &child(path, name),
+                    format!("`{}` must be between 0 and 1", name),
+                );
+            }
+        }
+    }
+
+    fn cardinality(&mut self, path: &str, fields: &Map<String, Value>, name: &str) {
+        if let Some(0) = self.unsigned(path, fields, name) {
+            self.report(&child(path, name), format!("`{}` must be at least 1", name));
+        }
+    }
+
+    fn unsigned(&mut self, path: &str, fields: &Map<String, Value>, name: &str) -> Option<u32> {
+        let value = self
+            .required(path, fields, name)?
+            .as_u64()
+            .and_then(|n| u32::try_from(n).ok());
+        if value.is_none() {
+            self.report(
+                &child(path, name),
+                format!("`{}` must be a non-negative integer", name),
+            );
+        }
+        value
+    }
+}
+
+fn child(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn index(path: &str, i: usize) -> String {
+    format!("{}[{}]", path, i)
+}
+
+/// The line and column of every value in a JSON
+/// document, keyed by the same paths used in
+/// [Diagnostic]s.
+struct Positions {
+    values: HashMap<String, (usize, usize)>,
+    /// The positions of the keys of the values of objects.
+    keys: HashMap<String, (usize, usize)>,
+}
+
+impl Positions {
+    /// Indexes a document that is already
+    /// known to be well-formed JSON.
+    fn index(source: &str) -> Self {
+        let mut scanner = Scanner {
+            chars: source.chars().collect(),
+            offset: 0,
+            line: 1,
+            column: 1,
+            positions: HashMap::new(),
+            keys: HashMap::new(),
+        };
+        scanner.value(String::new());
+        Positions {
+            values: scanner.positions,
+            keys: scanner.keys,
+        }
+    }
+
+    /// Returns the position of the key of the value at the given
+    /// path, or of the value when it is not the value of a key.
+    fn key(&self, path: &str) -> (usize, usize) {
+        match self.keys.get(path) {
+            Some(position) => *position,
+            None => self.get(path),
+        }
+    }
+
+    /// Returns the position of the value at the given path, or
+    /// of its nearest ancestor when the path does not exist.
+    fn get(&self, path: &str) -> (usize, usize) {
+        let mut path = path;
+        loop {
+            if let Some(position) = self.values.get(path) {
+                return *position;
+            }
+            match path.rfind(['.', '[']) {
+                Some(i) => path = &path[..i],
+                None => return self.values.get("").copied().unwrap_or((1, 1)),
+            }
+        }
+    }
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    offset: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+    keys: HashMap<String, (usize, usize)>,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn value(&mut self, path: String) {
+        self.whitespace();
+        self.positions
+            .insert(path.clone(), (self.line, self.column));
+
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.whitespace();
+                    match self.peek() {
+                        Some('"') => {
+                            let position = (self.line, self.column);
+                            let key = child(&path, &self.string());
+                            self.keys.insert(key.clone(), position);
+                            self.whitespace();
+                            self.bump(); // ':'
+                            self.value(key);
+                        }
+                        Some(',') => {
+                            self.bump();
+                        }
+                        _ => {
+                            self.bump(); // '}'
+                            break;
+                        }
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut i = 0;
+                loop {
+                    self.whitespace();
+                    match self.peek() {
+                        Some(']') | None => {
+                            self.bump();
+                            break;
+                        }
+                        Some(',') => {
+                            self.bump();
+                        }
+                        _ => {
+                            self.value(index(&path, i));
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while matches!(self.peek(), Some(c) if !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+                {
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let start = self.offset;
+        self.bump(); // opening quote
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '"' => break,
+                _ => {}
+            }
+        }
+        let raw: String = self.chars[start..self.offset].iter().collect();
+        serde_json::from_str(&raw).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{types, validate, validate_schema, Diagnostic};
+    use crate::data_type::DataType;
+    use crate::schema::Schema;
+
+    #[test]
+    fn test_valid_schema() {
+        let source = include_str!("../../schema.json");
+        assert_eq!(Vec::<Diagnostic>::new(), validate(source));
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let source = r##"{
+  "id": { "type": "range", "from": 10, "to": 1 },
+  "name": { "type": "strng" },
+  "website": { "type": "regex", "pattern": "[a-z" },
+  "drink": { "type": "one_of", "options": ["tea", "milk"], "weights": [1, -1] },
+  "nested": {
+    "type": "object",
+    "fields": {
+      "hero": { "type": "generator", "format": "#{ancient.villain}" },
+      "tags": { "type": "array", "element": { "type": "boolean" } }
+    }
+  }
+}"##;
+
+        let diagnostics = validate(source);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.path.as_str(), d.line, d.column))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("drink.weights[1]", 5, 75),
+                ("id", 2, 9),
+                ("name.type", 3, 21),
+                ("nested.fields.hero.format", 9, 48),
+                ("nested.fields.tags", 10, 15),
+                ("website.pattern", 4, 44),
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn test_unique_and_cardinality() {
+        let source = r#"{
+  "id": { "type": "serial", "unique": "yes" },
+  "user": { "type": "uuid", "cardinality": 0 },
+  "country": { "type": "strng", "cardinality": 10 }
+}"#;
+
+        let diagnostics = validate(source);
+        let found: Vec<_> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+
+        assert_eq!(vec!["country.type", "id.unique", "user.cardinality"], found);
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = r#"{
+  "$pools": { "users": { "count": 5, "schma": {} } },
+  "tags": { "type": "array", "element": { "type": "boolean" }, "size": 3, "min_sise": 1 },
+  "id": { "type": "serial", "uniqe": true }
+}"#;
+
+        let diagnostics = validate(source);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.path.as_str(), d.line, d.column))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("$pools.users.schma", 2, 38),
+                ("$pools.users", 2, 24),
+                ("id.uniqe", 4, 29),
+                ("tags.min_sise", 3, 75),
+            ],
+            found
+        );
+        assert_eq!(
+            "unknown key `min_sise`, expected one of: cardinality, element, min_size, size, type, unique",
+            diagnostics[3].message
+        );
+    }
+
+    #[test]
+    fn test_selections() {
+        let source = r#"{
+  "id": { "type": "range", "from": 0, "to": 10, "selection": { "type": "zipf", "exponent": -1 } },
+  "region": {
+    "type": "one_of",
+    "options": ["eu", "us"],
+    "weights": [1, 2],
+    "selection": { "type": "top_k", "keys": 0, "share": 2, "shift": 0 }
+  },
+  "kind": { "type": "one_of", "options": ["a"], "selection": { "type": "normal" } }
+}"#;
+
+        let diagnostics = validate(source);
+        let found: Vec<_> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+
+        assert_eq!(
+            vec![
+                "id.selection.exponent",
+                "kind.selection.type",
+                "region.selection",
+                "region.selection.keys",
+                "region.selection.share",
+                "region.selection.shift",
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn test_types_follow_the_data_type() {
+        let types = types();
+        assert!(types.contains(&"phone_number".to_owned()));
+        assert!(types.contains(&"member".to_owned()));
+        assert!(types.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_validate_imported_schema() {
+        let mut schema = Schema::default();
+        schema
+            .with_field("ratio", DataType::Float { from: 1.0, to: 1.0 })
+            .with_field("id", DataType::Serial);
+
+        let diagnostics = validate_schema(&schema);
+        let found: Vec<_> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(vec!["ratio"], found);
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let diagnostics = validate("{ \"id\": ");
+        assert_eq!(1, diagnostics.len());
+        assert!(diagnostics[0].message.starts_with("malformed JSON"));
+    }
+}
@@ -1,37 +1,372 @@
-use anyhow::Result;
+use std::ops::Deref;
+
+use anyhow::{bail, Context, Result};
+use arrow::array::RecordBatch;
 use clap::Subcommand;
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::generator::DataGenerator;
 use data_gen_lib::schema::Schema;
+use rand::Rng;
 use serde_json::Value;
 
-use self::csv::CsvWriter;
+use self::avro::AvroWriter;
+use self::cbor::CborWriter;
+use self::csv::{CsvOptions, CsvWriter};
+use self::ipc::IpcWriter;
 use self::json::JsonWriter;
+use self::log::{LogOptions, LogWriter};
+use self::msgpack::MsgpackWriter;
+use self::parquet::{Compression, ParquetWriter};
+use self::protobuf::ProtobufWriter;
+use self::sql::{CopyWriter, Dialect, InsertWriter, SqlOptions};
+use self::template::{TemplateOptions, TemplateWriter};
+use self::xml::{XmlOptions, XmlWriter};
+use self::yaml::YamlWriter;
+use crate::sink::Sink;
 
+mod avro;
+mod cbor;
 mod csv;
+mod ipc;
 mod json;
+mod log;
+mod msgpack;
+mod parquet;
+mod protobuf;
+pub(crate) mod sql;
+mod template;
+mod xml;
+mod yaml;
 
 /// Set the output data format.
 #[derive(Subcommand)]
 pub enum Format {
-    /// Write values as CSV to stdout.
-    Csv,
+    /// Write record batches as an Arrow IPC stream.
+    Arrow {
+        /// Write the Arrow IPC file format instead of a stream.
+        #[clap(long)]
+        file: bool,
+
+        /// The number of rows per record batch.
+        #[clap(long, default_value = "8192")]
+        batch_size: usize,
+    },
+
+    /// Write values as Avro.
+    Avro {
+        /// Write single object encoded messages instead
+        /// of an object container file.
+        #[clap(long)]
+        single_object: bool,
+
+        /// An Avro schema (.avsc) to encode values with. By default
+        /// the Avro schema is derived from the data-gen schema.
+        #[clap(long)]
+        avro_schema: Option<String>,
+
+        /// The number of records per object container file block.
+        #[clap(long, default_value = "100")]
+        block_size: usize,
+    },
+
+    /// Write values as a sequence of CBOR data items.
+    Cbor,
+
+    /// Write values as a Postgres COPY FROM STDIN block.
+    Copy {
+        #[clap(flatten)]
+        options: SqlOptions,
+
+        /// Use the CSV format instead of the text format.
+        #[clap(long)]
+        csv: bool,
+    },
+
+    /// Write values as CSV.
+    Csv(CsvOptions),
+
+    /// Write values as SQL INSERT statements.
+    Insert {
+        #[clap(flatten)]
+        options: SqlOptions,
+
+        /// The SQL dialect to quote identifiers and values for.
+        #[clap(long, arg_enum, default_value = "postgres")]
+        dialect: Dialect,
+
+        /// The number of rows per INSERT statement.
+        #[clap(long, default_value = "100")]
+        batch_size: usize,
+    },
 
-    /// Write values as CSV to stdout.
+    /// Write values as JSON.
     Json {
         /// Pretty print the JSON records.
         #[clap(short, long)]
         pretty: bool,
     },
+
+    /// Write values as log lines.
+    Log(LogOptions),
+
+    /// Write values as a stream of MessagePack values.
+    Msgpack,
+
+    /// Write values to a Parquet file.
+    Parquet {
+        /// The path of the Parquet file to create,
+        /// the same as setting `--output`.
+        path: Option<String>,
+
+        /// The number of rows per row group.
+        #[clap(long, default_value = "10000")]
+        row_group_size: usize,
+
+        /// The compression codec for column chunks.
+        #[clap(long, arg_enum, default_value = "snappy")]
+        compression: Compression,
+    },
+
+    /// Write values as length-delimited Protobuf messages.
+    Protobuf {
+        /// The `.proto` file defining the message.
+        #[clap(long)]
+        proto: String,
+
+        /// The message to encode values as, which may be omitted
+        /// if the `.proto` file defines a single message.
+        #[clap(long)]
+        message: Option<String>,
+    },
+
+    /// Render each value with a Jinja like template.
+    Template(TemplateOptions),
+
+    /// Write values as elements of an XML document.
+    Xml(XmlOptions),
+
+    /// Write values as a stream of YAML documents.
+    Yaml,
 }
 
 pub trait Writer {
-    fn write(&self, v: Value) -> Result<()>;
+    fn write(&mut self, v: Value) -> Result<()>;
+
+    /// Flushes any buffered values, called
+    /// once after the last value is written.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer of columnar formats, which are written from
+/// record batches generated column-wise instead of values.
+pub trait BatchWriter {
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()>;
+
+    /// Called once after the last batch is written.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Generated records, which are values, or a record
+/// batch for formats generated column-wise.
+pub enum Records {
+    Values(Vec<Value>),
+    Batch(RecordBatch),
+}
+
+impl Records {
+    pub fn len(&self) -> usize {
+        match self {
+            Records::Values(values) => values.len(),
+            Records::Batch(batch) => batch.num_rows(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the records at the given index, returning
+    /// the records from that index onwards.
+    pub fn split_off(&mut self, at: usize) -> Records {
+        match self {
+            Records::Values(values) => Records::Values(values.split_off(at)),
+            Records::Batch(batch) => {
+                let rest = batch.slice(at, batch.num_rows() - at);
+                *batch = batch.slice(0, at);
+                Records::Batch(rest)
+            }
+        }
+    }
+}
+
+pub enum Output {
+    Rows(Box<dyn Writer>),
+    Batches(Box<dyn BatchWriter>),
+}
+
+impl Output {
+    pub fn write(&mut self, records: Records) -> Result<()> {
+        match (self, records) {
+            (Output::Rows(writer), Records::Values(values)) => {
+                values.into_iter().try_for_each(|v| writer.write(v))
+            }
+            (Output::Batches(writer), Records::Batch(batch)) => writer.write_batch(&batch),
+            _ => bail!("the records do not match the output format"),
+        }
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        match self {
+            Output::Rows(writer) => writer.finish(),
+            Output::Batches(writer) => writer.finish(),
+        }
+    }
 }
 
 impl Format {
-    pub fn new_writer(&self, _: &Schema) -> Box<dyn Writer> {
+    /// The output path set by the format, if any.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Format::Parquet { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the format writes each record as a line of text of
+    /// its own, so that each line can be sent as a datagram. Quoted
+    /// CSV fields and templates may span lines, and CSV has a header.
+    pub fn is_line_delimited(&self) -> bool {
         match self {
-            Format::Json { pretty } => Box::new(JsonWriter::new(*pretty)),
-            Format::Csv => Box::new(CsvWriter),
+            Format::Json { pretty } => !pretty,
+            Format::Log(_) => true,
+            _ => false,
         }
     }
+
+    /// Names the table of the SQL formats, as the
+    /// tables of datasets are named after the dataset.
+    pub fn set_table(&mut self, table: &str) {
+        if let Format::Copy { options, .. } | Format::Insert { options, .. } = self {
+            options.table = table.to_owned();
+        }
+    }
+
+    /// The number of rows per record batch of the formats
+    /// generated column-wise, which are written in batches.
+    pub fn batch_size(&self) -> Option<usize> {
+        match self {
+            Format::Arrow { batch_size, .. } => Some((*batch_size).max(1)),
+            Format::Parquet { row_group_size, .. } => Some((*row_group_size).max(1)),
+            _ => None,
+        }
+    }
+
+    /// Generates the given number of rows, column-wise
+    /// if the format is written in batches.
+    pub fn generate<R: Rng>(
+        &self,
+        gen: &DataGenerator,
+        rng: &mut R,
+        rows: usize,
+    ) -> Result<Records> {
+        if self.batch_size().is_none() {
+            let values = (0..rows).map(|_| rng.sample(gen)).collect();
+            gen.check()?;
+            return Ok(Records::Values(values));
+        }
+
+        let batch = gen.record_batch(rng, rows);
+        gen.check()?;
+        Ok(Records::Batch(
+            batch.context("failed to generate record batch")?,
+        ))
+    }
+
+    pub fn new_output(&self, schema: &Schema, out: Sink) -> Result<Output> {
+        let writer: Box<dyn Writer> = match self {
+            Format::Arrow { file, .. } => {
+                return Ok(Output::Batches(Box::new(IpcWriter::new(
+                    out, schema, *file,
+                )?)))
+            }
+            Format::Avro {
+                single_object,
+                avro_schema,
+                block_size,
+            } => Box::new(AvroWriter::new(
+                out,
+                schema,
+                avro_schema.as_deref(),
+                *single_object,
+                *block_size,
+            )?),
+            Format::Cbor => Box::new(CborWriter::new(out)),
+            Format::Json { pretty } => Box::new(JsonWriter::new(out, *pretty)),
+            Format::Log(options) => Box::new(LogWriter::new(out, schema, options)?),
+            Format::Msgpack => Box::new(MsgpackWriter::new(out)),
+            Format::Parquet {
+                row_group_size,
+                compression,
+                ..
+            } => {
+                return Ok(Output::Batches(Box::new(ParquetWriter::new(
+                    out,
+                    schema,
+                    *row_group_size,
+                    *compression,
+                )?)))
+            }
+            Format::Protobuf { proto, message } => {
+                Box::new(ProtobufWriter::new(out, proto, message.as_deref())?)
+            }
+            Format::Template(options) => Box::new(TemplateWriter::new(out, options)?),
+            Format::Xml(options) => Box::new(XmlWriter::new(out, options)),
+            Format::Yaml => Box::new(YamlWriter::new(out)),
+            Format::Copy { options, csv } => Box::new(CopyWriter::new(out, schema, options, *csv)),
+            Format::Csv(options) => Box::new(CsvWriter::new(out, schema, options)),
+            Format::Insert {
+                options,
+                dialect,
+                batch_size,
+            } => Box::new(InsertWriter::new(
+                out,
+                schema,
+                options,
+                *dialect,
+                *batch_size,
+            )),
+        };
+
+        Ok(Output::Rows(writer))
+    }
+}
+
+/// Finds the data type of the field at the given path.
+pub(crate) fn find<'a>(schema: &'a Schema<'a>, path: &[String]) -> Option<&'a DataType<'a>> {
+    let (first, rest) = path.split_first()?;
+    let (_, mut dt) = schema.iter().find(|(name, _)| name == first)?;
+    for segment in rest {
+        dt = match unwrap(dt) {
+            DataType::Object { fields } => fields.get(segment.as_str())?,
+            _ => return None,
+        };
+    }
+    Some(unwrap(dt))
+}
+
+/// Unwraps nullable, optional, unique, cardinality and member data types.
+pub(crate) fn unwrap<'a>(dt: &'a DataType<'a>) -> &'a DataType<'a> {
+    match dt {
+        DataType::Nullable { element, .. }
+        | DataType::Optional { element, .. }
+        | DataType::Unique { element }
+        | DataType::Cardinality { element, .. }
+        | DataType::Member {
+            element: Some(element),
+            ..
+        } => unwrap(element.deref()),
+        dt => dt,
+    }
 }
@@ -0,0 +1,927 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::NaiveDate;
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::schema::Schema;
+use rand::{thread_rng, RngCore};
+use serde_json::{json, Map, Value};
+
+use super::Writer;
+use crate::sink::Sink;
+
+/// Writes values using the Avro binary encoding, either as an
+/// [object container file](https://avro.apache.org/docs/current/specification/#object-container-files)
+/// or as a stream of [single object encoded](https://avro.apache.org/docs/current/specification/#single-object-encoding)
+/// messages.
+pub struct AvroWriter {
+    out: Sink,
+    encoder: Encoder,
+    mode: Mode,
+}
+
+enum Mode {
+    Container {
+        json: String,
+        sync: [u8; 16],
+        header: bool,
+        block: Vec<u8>,
+        count: usize,
+        block_size: usize,
+    },
+    SingleObject {
+        fingerprint: [u8; 8],
+    },
+}
+
+impl AvroWriter {
+    /// Creates a new writer for the given Avro schema, or one derived
+    /// from the data-gen [Schema] when no Avro schema is provided.
+    pub fn new(
+        out: Sink,
+        schema: &Schema,
+        avro_schema: Option<&str>,
+        single_object: bool,
+        block_size: usize,
+    ) -> Result<AvroWriter> {
+        let avro_schema = match avro_schema {
+            Some(path) => {
+                let data = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read file: {}", path))?;
+                serde_json::from_str(&data)
+                    .with_context(|| format!("failed to parse Avro schema {}", path))?
+            }
+            None => derive_schema(schema),
+        };
+
+        let encoder = Encoder::new(&avro_schema)?;
+
+        let mode = if single_object {
+            Mode::SingleObject {
+                fingerprint: fingerprint(&canonical_form(&avro_schema)),
+            }
+        } else {
+            let mut sync = [0; 16];
+            thread_rng().fill_bytes(&mut sync);
+            Mode::Container {
+                json: avro_schema.to_string(),
+                sync,
+                header: false,
+                block: Vec::new(),
+                count: 0,
+                block_size: block_size.max(1),
+            }
+        };
+
+        Ok(AvroWriter { out, encoder, mode })
+    }
+}
+
+impl Writer for AvroWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        match &mut self.mode {
+            Mode::SingleObject { fingerprint } => {
+                let mut buf = vec![0xC3, 0x01];
+                buf.extend_from_slice(fingerprint);
+                self.encoder.encode(&v, &mut buf)?;
+                self.out.write_all(&buf).context("failed to write Avro")?;
+                self.out.flush().context("failed to write Avro")
+            }
+            Mode::Container {
+                json,
+                sync,
+                header,
+                block,
+                count,
+                block_size,
+            } => {
+                if !*header {
+                    let mut buf = b"Obj\x01".to_vec();
+                    write_long(&mut buf, 2);
+                    write_bytes(&mut buf, b"avro.schema");
+                    write_bytes(&mut buf, json.as_bytes());
+                    write_bytes(&mut buf, b"avro.codec");
+                    write_bytes(&mut buf, b"null");
+                    write_long(&mut buf, 0);
+                    buf.extend_from_slice(sync);
+                    self.out.write_all(&buf).context("failed to write Avro")?;
+                    *header = true;
+                }
+
+                self.encoder.encode(&v, block)?;
+                *count += 1;
+
+                if *count >= *block_size {
+                    write_block(&mut self.out, block, count, sync)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Mode::Container {
+            sync, block, count, ..
+        } = &mut self.mode
+        {
+            if *count > 0 {
+                write_block(&mut self.out, block, count, sync)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_block(
+    out: &mut impl Write,
+    block: &mut Vec<u8>,
+    count: &mut usize,
+    sync: &[u8; 16],
+) -> Result<()> {
+    let mut buf = Vec::with_capacity(block.len() + 32);
+    write_long(&mut buf, *count as i64);
+    write_long(&mut buf, block.len() as i64);
+    buf.append(block);
+    buf.extend_from_slice(sync);
+    *count = 0;
+
+    out.write_all(&buf).context("failed to write Avro")?;
+    out.flush().context("failed to write Avro")
+}
+
+/// A resolved Avro type. Named types are stored once
+/// and referenced by their full name so that recursive
+/// types can be represented.
+#[derive(Debug, Clone)]
+enum AvroType {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record(Vec<(String, AvroType)>),
+    Enum(Vec<String>),
+    Array(Box<AvroType>),
+    Map(Box<AvroType>),
+    Union(Vec<AvroType>),
+    Fixed(usize),
+    Named(String),
+    Date,
+    TimeMillis,
+    TimeMicros,
+    TimestampMillis,
+    TimestampMicros,
+    Decimal { scale: u32, fixed: Option<usize> },
+}
+
+struct Encoder {
+    root: AvroType,
+    names: HashMap<String, AvroType>,
+}
+
+impl Encoder {
+    fn new(schema: &Value) -> Result<Encoder> {
+        let mut encoder = Encoder {
+            root: AvroType::Null,
+            names: HashMap::new(),
+        };
+        encoder.root = encoder.parse(schema, None)?;
+        Ok(encoder)
+    }
+
+    fn parse(&mut self, schema: &Value, namespace: Option<&str>) -> Result<AvroType> {
+        match schema {
+            Value::String(name) => Ok(match name.as_str() {
+                "null" => AvroType::Null,
+                "boolean" => AvroType::Boolean,
+                "int" => AvroType::Int,
+                "long" => AvroType::Long,
+                "float" => AvroType::Float,
+                "double" => AvroType::Double,
+                "bytes" => AvroType::Bytes,
+                "string" => AvroType::String,
+                name => {
+                    let full = full_name(namespace, name);
+                    if self.names.contains_key(&full) {
+                        AvroType::Named(full)
+                    } else if self.names.contains_key(name) {
+                        AvroType::Named(name.to_string())
+                    } else {
+                        bail!("unknown Avro type `{}`", name)
+                    }
+                }
+            }),
+            Value::Array(branches) => Ok(AvroType::Union(
+                branches
+                    .iter()
+                    .map(|branch| self.parse(branch, namespace))
+                    .collect::<Result<_>>()?,
+            )),
+            Value::Object(schema) => self.parse_complex(schema, namespace),
+            _ => bail!("invalid Avro schema `{}`", schema),
+        }
+    }
+
+    fn parse_complex(
+        &mut self,
+        schema: &Map<String, Value>,
+        namespace: Option<&str>,
+    ) -> Result<AvroType> {
+        let tpe = match schema.get("type") {
+            Some(Value::String(tpe)) => tpe.as_str(),
+            Some(tpe) => return self.parse(tpe, namespace),
+            None => bail!("Avro schema is missing `type`"),
+        };
+
+        let logical = schema.get("logicalType").and_then(Value::as_str);
+        let scale = schema.get("scale").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+        match (tpe, logical) {
+            ("int", Some("date")) => return Ok(AvroType::Date),
+            ("int", Some("time-millis")) => return Ok(AvroType::TimeMillis),
+            ("long", Some("time-micros")) => return Ok(AvroType::TimeMicros),
+            ("long", Some("timestamp-millis" | "local-timestamp-millis")) => {
+                return Ok(AvroType::TimestampMillis)
+            }
+            ("long", Some("timestamp-micros" | "local-timestamp-micros")) => {
+                return Ok(AvroType::TimestampMicros)
+            }
+            ("bytes", Some("decimal")) => return Ok(AvroType::Decimal { scale, fixed: None }),
+            _ => {}
+        }
+
+        match tpe {
+            "record" | "error" | "enum" | "fixed" => {
+                let name = schema
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("Avro {} is missing a name", tpe))?;
+                let namespace = schema
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .or(namespace);
+                let full = full_name(namespace, name);
+                let namespace = full.rsplit_once('.').map(|(namespace, _)| namespace);
+
+                let resolved = match tpe {
+                    "enum" => AvroType::Enum(
+                        schema
+                            .get("symbols")
+                            .and_then(Value::as_array)
+                            .map(|symbols| {
+                                symbols
+                                    .iter()
+                                    .filter_map(Value::as_str)
+                                    .map(str::to_string)
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    ),
+                    "fixed" => {
+                        let size = schema
+                            .get("size")
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| anyhow!("Avro fixed {} is missing a size", full))?
+                            as usize;
+                        if logical == Some("decimal") {
+                            AvroType::Decimal {
+                                scale,
+                                fixed: Some(size),
+                            }
+                        } else {
+                            AvroType::Fixed(size)
+                        }
+                    }
+                    _ => {
+                        // register the name before parsing the fields
+                        // so that recursive references resolve
+                        self.names
+                            .insert(full.clone(), AvroType::Record(Vec::new()));
+
+                        let mut fields = Vec::new();
+                        for field in schema
+                            .get("fields")
+                            .and_then(Value::as_array)
+                            .into_iter()
+                            .flatten()
+                        {
+                            let name = field
+                                .get("name")
+                                .and_then(Value::as_str)
+                                .ok_or_else(|| anyhow!("field of {} is missing a name", full))?;
+                            let tpe = field.get("type").unwrap_or(&Value::Null);
+                            fields.push((name.to_string(), self.parse(tpe, namespace)?));
+                        }
+                        AvroType::Record(fields)
+                    }
+                };
+
+                self.names.insert(full.clone(), resolved);
+                Ok(AvroType::Named(full))
+            }
+            "array" => {
+                let items = schema.get("items").unwrap_or(&Value::Null);
+                Ok(AvroType::Array(Box::new(self.parse(items, namespace)?)))
+            }
+            "map" => {
+                let values = schema.get("values").unwrap_or(&Value::Null);
+                Ok(AvroType::Map(Box::new(self.parse(values, namespace)?)))
+            }
+            primitive => self.parse(&Value::String(primitive.to_string()), namespace),
+        }
+    }
+
+    fn encode(&self, value: &Value, buf: &mut Vec<u8>) -> Result<()> {
+        self.encode_type(&self.root, value, buf, "")
+    }
+
+    fn resolve<'t>(&'t self, tpe: &'t AvroType) -> &'t AvroType {
+        match tpe {
+            AvroType::Named(name) => self.names.get(name).unwrap_or(&AvroType::Null),
+            tpe => tpe,
+        }
+    }
+
+    fn encode_type(
+        &self,
+        tpe: &AvroType,
+        value: &Value,
+        buf: &mut Vec<u8>,
+        path: &str,
+    ) -> Result<()> {
+        match (self.resolve(tpe), value) {
+            (AvroType::Null, Value::Null) => {}
+            (AvroType::Boolean, Value::Bool(b)) => buf.push(*b as u8),
+            (AvroType::Int | AvroType::TimeMillis, _) => {
+                let n = integer(value).ok_or_else(|| mismatch(path, "int", value))?;
+                i32::try_from(n)
+                    .map_err(|_| anyhow!("{}: {} does not fit in an Avro int", path, n))?;
+                write_long(buf, n)
+            }
+            (
+                AvroType::Long
+                | AvroType::TimeMicros
+                | AvroType::TimestampMillis
+                | AvroType::TimestampMicros,
+                _,
+            ) => write_long(
+                buf,
+                integer(value).ok_or_else(|| mismatch(path, "long", value))?,
+            ),
+            (AvroType::Date, _) => {
+                let days = match value {
+                    Value::String(date) => NaiveDate::parse_from_str(date, "%F")
+                        .ok()
+                        .map(|date| (date - NaiveDate::default()).num_days()),
+                    value => integer(value),
+                };
+                write_long(buf, days.ok_or_else(|| mismatch(path, "date", value))?)
+            }
+            (AvroType::Float, _) => {
+                let n = float(value).ok_or_else(|| mismatch(path, "float", value))?;
+                buf.extend_from_slice(&(n as f32).to_le_bytes())
+            }
+            (AvroType::Double, _) => {
+                let n = float(value).ok_or_else(|| mismatch(path, "double", value))?;
+                buf.extend_from_slice(&n.to_le_bytes())
+            }
+            (AvroType::Bytes | AvroType::String, Value::String(s)) => {
+                write_bytes(buf, s.as_bytes())
+            }
+            (AvroType::Bytes | AvroType::String, Value::Bool(_) | Value::Number(_)) => {
+                write_bytes(buf, value.to_string().as_bytes())
+            }
+            (AvroType::Fixed(size), Value::String(s)) => {
+                if s.len() != *size {
+                    bail!("{}: `{}` is not exactly {} bytes", path, s, size)
+                }
+                buf.extend_from_slice(s.as_bytes())
+            }
+            (AvroType::Decimal { scale, fixed }, _) => {
+                let unscaled =
+                    unscaled(value, *scale).ok_or_else(|| mismatch(path, "decimal", value))?;
+                let bytes = unscaled.to_be_bytes();
+                match fixed {
+                    Some(size) => {
+                        let size = (*size).min(bytes.len());
+                        buf.extend_from_slice(&bytes[bytes.len() - size..])
+                    }
+                    None => {
+                        // the minimal two's complement representation
+                        let sign = if unscaled < 0 { 0xFF } else { 0x00 };
+                        let mut start = 0;
+                        while start < bytes.len() - 1
+                            && bytes[start] == sign
+                            && (bytes[start + 1] & 0x80) == (sign & 0x80)
+                        {
+                            start += 1;
+                        }
+                        write_bytes(buf, &bytes[start..])
+                    }
+                }
+            }
+            (AvroType::Enum(symbols), Value::String(s)) => {
+                let index = symbols
+                    .iter()
+                    .position(|symbol| symbol == s)
+                    .ok_or_else(|| anyhow!("{}: `{}` is not a symbol of the Avro enum", path, s))?;
+                write_long(buf, index as i64)
+            }
+            (AvroType::Array(items), Value::Array(values)) => {
+                if !values.is_empty() {
+                    write_long(buf, values.len() as i64);
+                    for value in values {
+                        self.encode_type(items, value, buf, path)?;
+                    }
+                }
+                write_long(buf, 0)
+            }
+            (AvroType::Map(values), Value::Object(entries)) => {
+                if !entries.is_empty() {
+                    write_long(buf, entries.len() as i64);
+                    for (key, value) in entries {
+                        write_bytes(buf, key.as_bytes());
+                        self.encode_type(values, value, buf, &child(path, key))?;
+                    }
+                }
+                write_long(buf, 0)
+            }
+            (AvroType::Record(fields), Value::Object(entries)) => {
+                for (name, tpe) in fields {
+                    let value = entries
+                        .get(name)
+                        .or_else(|| {
+                            entries
+                                .iter()
+                                .find(|(key, _)| sanitize(key) == *name)
+                                .map(|(_, value)| value)
+                        })
+                        .unwrap_or(&Value::Null);
+                    self.encode_type(tpe, value, buf, &child(path, name))?;
+                }
+            }
+            (AvroType::Union(branches), _) => {
+                let index = branches
+                    .iter()
+                    .position(|branch| self.matches(branch, value))
+                    .ok_or_else(|| mismatch(path, "union", value))?;
+                write_long(buf, index as i64);
+                self.encode_type(&branches[index], value, buf, path)?
+            }
+            (tpe, value) => return Err(mismatch(path, &format!("{:?}", tpe), value)),
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the value can be encoded as the
+    /// given type, used to select the branch of a union.
+    fn matches(&self, tpe: &AvroType, value: &Value) -> bool {
+        match (self.resolve(tpe), value) {
+            (AvroType::Null, Value::Null) => true,
+            (AvroType::Boolean, Value::Bool(_)) => true,
+            (AvroType::Int | AvroType::TimeMillis, Value::Number(n)) => n
+                .as_i64()
+                .map(|n| i32::try_from(n).is_ok())
+                .unwrap_or(false),
+            (
+                AvroType::Long
+                | AvroType::TimeMicros
+                | AvroType::TimestampMillis
+                | AvroType::TimestampMicros
+                | AvroType::Date,
+                Value::Number(n),
+            ) => n.is_i64(),
+            (AvroType::TimestampMillis | AvroType::TimestampMicros, Value::String(s)) => {
+                s.parse::<i64>().is_ok()
+            }
+            (AvroType::Date, Value::String(s)) => NaiveDate::parse_from_str(s, "%F").is_ok(),
+            (AvroType::Float | AvroType::Double | AvroType::Decimal { .. }, Value::Number(_)) => {
+                true
+            }
+            (AvroType::Bytes | AvroType::String, Value::String(_)) => true,
+            (AvroType::Fixed(size), Value::String(s)) => s.len() == *size,
+            (AvroType::Enum(symbols), Value::String(s)) => symbols.contains(s),
+            (AvroType::Array(_), Value::Array(_)) => true,
+            (AvroType::Record(_) | AvroType::Map(_), Value::Object(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn mismatch(path: &str, expected: &str, value: &Value) -> anyhow::Error {
+    let path = if path.is_empty() { "<root>" } else { path };
+    anyhow!("{}: cannot encode `{}` as Avro {}", path, value, expected)
+}
+
+fn child(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// The unscaled value of a decimal number or string at the given scale,
+/// read exactly from its digits, such as `-123.45` at scale 2 as `-12345`.
+/// Extra digits after the decimal point are rounded.
+fn unscaled(value: &Value, scale: u32) -> Option<i128> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_owned(),
+        _ => return None,
+    };
+    if text.contains(['e', 'E']) {
+        let n: f64 = text.parse().ok()?;
+        return Some((n * 10f64.powi(scale as i32)).round() as i128);
+    }
+
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if !(integer.chars().chain(fraction.chars())).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let scale = scale as usize;
+    let mut unscaled: i128 = format!(
+        "{}{:0<scale$}",
+        integer,
+        &fraction[..fraction.len().min(scale)]
+    )
+    .parse()
+    .ok()?;
+    if fraction.len() > scale && fraction.as_bytes()[scale] >= b'5' {
+        unscaled += 1;
+    }
+    Some(if negative { -unscaled } else { unscaled })
+}
+
+fn write_long(buf: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8)
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(buf, bytes.len() as i64);
+    buf.extend_from_slice(bytes)
+}
+
+fn full_name(namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{}.{}", namespace, name)
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Replaces characters that are not valid in Avro names.
+fn sanitize(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !sanitized.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Derives an Avro schema for the values generated by a [Schema].
+/// The top level type is a record named `data_gen.Record` and
+/// nested records and enums are named after their field path.
+pub fn derive_schema(schema: &Schema) -> Value {
+    let fields: Vec<_> = schema
+        .iter()
+        .map(|(name, dt)| {
+            let name = sanitize(name);
+            let tpe = derive_type(&format!("Record_{}", name), dt);
+            json!({ "name": name, "type": tpe })
+        })
+        .collect();
+
+    json!({
+        "type": "record",
+        "name": "Record",
+        "namespace": "data_gen",
+        "fields": fields,
+    })
+}
+
+fn derive_type(name: &str, dt: &DataType) -> Value {
+    match dt {
+        DataType::Array { element, .. } => {
+            json!({ "type": "array", "items": derive_type(name, element) })
+        }
+        DataType::Boolean => json!("boolean"),
+        DataType::Cardinality { element, .. } | DataType::Unique { element } => {
+            derive_type(name, element)
+        }
+        DataType::SmallInt | DataType::Integer | DataType::Serial => json!("int"),
+        DataType::BigInt => json!("long"),
+        DataType::Range { from, to, .. } => {
+            if *from >= i32::MIN as i64 && *to - 1 <= i32::MAX as i64 {
+                json!("int")
+            } else {
+                json!("long")
+            }
+        }
+        DataType::Float { .. } => json!("double"),
+        DataType::Decimal {
+            precision, scale, ..
+        } => json!({
+            "type": "bytes",
+            "logicalType": "decimal",
+            "precision": precision,
+            "scale": scale,
+        }),
+        DataType::Map { values, .. } => {
+            json!({ "type": "map", "values": derive_type(name, values) })
+        }
+        DataType::Member {
+            element: Some(element),
+            ..
+        } => derive_type(name, element),
+        DataType::Member { element: None, .. } => json!(["null", "string"]),
+        DataType::Nullable { element, .. } | DataType::Optional { element, .. } => {
+            let mut branches = vec![json!("null")];
+            match derive_type(name, element) {
+                Value::Array(inner) => branches.extend(inner),
+                inner => branches.push(inner),
+            }
+            branches.dedup();
+            Value::Array(branches)
+        }
+        DataType::Object { fields } => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            let fields: Vec<_> = fields
+                .into_iter()
+                .map(|(field, dt)| {
+                    let field = sanitize(field);
+                    let tpe = derive_type(&format!("{}_{}", name, field), dt);
+                    json!({ "name": field, "type": tpe })
+                })
+                .collect();
+            json!({ "type": "record", "name": name, "fields": fields })
+        }
+        DataType::OneOf { options, .. } => {
+            let symbols: HashSet<_> = options.iter().collect();
+            let valid = symbols.len() == options.len()
+                && options.iter().all(|option| sanitize(option) == *option);
+            if valid && !options.is_empty() {
+                json!({ "type": "enum", "name": name, "symbols": options })
+            } else {
+                json!("string")
+            }
+        }
+        DataType::Timestamp { format } => match format.as_deref() {
+            Some("%s%3f") => json!({ "type": "long", "logicalType": "timestamp-millis" }),
+            Some("%s%6f") => json!({ "type": "long", "logicalType": "timestamp-micros" }),
+            Some("%F") => json!({ "type": "int", "logicalType": "date" }),
+            _ => json!("string"),
+        },
+        DataType::Union { variants } => {
+            let mut branches: Vec<Value> = Vec::new();
+            for (i, variant) in variants.iter().enumerate() {
+                let branch = derive_type(&format!("{}_{}", name, i), variant);
+                let inner = match branch {
+                    Value::Array(inner) => inner,
+                    branch => vec![branch],
+                };
+                for branch in inner {
+                    if !branches.contains(&branch) {
+                        branches.push(branch);
+                    }
+                }
+            }
+            // null must appear at most once, first by convention
+            if let Some(i) = branches.iter().position(|branch| branch == "null") {
+                let null = branches.remove(i);
+                branches.insert(0, null);
+            }
+            Value::Array(branches)
+        }
+        DataType::Uuid => json!({ "type": "string", "logicalType": "uuid" }),
+        DataType::Email
+        | DataType::Generator { .. }
+        | DataType::Literal { .. }
+        | DataType::PhoneNumber
+        | DataType::Regex { .. } => json!("string"),
+    }
+}
+
+/// Returns the [parsing canonical form](https://avro.apache.org/docs/current/specification/#parsing-canonical-form-for-schemas)
+/// of an Avro schema.
+fn canonical_form(schema: &Value) -> String {
+    canonical(schema, None, &mut HashSet::new())
+}
+
+fn canonical(schema: &Value, namespace: Option<&str>, seen: &mut HashSet<String>) -> String {
+    const PRIMITIVES: &[&str] = &[
+        "null", "boolean", "int", "long", "float", "double", "bytes", "string",
+    ];
+
+    let quote = |s: &str| serde_json::to_string(s).unwrap();
+
+    match schema {
+        Value::String(name) if PRIMITIVES.contains(&name.as_str()) => quote(name),
+        Value::String(name) => quote(&full_name(namespace, name)),
+        Value::Array(branches) => {
+            let branches: Vec<_> = branches
+                .iter()
+                .map(|branch| canonical(branch, namespace, seen))
+                .collect();
+            format!("[{}]", branches.join(","))
+        }
+        Value::Object(schema) => {
+            let tpe = schema.get("type").unwrap_or(&Value::Null);
+            let tpe = match tpe {
+                Value::String(tpe) => tpe.as_str(),
+                tpe => return canonical(tpe, namespace, seen),
+            };
+
+            match tpe {
+                "record" | "error" | "enum" | "fixed" => {
+                    let name = schema.get("name").and_then(Value::as_str).unwrap_or("");
+                    let namespace = schema
+                        .get("namespace")
+                        .and_then(Value::as_str)
+                        .or(namespace);
+                    let full = full_name(namespace, name);
+
+                    if !seen.insert(full.clone()) {
+                        return quote(&full);
+                    }
+
+                    let inner = full.rsplit_once('.').map(|(namespace, _)| namespace);
+                    let mut out = format!("{{\"name\":{},\"type\":{}", quote(&full), quote(tpe));
+                    match tpe {
+                        "enum" => {
+                            let symbols = schema.get("symbols").cloned().unwrap_or(json!([]));
+                            out.push_str(&format!(",\"symbols\":{}", symbols));
+                        }
+                        "fixed" => {
+                            let size = schema.get("size").cloned().unwrap_or(json!(0));
+                            out.push_str(&format!(",\"size\":{}", size));
+                        }
+                        _ => {
+                            let fields: Vec<_> = schema
+                                .get("fields")
+                                .and_then(Value::as_array)
+                                .into_iter()
+                                .flatten()
+                                .map(|field| {
+                                    let name = field.get("name").and_then(Value::as_str);
+                                    let tpe = field.get("type").unwrap_or(&Value::Null);
+                                    format!(
+                                        "{{\"name\":{},\"type\":{}}}",
+                                        quote(name.unwrap_or("")),
+                                        canonical(tpe, inner, seen)
+                                    )
+                                })
+                                .collect();
+                            out.push_str(&format!(",\"fields\":[{}]", fields.join(",")));
+                        }
+                    }
+                    out.push('}');
+                    out
+                }
+                "array" => format!(
+                    "{{\"type\":\"array\",\"items\":{}}}",
+                    canonical(schema.get("items").unwrap_or(&Value::Null), namespace, seen)
+                ),
+                "map" => format!(
+                    "{{\"type\":\"map\",\"values\":{}}}",
+                    canonical(
+                        schema.get("values").unwrap_or(&Value::Null),
+                        namespace,
+                        seen
+                    )
+                ),
+                name => canonical(&Value::String(name.to_string()), namespace, seen),
+            }
+        }
+        _ => quote(""),
+    }
+}
+
+/// Computes the CRC-64-AVRO Rabin fingerprint of a schema's
+/// canonical form, in little-endian byte order.
+fn fingerprint(canonical: &str) -> [u8; 8] {
+    const EMPTY: u64 = 0xc15d213aa4d7a795;
+
+    let mut table = [0u64; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut fp = i as u64;
+        for _ in 0..8 {
+            fp = (fp >> 1) ^ (EMPTY & (fp & 1).wrapping_neg());
+        }
+        *entry = fp;
+    }
+
+    let mut fp = EMPTY;
+    for byte in canonical.bytes() {
+        fp = (fp >> 8) ^ table[((fp ^ byte as u64) & 0xff) as usize];
+    }
+
+    fp.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{canonical_form, unscaled, write_long, Encoder};
+    use serde_json::json;
+
+    #[test]
+    fn test_zig_zag_encoding() {
+        let mut buf = Vec::new();
+        for n in [0, -1, 1, -64, 64] {
+            write_long(&mut buf, n);
+        }
+        assert_eq!(vec![0x00, 0x01, 0x02, 0x7F, 0x80, 0x01], buf);
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let schema = json!({
+            "type": "record",
+            "name": "User",
+            "namespace": "com.example",
+            "doc": "stripped",
+            "fields": [
+                { "name": "id", "type": { "type": "string", "logicalType": "uuid" } },
+                { "name": "friend", "type": ["null", "User"], "default": null }
+            ]
+        });
+
+        assert_eq!(
+            r#"{"name":"com.example.User","type":"record","fields":[{"name":"id","type":"string"},{"name":"friend","type":["null","com.example.User"]}]}"#,
+            canonical_form(&schema)
+        );
+    }
+
+    #[test]
+    fn test_encode_record() {
+        let encoder = Encoder::new(&json!({
+            "type": "record",
+            "name": "Payment",
+            "fields": [
+                { "name": "amount", "type": { "type": "bytes", "logicalType": "decimal", "precision": 4, "scale": 2 } },
+                { "name": "note", "type": ["null", "string"] },
+                { "name": "day", "type": { "type": "int", "logicalType": "date" } }
+            ]
+        }))
+        .unwrap();
+
+        let mut buf = Vec::new();
+        encoder
+            .encode(&json!({ "amount": -1.28, "day": "1970-01-02" }), &mut buf)
+            .unwrap();
+
+        // -128 is the single byte 0x80, the note takes the null
+        // branch of the union and day 1 is zig-zag encoded as 2
+        assert_eq!(vec![0x02, 0x80, 0x00, 0x02], buf);
+    }
+
+    #[test]
+    fn test_unscaled_decimals() {
+        assert_eq!(Some(-12345), unscaled(&json!(-123.45), 2));
+        assert_eq!(Some(1200), unscaled(&json!("12"), 2));
+        assert_eq!(Some(13), unscaled(&json!("1.25"), 1));
+        assert_eq!(
+            Some(-123456789012345678901234567891),
+            unscaled(&json!("-12345678901234567890.1234567891"), 10)
+        );
+        assert_eq!(None, unscaled(&json!("12a"), 2));
+    }
+}
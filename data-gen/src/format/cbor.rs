@@ -0,0 +1,70 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+/// Writes values as a sequence of concatenated
+/// [CBOR](https://cbor.io) data items.
+pub struct CborWriter {
+    out: Sink,
+}
+
+impl CborWriter {
+    pub fn new(out: Sink) -> CborWriter {
+        CborWriter { out }
+    }
+}
+
+impl Writer for CborWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        ciborium::into_writer(&v, &mut self.out).context("failed to serialize value as CBOR")?;
+        self.out.flush().context("failed to write CBOR")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CborWriter;
+    use crate::format::Writer;
+    use crate::sink::{Compress, Sink};
+    use serde_json::{json, Value};
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_round_trip() {
+        let values = vec![
+            json!({
+                "id": 1,
+                "name": "Zelda",
+                "price": 12.5,
+                "big": 18446744073709551615u64,
+                "negative": -42,
+                "tags": ["a", "b"],
+                "nested": { "flag": true, "missing": null }
+            }),
+            json!({ "id": 2, "name": "", "tags": [], "nested": {} }),
+        ];
+
+        let path = std::env::temp_dir().join(format!("data-gen-{}.cbor", std::process::id()));
+        let sink = Sink::new(Box::new(File::create(&path).unwrap()), Compress::None).unwrap();
+        let mut writer = CborWriter::new(sink.clone());
+        for value in &values {
+            writer.write(value.clone()).unwrap();
+        }
+        writer.finish().unwrap();
+        sink.close().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut buf = bytes.as_slice();
+        for value in &values {
+            let decoded: Value = ciborium::from_reader(&mut buf).unwrap();
+            assert_eq!(value, &decoded);
+        }
+        assert!(buf.is_empty());
+    }
+}
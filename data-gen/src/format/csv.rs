@@ -1,46 +1,244 @@
-use serde_json::Value;
+use std::ops::Deref;
 
-use super::Writer;
 use anyhow::{bail, Context, Result};
+use clap::{ArgEnum, Args};
+use csv::{QuoteStyle, Terminator};
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::schema::Schema;
+use serde_json::Value;
+
+use super::{unwrap, Writer};
+use crate::sink::Sink;
+
+#[derive(Args)]
+pub struct CsvOptions {
+    /// Do not write a header row.
+    #[clap(long)]
+    no_header: bool,
+
+    /// The field delimiter, a single character or `\t`.
+    #[clap(long, default_value = ",", parse(try_from_str = parse_delimiter))]
+    delimiter: u8,
+
+    /// Write tab separated values, shorthand for `--delimiter '\t'`.
+    #[clap(long, conflicts_with = "delimiter")]
+    tsv: bool,
+
+    /// When to quote fields.
+    #[clap(long, arg_enum, default_value = "necessary")]
+    quote: Quote,
+
+    /// The text written for null or missing values.
+    #[clap(long, default_value = "")]
+    null: String,
+
+    /// The line ending written after each record.
+    #[clap(long, arg_enum, default_value = "lf")]
+    line_ending: LineEnding,
+
+    /// How array fields are written.
+    #[clap(long, arg_enum, default_value = "json")]
+    arrays: Arrays,
+
+    /// The separator between elements when arrays are joined.
+    #[clap(long, default_value = ";")]
+    array_separator: String,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Quote {
+    /// Only quote fields containing delimiters, quotes or line breaks.
+    Necessary,
+    /// Quote every field.
+    Always,
+    /// Quote every field which is not a number.
+    NonNumeric,
+    /// Never quote fields.
+    Never,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Arrays {
+    /// Write arrays as JSON, e.g. `["a","b"]`.
+    Json,
+    /// Join the elements of arrays with the array separator, e.g. `a;b`.
+    Join,
+}
+
+fn parse_delimiter(s: &str) -> Result<u8> {
+    match s {
+        "\\t" | "tab" => Ok(b'\t'),
+        s if s.len() == 1 => Ok(s.as_bytes()[0]),
+        s => bail!("expected a single character delimiter, found `{}`", s),
+    }
+}
+
+pub struct CsvWriter {
+    writer: csv::Writer<Sink>,
+    /// The path of each column, nested object
+    /// fields are flattened into dotted names.
+    columns: Vec<Vec<String>>,
+    header: bool,
+    null: String,
+    arrays: Arrays,
+    array_separator: String,
+}
+
+impl CsvWriter {
+    pub fn new(out: Sink, schema: &Schema, options: &CsvOptions) -> CsvWriter {
+        let writer = csv::WriterBuilder::new()
+            .delimiter(if options.tsv {
+                b'\t'
+            } else {
+                options.delimiter
+            })
+            .quote_style(match options.quote {
+                Quote::Necessary => QuoteStyle::Necessary,
+                Quote::Always => QuoteStyle::Always,
+                Quote::NonNumeric => QuoteStyle::NonNumeric,
+                Quote::Never => QuoteStyle::Never,
+            })
+            .terminator(match options.line_ending {
+                LineEnding::Lf => Terminator::Any(b'\n'),
+                LineEnding::Crlf => Terminator::CRLF,
+            })
+            .from_writer(out);
 
-pub struct CsvWriter;
+        let mut columns = Vec::new();
+        for (name, dt) in schema.iter() {
+            flatten(&mut vec![name.to_string()], dt, &mut columns);
+        }
+
+        CsvWriter {
+            writer,
+            columns,
+            header: !options.no_header,
+            null: options.null.clone(),
+            arrays: options.arrays,
+            array_separator: options.array_separator.clone(),
+        }
+    }
+
+    fn field(&self, value: Option<&Value>) -> Result<String> {
+        let field = match value {
+            None | Some(Value::Null) => self.null.clone(),
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(elements)) if matches!(self.arrays, Arrays::Join) => elements
+                .iter()
+                .map(|element| match element {
+                    Value::String(s) => s.clone(),
+                    element => element.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(&self.array_separator),
+            Some(value @ (Value::Array(_) | Value::Object(_))) => {
+                serde_json::to_string(value).context("failed to serialize field as JSON")?
+            }
+            Some(value) => value.to_string(),
+        };
+
+        Ok(field)
+    }
+}
+
+/// Collects the column paths of a data type, expanding
+/// nested objects into one column per field.
+fn flatten(path: &mut Vec<String>, dt: &DataType, columns: &mut Vec<Vec<String>>) {
+    match dt {
+        DataType::Object { fields } => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by_key(|(name, _)| *name);
+            for (name, dt) in fields {
+                path.push(name.to_string());
+                flatten(path, dt, columns);
+                path.pop();
+            }
+        }
+        DataType::Nullable { element, .. } | DataType::Optional { element, .. }
+            if matches!(unwrap(element.deref()), DataType::Object { .. }) =>
+        {
+            flatten(path, element, columns)
+        }
+        DataType::Member {
+            element: Some(element),
+            ..
+        } => flatten(path, element, columns),
+        _ => columns.push(path.clone()),
+    }
+}
 
 impl Writer for CsvWriter {
-    fn write(&self, v: Value) -> Result<()> {
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-
-        let mut writer = csv::Writer::from_writer(&mut stdout);
-
-        for (name, field) in v.as_object().unwrap().into_iter() {
-            match field {
-                Value::Null => writer.write_field("")?,
-                Value::Bool(b) => writer.write_field(b.to_string())?,
-                Value::Number(n) => {
-                    if n.is_f64() {
-                        writer.write_field(n.as_f64().unwrap().to_string())?
-                    } else if n.is_i64() {
-                        writer.write_field(n.as_i64().unwrap().to_string())?
-                    } else if n.is_u64() {
-                        writer.write_field(n.as_u64().unwrap().to_string())?
-                    } else {
-                        unreachable!("A number should always be f64, i64, or u64")
+    fn write(&mut self, v: Value) -> Result<()> {
+        if self.header {
+            let header = self.columns.iter().map(|path| path.join("."));
+            self.writer
+                .write_record(header)
+                .context("failed to write CSV header")?;
+            self.header = false;
+        }
+
+        let fields = self
+            .columns
+            .iter()
+            .map(|path| {
+                let value = path
+                    .iter()
+                    .try_fold(&v, |value, segment| value.get(segment));
+                self.field(value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.writer
+            .write_record(&fields)
+            .context("failed to write CSV")?;
+        self.writer.flush().context("failed to write CSV")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::flatten;
+    use data_gen_lib::schema::Schema;
+
+    #[test]
+    fn test_flatten() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "id": { "type": "serial" },
+                "customer": {
+                    "type": "optional",
+                    "missing_rate": 0.5,
+                    "element": {
+                        "type": "object",
+                        "fields": {
+                            "name": { "type": "email" },
+                            "address": {
+                                "type": "object",
+                                "fields": { "zip": { "type": "regex", "pattern": "[0-9]{5}" } }
+                            }
+                        }
                     }
-                }
-                Value::String(string) => writer.write_field(string)?,
-                Value::Array(_) => bail!(
-                    "Cannot seriailize field {}, csv output does not support array types",
-                    name
-                ),
-                Value::Object(_) => bail!(
-                    "Cannot seriailize field {}, csv output does not support map types",
-                    name
-                ),
-            }
+                },
+                "tags": { "type": "array", "element": { "type": "uuid" }, "size": 2 }
+            }"#,
+        )
+        .unwrap();
+
+        let mut columns = Vec::new();
+        for (name, dt) in schema.iter() {
+            flatten(&mut vec![name.to_string()], dt, &mut columns);
         }
 
-        writer
-            .write_record(None::<&[u8]>)
-            .context("failed to write CSV")
+        let columns: Vec<_> = columns.iter().map(|path| path.join(".")).collect();
+        assert_eq!(
+            vec!["customer.address.zip", "customer.name", "id", "tags"],
+            columns
+        );
     }
 }
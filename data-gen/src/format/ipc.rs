@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use arrow::array::RecordBatch;
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use data_gen_lib::columnar::arrow_schema;
+use data_gen_lib::schema::Schema;
+
+use super::BatchWriter;
+use crate::sink::Sink;
+
+/// Writes record batches using the Arrow IPC
+/// [streaming or file format](https://arrow.apache.org/docs/format/Columnar.html#serialization-and-interprocess-communication-ipc).
+pub enum IpcWriter {
+    Stream(StreamWriter<Sink>),
+    File(FileWriter<Sink>),
+}
+
+impl IpcWriter {
+    pub fn new(out: Sink, schema: &Schema, file: bool) -> Result<IpcWriter> {
+        let schema = arrow_schema(schema);
+
+        let writer = if file {
+            IpcWriter::File(
+                FileWriter::try_new(out, &schema).context("failed to write Arrow file")?,
+            )
+        } else {
+            IpcWriter::Stream(
+                StreamWriter::try_new(out, &schema).context("failed to write Arrow stream")?,
+            )
+        };
+
+        Ok(writer)
+    }
+}
+
+impl BatchWriter for IpcWriter {
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            IpcWriter::Stream(writer) => {
+                writer
+                    .write(batch)
+                    .context("failed to write Arrow record batch")?;
+                // Flush each batch so streams can be consumed as they are written.
+                writer
+                    .get_mut()
+                    .flush()
+                    .context("failed to write Arrow record batch")
+            }
+            IpcWriter::File(writer) => writer
+                .write(batch)
+                .context("failed to write Arrow record batch"),
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        match self {
+            IpcWriter::Stream(writer) => writer.finish(),
+            IpcWriter::File(writer) => writer.finish(),
+        }
+        .context("failed to finish Arrow output")?;
+
+        let out = match self {
+            IpcWriter::Stream(writer) => writer.get_mut(),
+            IpcWriter::File(writer) => writer.get_mut(),
+        };
+        out.flush().context("failed to finish Arrow output")
+    }
+}
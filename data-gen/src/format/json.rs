@@ -2,30 +2,30 @@ use serde_json::Value;
 use std::io::Write;
 
 use super::Writer;
+use crate::sink::Sink;
 use anyhow::{Context, Result};
 
 pub struct JsonWriter {
+    out: Sink,
     pretty: bool,
 }
 
 impl JsonWriter {
-    pub fn new(pretty: bool) -> JsonWriter {
-        JsonWriter { pretty }
+    pub fn new(out: Sink, pretty: bool) -> JsonWriter {
+        JsonWriter { out, pretty }
     }
 }
 
 impl Writer for JsonWriter {
-    fn write(&self, v: Value) -> Result<()> {
-        let stdout = std::io::stdout();
-        let mut stdout = stdout.lock();
-
+    fn write(&mut self, v: Value) -> Result<()> {
         if self.pretty {
-            serde_json::to_writer_pretty(&mut stdout, &v)
+            serde_json::to_writer_pretty(&mut self.out, &v)
                 .context("failed to serialize value as JSON")?;
         } else {
-            serde_json::to_writer(&mut stdout, &v).context("failed to serialize value as JSON")?;
+            serde_json::to_writer(&mut self.out, &v)
+                .context("failed to serialize value as JSON")?;
         }
 
-        writeln!(&mut stdout).context("failed to write newline")
+        writeln!(&mut self.out).context("failed to write newline")
     }
 }
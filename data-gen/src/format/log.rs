@@ -0,0 +1,605 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone};
+use clap::{ArgEnum, Args};
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::schema::Schema;
+use serde_json::{Map, Value};
+
+use super::{find, unwrap, Writer};
+use crate::sink::Sink;
+
+#[derive(Args)]
+pub struct LogOptions {
+    /// The shape of the log lines.
+    #[clap(arg_enum)]
+    style: LogStyle,
+
+    /// Maps a log field to a field of the schema as `<log field>=<path>`,
+    /// for example `--field host=client.ip`. By default log fields are
+    /// read from schema fields of the same name.
+    #[clap(long = "field", multiple_occurrences = true)]
+    fields: Vec<String>,
+
+    /// The syslog facility code.
+    #[clap(long, default_value = "1")]
+    facility: u8,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogStyle {
+    /// The Apache and Nginx combined log format.
+    Combined,
+
+    /// Syslog messages as specified by RFC 5424.
+    Rfc5424,
+
+    /// BSD syslog messages as specified by RFC 3164.
+    Rfc3164,
+
+    /// Space separated `key=value` pairs.
+    Logfmt,
+
+    /// JSON lines, starting with the timestamp, level, logger and message.
+    Json,
+}
+
+/// The fields of a log line which are read from the generated values.
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum LogField {
+    Timestamp,
+    Level,
+    Logger,
+    Message,
+    Host,
+    User,
+    Pid,
+    MsgId,
+    Method,
+    Path,
+    Protocol,
+    Status,
+    Size,
+    Referer,
+    UserAgent,
+}
+
+impl LogField {
+    fn name(&self) -> &'static str {
+        self.to_possible_value().unwrap().get_name()
+    }
+}
+
+impl LogStyle {
+    /// The log fields which are read from schema fields of the same name.
+    fn fields(&self) -> &'static [LogField] {
+        use LogField::*;
+
+        match self {
+            LogStyle::Combined => &[
+                Timestamp, Host, User, Method, Path, Protocol, Status, Size, Referer, UserAgent,
+            ],
+            LogStyle::Rfc5424 => &[Timestamp, Level, Logger, Message, Host, Pid, MsgId],
+            LogStyle::Rfc3164 => &[Timestamp, Level, Logger, Message, Host, Pid],
+            LogStyle::Logfmt | LogStyle::Json => &[Timestamp, Level, Logger, Message],
+        }
+    }
+
+    /// Whether a log field can be mapped. Logfmt and JSON
+    /// lines write any log field under its own name.
+    fn accepts(&self, field: LogField) -> bool {
+        matches!(self, LogStyle::Logfmt | LogStyle::Json) || self.fields().contains(&field)
+    }
+}
+
+/// Writes values as log lines of a common [LogStyle]. The remaining fields
+/// of a value which are not mapped to a log field are appended to logfmt
+/// and JSON lines, and written as structured data of RFC 5424 messages.
+pub struct LogWriter {
+    out: Sink,
+    style: LogStyle,
+    fields: HashMap<LogField, Vec<String>>,
+    /// The format of the timestamp field, if it is a timestamp.
+    timestamp_format: Option<String>,
+    facility: u8,
+}
+
+impl LogWriter {
+    pub fn new(out: Sink, schema: &Schema, options: &LogOptions) -> Result<LogWriter> {
+        let mut fields: HashMap<_, _> = options
+            .style
+            .fields()
+            .iter()
+            .map(|field| (*field, vec![field.name().replace('-', "_")]))
+            .collect();
+
+        // The timestamp defaults to the first timestamp of the schema
+        // which parses back to a date and time, or else the current time.
+        if schema.iter().all(|(name, _)| name != "timestamp") {
+            if let Some((name, _)) = schema.iter().find(|(_, dt)| match unwrap(dt) {
+                DataType::Timestamp { format } => {
+                    is_date_time(format.as_deref().unwrap_or("%F %r"))
+                }
+                _ => false,
+            }) {
+                fields.insert(LogField::Timestamp, vec![name.to_owned()]);
+            }
+        }
+
+        for mapping in &options.fields {
+            let (field, path) = mapping.split_once('=').ok_or_else(|| {
+                anyhow!(
+                    "invalid field mapping `{}`, expected <log field>=<path>",
+                    mapping
+                )
+            })?;
+            let field = LogField::from_str(field, true).map_err(|_| {
+                let fields: Vec<_> = LogField::value_variants()
+                    .iter()
+                    .map(|field| field.name())
+                    .collect();
+                anyhow!(
+                    "unknown log field `{}`, expected one of: {}",
+                    field,
+                    fields.join(", ")
+                )
+            })?;
+            if !options.style.accepts(field) {
+                bail!(
+                    "log field `{}` is not part of {} log lines",
+                    field.name(),
+                    options.style.to_possible_value().unwrap().get_name()
+                );
+            }
+            fields.insert(field, path.split('.').map(str::to_owned).collect());
+        }
+
+        let timestamp_format = match find(schema, &fields[&LogField::Timestamp]) {
+            Some(DataType::Timestamp { format }) => {
+                Some(format.as_deref().unwrap_or("%F %r").to_owned())
+            }
+            _ => None,
+        };
+
+        Ok(LogWriter {
+            out,
+            style: options.style,
+            fields,
+            timestamp_format,
+            facility: options.facility,
+        })
+    }
+
+    fn line(&self, mut v: Value) -> Result<String> {
+        let mut fields = HashMap::new();
+        for (field, path) in &self.fields {
+            if let Some(value) = take(&mut v, path) {
+                fields.insert(*field, value);
+            }
+        }
+        let rest = match v {
+            Value::Object(rest) => rest,
+            _ => Map::new(),
+        };
+
+        let timestamp = match fields.get(&LogField::Timestamp) {
+            Some(value) => self.timestamp(value)?,
+            None => Local::now().fixed_offset(),
+        };
+        let field_text = |field| fields.get(&field).and_then(text);
+        let dash = |field| field_text(field).unwrap_or_else(|| "-".to_owned());
+        let field_header = |field| field_text(field).map_or_else(|| "-".to_owned(), |s| header(&s));
+        let priority = self.facility as u32 * 8 + severity(fields.get(&LogField::Level));
+
+        let line = match self.style {
+            LogStyle::Combined => {
+                let quoted =
+                    |field| field_text(field).map_or_else(|| "-".to_owned(), |s| escape(&s));
+                format!(
+                    "{} - {} [{}] \"{} {} {}\" {} {} \"{}\" \"{}\"",
+                    dash(LogField::Host),
+                    dash(LogField::User),
+                    timestamp.format("%d/%b/%Y:%H:%M:%S %z"),
+                    quoted(LogField::Method),
+                    quoted(LogField::Path),
+                    field_text(LogField::Protocol)
+                        .map_or_else(|| "HTTP/1.1".to_owned(), |s| escape(&s)),
+                    dash(LogField::Status),
+                    dash(LogField::Size),
+                    quoted(LogField::Referer),
+                    quoted(LogField::UserAgent),
+                )
+            }
+            LogStyle::Rfc5424 => {
+                let mut params = Vec::new();
+                flatten(String::new(), &Value::Object(rest), &mut params);
+                let data = if params.is_empty() {
+                    "-".to_owned()
+                } else {
+                    let params: Vec<_> = params
+                        .into_iter()
+                        .map(|(name, value)| {
+                            let name: String = name
+                                .chars()
+                                .filter(|c| c.is_ascii_graphic() && !"=]\"".contains(*c))
+                                .take(32)
+                                .collect();
+                            let value = value
+                                .replace('\\', "\\\\")
+                                .replace('"', "\\\"")
+                                .replace(']', "\\]");
+                            format!(" {}=\"{}\"", name, value)
+                        })
+                        .collect();
+                    format!("[fields@32473{}]", params.concat())
+                };
+
+                let mut line = format!(
+                    "<{}>1 {} {} {} {} {} {}",
+                    priority,
+                    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, false),
+                    field_header(LogField::Host),
+                    field_header(LogField::Logger),
+                    field_header(LogField::Pid),
+                    field_header(LogField::MsgId),
+                    data,
+                );
+                if let Some(message) = field_text(LogField::Message) {
+                    line.push(' ');
+                    line.push_str(&message);
+                }
+                line
+            }
+            LogStyle::Rfc3164 => {
+                let tag = match (field_text(LogField::Logger), field_text(LogField::Pid)) {
+                    (Some(logger), Some(pid)) => format!("{}[{}]: ", header(&logger), pid),
+                    (Some(logger), None) => format!("{}: ", header(&logger)),
+                    (None, _) => String::new(),
+                };
+                format!(
+                    "<{}>{} {} {}{}",
+                    priority,
+                    timestamp.format("%b %e %H:%M:%S"),
+                    field_header(LogField::Host),
+                    tag,
+                    field_text(LogField::Message).unwrap_or_default(),
+                )
+            }
+            LogStyle::Logfmt => {
+                let mut pairs = Vec::new();
+                for (key, value) in keyed(fields, rest, "time", "msg", timestamp) {
+                    flatten(key, &value, &mut pairs);
+                }
+
+                let pairs: Vec<_> = pairs
+                    .into_iter()
+                    .map(|(key, value)| format!("{}={}", key, logfmt(&value)))
+                    .collect();
+                pairs.join(" ")
+            }
+            LogStyle::Json => {
+                // The conventional fields are written first, so the
+                // line is serialized pair by pair in this order.
+                let pairs = keyed(fields, rest, "timestamp", "message", timestamp)
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok(format!(
+                            "{}:{}",
+                            Value::from(key.as_str()),
+                            serde_json::to_string(value)?
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, serde_json::Error>>()
+                    .context("failed to serialize log line as JSON")?;
+                format!("{{{}}}", pairs.join(","))
+            }
+        };
+
+        Ok(line)
+    }
+
+    fn timestamp(&self, value: &Value) -> Result<DateTime<FixedOffset>> {
+        let s = match value {
+            Value::String(s) => s,
+            value => bail!("expected the timestamp to be a string, found {}", value),
+        };
+
+        let format = self.timestamp_format.as_deref().unwrap_or("%+");
+        let timestamp = DateTime::parse_from_str(s, format)
+            .ok()
+            .or_else(|| DateTime::parse_from_rfc3339(s).ok())
+            .or_else(|| {
+                let naive = NaiveDateTime::parse_from_str(s, format)
+                    .or_else(|_| NaiveDate::parse_from_str(s, format).map(|date| date.into()))
+                    .ok()?;
+                Some(Local.from_local_datetime(&naive).earliest()?.fixed_offset())
+            });
+
+        timestamp.ok_or_else(|| anyhow!("failed to parse timestamp `{}`", s))
+    }
+}
+
+impl Writer for LogWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        let line = self.line(v)?;
+
+        writeln!(self.out, "{}", line).context("failed to write log line")
+    }
+}
+
+/// Whether timestamps of the given format parse back to a date and
+/// time, unlike formats such as `%H:%M` which only hold a time.
+fn is_date_time(format: &str) -> bool {
+    let mut s = String::new();
+    if write!(s, "{}", Local::now().format(format)).is_err() {
+        return false;
+    }
+    DateTime::parse_from_str(&s, format).is_ok()
+        || NaiveDateTime::parse_from_str(&s, format).is_ok()
+}
+
+/// The key-value pairs of logfmt and JSON lines, starting with
+/// the timestamp, level, logger and message, followed by any other
+/// mapped log fields, and then the remaining fields of the value.
+fn keyed(
+    mut fields: HashMap<LogField, Value>,
+    rest: Map<String, Value>,
+    timestamp_key: &str,
+    message_key: &str,
+    timestamp: DateTime<FixedOffset>,
+) -> Vec<(String, Value)> {
+    let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, false);
+    let mut pairs = vec![(timestamp_key.to_owned(), Value::String(timestamp))];
+    fields.remove(&LogField::Timestamp);
+
+    for field in LogField::value_variants() {
+        if let Some(value) = fields.remove(field) {
+            let key = match field {
+                LogField::Message => message_key.to_owned(),
+                field => field.name().replace('-', "_"),
+            };
+            pairs.push((key, value));
+        }
+    }
+    pairs.extend(rest);
+    pairs
+}
+
+/// Removes the value at the given path, ignoring null values.
+fn take(v: &mut Value, path: &[String]) -> Option<Value> {
+    let (last, parents) = path.split_last()?;
+    let parent = parents
+        .iter()
+        .try_fold(v, |value, segment| value.get_mut(segment))?;
+    match parent.as_object_mut()?.remove(last)? {
+        Value::Null => None,
+        value => Some(value),
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// The syslog severity of a level, defaulting to informational.
+fn severity(level: Option<&Value>) -> u32 {
+    match level {
+        Some(Value::Number(n)) => n.as_u64().filter(|n| *n <= 7).map_or(6, |n| n as u32),
+        Some(Value::String(s)) => match s.to_lowercase().as_str() {
+            "emerg" | "emergency" | "panic" => 0,
+            "alert" => 1,
+            "crit" | "critical" | "fatal" => 2,
+            "err" | "error" => 3,
+            "warn" | "warning" => 4,
+            "notice" => 5,
+            "debug" | "trace" => 7,
+            _ => 6,
+        },
+        _ => 6,
+    }
+}
+
+/// Syslog header fields are printable ASCII without spaces.
+fn header(s: &str) -> String {
+    let s: String = s
+        .chars()
+        .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+        .collect();
+    if s.is_empty() {
+        "-".to_owned()
+    } else {
+        s
+    }
+}
+
+/// Escapes a quoted field of the combined log format the way Apache does.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn logfmt(s: &str) -> String {
+    if !s.is_empty() && !s.contains(|c: char| c <= ' ' || c == '=' || c == '"' || c == '\\') {
+        s.to_owned()
+    } else {
+        serde_json::to_string(s).unwrap_or_default()
+    }
+}
+
+/// Flattens nested objects into dotted keys, while
+/// arrays are written as JSON and nulls are omitted.
+fn flatten(prefix: String, value: &Value, pairs: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(key, value, pairs);
+            }
+        }
+        Value::Null => {}
+        value => pairs.extend(text(value).map(|text| (prefix, text))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogOptions, LogStyle, LogWriter};
+    use crate::sink::Sink;
+    use data_gen_lib::schema::Schema;
+    use serde_json::json;
+
+    fn log_writer(style: LogStyle, fields: &[&str]) -> LogWriter {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "at": {"type": "timestamp", "format": "%Y-%m-%dT%H:%M:%S%:z"},
+                "client": {"type": "object", "fields": {"ip": {"type": "uuid"}}}
+            }"#,
+        )
+        .unwrap();
+        let options = LogOptions {
+            style,
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            facility: 16,
+        };
+        LogWriter::new(Sink::discard(), &schema, &options).unwrap()
+    }
+
+    #[test]
+    fn test_combined() {
+        let writer = log_writer(
+            LogStyle::Combined,
+            &[
+                "host=client.ip",
+                "method=request.method",
+                "path=request.path",
+            ],
+        );
+        let value = json!({
+            "at": "2000-10-10T13:55:36-07:00",
+            "client": {"ip": "127.0.0.1"},
+            "request": {"method": "GET", "path": "/a \"b\""},
+            "status": 200,
+            "user_agent": "curl/8.0",
+        });
+
+        assert_eq!(
+            writer.line(value).unwrap(),
+            r#"127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] "GET /a \"b\" HTTP/1.1" 200 - "-" "curl/8.0""#
+        );
+    }
+
+    #[test]
+    fn test_syslog() {
+        let value = json!({
+            "at": "2003-10-11T22:14:15+00:00",
+            "host": "mymachine example",
+            "logger": "su",
+            "level": "crit",
+            "message": "'su root' failed",
+            "user": {"name": "lonvick"},
+        });
+
+        let writer = log_writer(LogStyle::Rfc5424, &["pid=user.id"]);
+        assert_eq!(
+            writer.line(value.clone()).unwrap(),
+            r#"<130>1 2003-10-11T22:14:15+00:00 mymachine_example su - - [fields@32473 user.name="lonvick"] 'su root' failed"#
+        );
+
+        let writer = log_writer(LogStyle::Rfc3164, &[]);
+        assert_eq!(
+            writer.line(value).unwrap(),
+            "<130>Oct 11 22:14:15 mymachine_example su: 'su root' failed"
+        );
+    }
+
+    #[test]
+    fn test_logfmt_and_json() {
+        let value = json!({
+            "at": "2023-11-14T22:13:20+00:00",
+            "level": "info",
+            "message": "request done",
+            "duration": 0.25,
+            "tags": ["a", "b"],
+        });
+
+        let writer = log_writer(LogStyle::Logfmt, &["logger=service"]);
+        assert_eq!(
+            writer.line(value.clone()).unwrap(),
+            r#"time=2023-11-14T22:13:20+00:00 level=info msg="request done" duration=0.25 tags="[\"a\",\"b\"]""#
+        );
+
+        let writer = log_writer(LogStyle::Json, &[]);
+        assert_eq!(
+            writer.line(value).unwrap(),
+            r#"{"timestamp":"2023-11-14T22:13:20+00:00","level":"info","message":"request done","duration":0.25,"tags":["a","b"]}"#
+        );
+    }
+
+    #[test]
+    fn test_timestamp_without_a_date() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "ts": {"type": "timestamp", "format": "%H:%M"},
+                "created": {"type": "timestamp", "format": "%d.%m.%Y %H:%M"}
+            }"#,
+        )
+        .unwrap();
+        let options = LogOptions {
+            style: LogStyle::Logfmt,
+            fields: vec![],
+            facility: 1,
+        };
+
+        // The first timestamp with a date and time is the timestamp.
+        let writer = LogWriter::new(Sink::discard(), &schema, &options).unwrap();
+        let value = json!({"ts": "22:13", "created": "14.11.2023 22:13", "msg": "done"});
+        let line = writer.line(value).unwrap();
+        assert!(line.starts_with("time=2023-11-14T22:13:00"), "{}", line);
+        assert!(line.ends_with(" ts=22:13"), "{}", line);
+
+        // Otherwise the timestamp is the current time.
+        let schema: Schema =
+            serde_json::from_str(r#"{"ts": {"type": "timestamp", "format": "%H:%M"}}"#).unwrap();
+        let writer = LogWriter::new(Sink::discard(), &schema, &options).unwrap();
+        let line = writer.line(json!({"ts": "22:13"})).unwrap();
+        let now = chrono::Local::now().format("time=%Y-%m-%d").to_string();
+        assert!(line.starts_with(&now), "{}", line);
+    }
+
+    #[test]
+    fn test_invalid_mapping() {
+        let schema = Schema::default();
+        let options = LogOptions {
+            style: LogStyle::Rfc3164,
+            fields: vec!["status=code".to_owned()],
+            facility: 1,
+        };
+
+        let error = LogWriter::new(Sink::discard(), &schema, &options)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "log field `status` is not part of rfc3164 log lines"
+        );
+    }
+}
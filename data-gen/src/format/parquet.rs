@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow::array::RecordBatch;
+use clap::ArgEnum;
+use data_gen_lib::columnar::arrow_schema;
+use data_gen_lib::schema::Schema;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{BrotliLevel, Compression as ParquetCompression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+
+use super::BatchWriter;
+use crate::sink::Sink;
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum Compression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+impl From<Compression> for ParquetCompression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Uncompressed => ParquetCompression::UNCOMPRESSED,
+            Compression::Snappy => ParquetCompression::SNAPPY,
+            Compression::Gzip => ParquetCompression::GZIP(GzipLevel::default()),
+            Compression::Lz4 => ParquetCompression::LZ4_RAW,
+            Compression::Zstd => ParquetCompression::ZSTD(ZstdLevel::default()),
+            Compression::Brotli => ParquetCompression::BROTLI(BrotliLevel::default()),
+        }
+    }
+}
+
+/// Writes record batches as a Parquet file. The column
+/// types are derived from the schema, see [arrow_schema].
+pub struct ParquetWriter {
+    writer: Option<ArrowWriter<Sink>>,
+}
+
+impl ParquetWriter {
+    pub fn new(
+        out: Sink,
+        schema: &Schema,
+        row_group_size: usize,
+        compression: Compression,
+    ) -> Result<ParquetWriter> {
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(row_group_size)
+            .set_compression(compression.into())
+            .build();
+
+        let writer = ArrowWriter::try_new(out, Arc::new(arrow_schema(schema)), Some(properties))
+            .context("failed to write Parquet file")?;
+
+        Ok(ParquetWriter {
+            writer: Some(writer),
+        })
+    }
+}
+
+impl BatchWriter for ParquetWriter {
+    fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer
+                .write(batch)
+                .context("failed to write Parquet row group")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.close().context("failed to write Parquet footer")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{DataType as ArrowType, TimeUnit};
+    use data_gen_lib::generator::DataGenerator;
+    use data_gen_lib::schema::Schema;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rand::thread_rng;
+
+    use super::{Compression, ParquetWriter};
+    use crate::format::BatchWriter;
+    use crate::sink::{Compress, Sink};
+
+    #[test]
+    fn test_round_trip() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "id": {"type": "serial"},
+                "price": {"type": "decimal", "precision": 10, "scale": 2},
+                "created": {"type": "timestamp", "format": "%Y-%m-%dT%H:%M:%S%.6f%:z"},
+                "tags": {"type": "array", "element": {"type": "regex", "pattern": "[a-z]{4}"}, "size": 3, "min_size": 0},
+                "customer": {"type": "object", "fields": {
+                    "name": {"type": "regex", "pattern": "[A-Z][a-z]{5}"},
+                    "email": {"type": "nullable", "null_rate": 0.5, "element": {"type": "email"}}
+                }}
+            }"#,
+        )
+        .unwrap();
+        let gen = DataGenerator::new(&schema);
+        let batch = gen.record_batch(&mut thread_rng(), 250).unwrap();
+
+        let path = std::env::temp_dir().join(format!("data-gen-{}.parquet", std::process::id()));
+        let sink = Sink::new(Box::new(File::create(&path).unwrap()), Compress::None).unwrap();
+        let mut writer =
+            ParquetWriter::new(sink.clone(), &schema, 100, Compression::Snappy).unwrap();
+        writer.write_batch(&batch).unwrap();
+        writer.finish().unwrap();
+        sink.close().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.schema().fields(), gen.arrow_schema().fields());
+        let types: Vec<_> = reader
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.data_type().clone())
+            .collect();
+        assert!(matches!(
+            types.as_slice(),
+            [
+                ArrowType::Timestamp(TimeUnit::Microsecond, Some(_)),
+                ArrowType::Struct(_),
+                ArrowType::Int32,
+                ArrowType::Decimal128(10, 2),
+                ArrowType::List(_),
+            ]
+        ));
+
+        let row_groups: Vec<_> = reader
+            .metadata()
+            .row_groups()
+            .iter()
+            .map(|row_group| row_group.num_rows())
+            .collect();
+        assert_eq!(row_groups, [100, 100, 50]);
+
+        let batches = reader
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let read = concat_batches(&gen.arrow_schema(), &batches).unwrap();
+        assert_eq!(read.num_rows(), 250);
+        assert_eq!(read, batch);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use data_gen_lib::import::protobuf::{compile_file, find_message};
+use prost_reflect::prost::Message;
+use prost_reflect::{DynamicMessage, Kind, MessageDescriptor, OneofDescriptor};
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+/// Writes values as length-delimited Protobuf messages. Values
+/// are mapped to the message using the canonical Protobuf JSON mapping,
+/// where an object named after a `oneof` holds the field which is set.
+pub struct ProtobufWriter {
+    out: Sink,
+    message: MessageDescriptor,
+}
+
+impl ProtobufWriter {
+    /// Creates a new writer for a message defined in the given `.proto`
+    /// file. The message may be omitted if the file defines only one.
+    pub fn new(out: Sink, path: &str, message: Option<&str>) -> Result<ProtobufWriter> {
+        let pool = compile_file(Path::new(path))
+            .with_context(|| format!("failed to compile Protobuf definition {}", path))?;
+        let message = find_message(&pool, message)
+            .with_context(|| format!("failed to select a message of {}", path))?;
+
+        Ok(ProtobufWriter { out, message })
+    }
+}
+
+impl Writer for ProtobufWriter {
+    fn write(&mut self, mut v: Value) -> Result<()> {
+        flatten_oneofs(&self.message, &mut v);
+        let message = DynamicMessage::deserialize(self.message.clone(), v).with_context(|| {
+            format!(
+                "failed to convert value to message {}",
+                self.message.full_name()
+            )
+        })?;
+
+        self.out
+            .write_all(&message.encode_length_delimited_to_vec())
+            .context("failed to write Protobuf")?;
+        self.out.flush().context("failed to write Protobuf")
+    }
+}
+
+/// Moves the field of each object named after a `oneof`, as
+/// imported from Protobuf, into the message holding the `oneof`.
+fn flatten_oneofs(message: &MessageDescriptor, v: &mut Value) {
+    let Value::Object(fields) = v else {
+        return;
+    };
+
+    // proto3 `optional` fields are in a `oneof` of their own
+    let synthetic = |oneof: &OneofDescriptor| {
+        oneof
+            .fields()
+            .any(|field| field.field_descriptor_proto().proto3_optional())
+    };
+    for oneof in message.oneofs().filter(|oneof| !synthetic(oneof)) {
+        if let Some(Value::Object(set)) = fields.remove(oneof.name()) {
+            fields.extend(set);
+        }
+    }
+
+    for field in message.fields() {
+        let Kind::Message(nested) = field.kind() else {
+            continue;
+        };
+        let name = if fields.contains_key(field.json_name()) {
+            field.json_name()
+        } else {
+            field.name()
+        };
+        let Some(value) = fields.get_mut(name) else {
+            continue;
+        };
+
+        if field.is_map() {
+            let nested = match nested.map_entry_value_field().kind() {
+                Kind::Message(nested) => nested,
+                _ => continue,
+            };
+            if let Value::Object(entries) = value {
+                for entry in entries.values_mut() {
+                    flatten_oneofs(&nested, entry);
+                }
+            }
+        } else if let Value::Array(elements) = value {
+            for element in elements {
+                flatten_oneofs(&nested, element);
+            }
+        } else {
+            flatten_oneofs(&nested, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flatten_oneofs, ProtobufWriter};
+    use crate::format::Writer;
+    use crate::sink::{Compress, Sink};
+    use data_gen_lib::generator::DataGenerator;
+    use data_gen_lib::import::protobuf::{compile, find_message, import};
+    use prost_reflect::prost::Message;
+    use prost_reflect::DynamicMessage;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde_json::Value;
+    use std::fs::{self, File};
+
+    const ORDER: &str = r#"
+        syntax = "proto3";
+
+        message Order {
+            message Line {
+                string sku = 1;
+                uint32 quantity = 2;
+                oneof price {
+                    double amount = 3;
+                    bool free = 4;
+                }
+            }
+
+            int64 id = 1;
+            string email = 2;
+            repeated Line lines = 3;
+            map<string, Line> returns = 4;
+            optional string note = 5;
+            oneof payment {
+                string card = 6;
+                string voucher = 7;
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = std::env::temp_dir();
+        let proto = dir.join(format!("data-gen-{}.proto", std::process::id()));
+        let path = dir.join(format!("data-gen-{}.pb", std::process::id()));
+        fs::write(&proto, ORDER).unwrap();
+
+        let import = import(ORDER).unwrap();
+        let gen = DataGenerator::new(&import.schema);
+        let values: Vec<Value> = StdRng::seed_from_u64(7)
+            .sample_iter(&gen)
+            .take(50)
+            .collect();
+
+        let sink = Sink::new(Box::new(File::create(&path).unwrap()), Compress::None).unwrap();
+        let mut writer = ProtobufWriter::new(sink.clone(), proto.to_str().unwrap(), None).unwrap();
+        for value in &values {
+            writer.write(value.clone()).unwrap();
+        }
+        writer.finish().unwrap();
+        sink.close().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&proto).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let descriptor = find_message(&compile(ORDER).unwrap(), None).unwrap();
+        let mut buf = bytes.as_slice();
+        for value in &values {
+            let mut message = DynamicMessage::new(descriptor.clone());
+            message.merge_length_delimited(&mut buf).unwrap();
+
+            let mut expected = value.clone();
+            flatten_oneofs(&descriptor, &mut expected);
+            let expected = DynamicMessage::deserialize(descriptor.clone(), expected).unwrap();
+            assert_eq!(
+                serde_json::to_value(&expected).unwrap(),
+                serde_json::to_value(&message).unwrap()
+            );
+
+            // exactly the field held by the object of the oneof is set
+            let payment = value["payment"].as_object().unwrap();
+            for field in ["card", "voucher"] {
+                assert_eq!(
+                    payment.contains_key(field),
+                    message.has_field_by_name(field)
+                );
+            }
+            for line in message
+                .get_field_by_name("lines")
+                .unwrap()
+                .as_list()
+                .unwrap()
+            {
+                let line = line.as_message().unwrap();
+                assert!(line.has_field_by_name("amount") != line.has_field_by_name("free"));
+            }
+        }
+        assert!(buf.is_empty());
+    }
+}
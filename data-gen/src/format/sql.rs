@@ -0,0 +1,592 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use arrow::datatypes::DataType as ArrowType;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use clap::{ArgEnum, Args};
+use data_gen_lib::columnar::arrow_field;
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::schema::Schema;
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+#[derive(Args)]
+pub struct SqlOptions {
+    /// The name of the table, optionally qualified with a schema.
+    #[clap(long, default_value = "data")]
+    pub(crate) table: String,
+
+    /// Precede the data with a CREATE TABLE statement
+    /// derived from the schema.
+    #[clap(long)]
+    pub(crate) create_table: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    Mysql,
+    Sqlite,
+}
+
+/// The SQL type of a column. Objects and maps are written as
+/// JSON, as are arrays in dialects without array types.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SqlType {
+    SmallInt,
+    Integer,
+    BigInt,
+    Serial,
+    Boolean,
+    Double,
+    Numeric { precision: u32, scale: u32 },
+    Timestamp { format: String },
+    Date { format: String },
+    Time { format: String },
+    Uuid,
+    Text,
+    Array(Box<SqlType>),
+    Json,
+}
+
+pub(crate) struct Column {
+    pub(crate) name: String,
+    pub(crate) tpe: SqlType,
+    pub(crate) nullable: bool,
+}
+
+pub(crate) fn columns(schema: &Schema) -> Vec<Column> {
+    schema
+        .iter()
+        .map(|(name, dt)| {
+            let (tpe, nullable) = sql_type(name, dt);
+            Column {
+                name: name.to_string(),
+                tpe,
+                nullable,
+            }
+        })
+        .collect()
+}
+
+/// Derives the SQL type of a data-gen type, and whether it is nullable.
+fn sql_type(name: &str, dt: &DataType) -> (SqlType, bool) {
+    let tpe = match dt {
+        DataType::Nullable { element, .. } | DataType::Optional { element, .. } => {
+            return (sql_type(name, element).0, true)
+        }
+        DataType::Cardinality { element, .. } | DataType::Unique { element } => {
+            return sql_type(name, element)
+        }
+        // Members reference the values of a pool, rather than being keys
+        DataType::Member {
+            element: Some(element),
+            ..
+        } => match sql_type(name, element) {
+            (SqlType::Serial, nullable) => return (SqlType::Integer, nullable),
+            tpe => return tpe,
+        },
+        DataType::SmallInt => SqlType::SmallInt,
+        DataType::Integer => SqlType::Integer,
+        DataType::BigInt => SqlType::BigInt,
+        DataType::Serial => SqlType::Serial,
+        DataType::Range { from, to, .. } => {
+            if i32::try_from(*from).is_ok() && i32::try_from(to - 1).is_ok() {
+                SqlType::Integer
+            } else {
+                SqlType::BigInt
+            }
+        }
+        DataType::Boolean => SqlType::Boolean,
+        DataType::Float { .. } => SqlType::Double,
+        DataType::Decimal {
+            precision, scale, ..
+        } => SqlType::Numeric {
+            precision: *precision,
+            scale: *scale,
+        },
+        DataType::Timestamp { format } => {
+            let format = format.clone().unwrap_or_else(|| "%F %r".to_owned());
+            match arrow_field(name, dt).data_type() {
+                ArrowType::Timestamp(..) => SqlType::Timestamp { format },
+                ArrowType::Date32 => SqlType::Date { format },
+                ArrowType::Time64(_) => SqlType::Time { format },
+                _ => SqlType::Text,
+            }
+        }
+        DataType::Uuid => SqlType::Uuid,
+        DataType::Array { element, .. } => match sql_type(name, element).0 {
+            SqlType::Array(_) | SqlType::Json => SqlType::Json,
+            SqlType::Serial => SqlType::Array(Box::new(SqlType::Integer)),
+            element => SqlType::Array(Box::new(element)),
+        },
+        DataType::Map { .. } | DataType::Object { .. } => SqlType::Json,
+        DataType::Union { variants } => {
+            let mut types = variants.iter().map(|variant| sql_type(name, variant).0);
+            match types.next() {
+                Some(first) if types.all(|tpe| tpe == first) => first,
+                _ => SqlType::Text,
+            }
+        }
+        DataType::Email
+        | DataType::Generator { .. }
+        | DataType::Literal { .. }
+        | DataType::Member { element: None, .. }
+        | DataType::OneOf { .. }
+        | DataType::PhoneNumber
+        | DataType::Regex { .. } => SqlType::Text,
+    };
+
+    (tpe, matches!(dt, DataType::Union { .. }))
+}
+
+impl Dialect {
+    pub(crate) fn type_name(&self, tpe: &SqlType) -> String {
+        let name = match (self, tpe) {
+            (_, SqlType::SmallInt) => "smallint",
+            (Dialect::Mysql, SqlType::Integer) => "int",
+            (_, SqlType::Integer) => "integer",
+            (_, SqlType::BigInt) => "bigint",
+            (Dialect::Postgres, SqlType::Serial) => "serial",
+            (Dialect::Mysql, SqlType::Serial) => "int",
+            (Dialect::Sqlite, SqlType::Serial) => "integer",
+            (_, SqlType::Boolean) => "boolean",
+            (Dialect::Postgres, SqlType::Double) => "double precision",
+            (Dialect::Mysql, SqlType::Double) => "double",
+            (Dialect::Sqlite, SqlType::Double) => "real",
+            (Dialect::Mysql, SqlType::Numeric { precision, scale }) => {
+                return format!("decimal({}, {})", precision, scale)
+            }
+            (_, SqlType::Numeric { precision, scale }) => {
+                return format!("numeric({}, {})", precision, scale)
+            }
+            (Dialect::Postgres, SqlType::Timestamp { .. }) => "timestamp",
+            (Dialect::Mysql, SqlType::Timestamp { .. }) => "datetime(6)",
+            (Dialect::Postgres, SqlType::Date { .. }) | (Dialect::Mysql, SqlType::Date { .. }) => {
+                "date"
+            }
+            (Dialect::Postgres, SqlType::Time { .. }) => "time",
+            (Dialect::Mysql, SqlType::Time { .. }) => "time(6)",
+            (Dialect::Postgres, SqlType::Uuid) => "uuid",
+            (Dialect::Mysql, SqlType::Uuid) => "char(36)",
+            (Dialect::Postgres, SqlType::Array(element)) => {
+                return format!("{}[]", self.type_name(element))
+            }
+            (Dialect::Postgres, SqlType::Json) => "jsonb",
+            (Dialect::Mysql, SqlType::Array(_)) | (Dialect::Mysql, SqlType::Json) => "json",
+            (_, SqlType::Timestamp { .. })
+            | (_, SqlType::Date { .. })
+            | (_, SqlType::Time { .. })
+            | (_, SqlType::Uuid)
+            | (_, SqlType::Text)
+            | (_, SqlType::Array(_))
+            | (_, SqlType::Json) => "text",
+        };
+
+        name.to_owned()
+    }
+
+    /// Quotes an identifier, each part of a
+    /// qualified name is quoted separately.
+    pub(crate) fn identifier(&self, name: &str) -> String {
+        name.split('.')
+            .map(|part| match self {
+                Dialect::Mysql => format!("`{}`", part.replace('`', "``")),
+                _ => format!("\"{}\"", part.replace('"', "\"\"")),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    fn string(&self, s: &str) -> String {
+        match self {
+            Dialect::Mysql => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+            _ => format!("'{}'", s.replace('\'', "''")),
+        }
+    }
+
+    /// Renders a value as a SQL literal for a column of the given type.
+    fn literal(&self, tpe: &SqlType, value: &Value) -> String {
+        let numeric = matches!(
+            tpe,
+            SqlType::SmallInt
+                | SqlType::Integer
+                | SqlType::BigInt
+                | SqlType::Serial
+                | SqlType::Double
+                | SqlType::Numeric { .. }
+        );
+
+        match (tpe, value) {
+            (_, Value::Null) => "NULL".to_owned(),
+            (SqlType::Boolean, Value::Bool(b)) => match (self, b) {
+                (Dialect::Sqlite, true) => "1".to_owned(),
+                (Dialect::Sqlite, false) => "0".to_owned(),
+                (_, true) => "TRUE".to_owned(),
+                (_, false) => "FALSE".to_owned(),
+            },
+            (_, Value::Number(n)) if numeric => n.to_string(),
+            // decimals too precise for numbers are strings of their digits
+            (SqlType::Numeric { .. }, Value::String(s)) if is_decimal(s) => s.clone(),
+            _ => match self.text(tpe, value) {
+                Some(text) => self.string(&text),
+                None => "NULL".to_owned(),
+            },
+        }
+    }
+
+    /// The text representation of a value, or `None` when it is null.
+    /// Timestamps are normalized to ISO 8601, which all dialects parse.
+    pub(crate) fn text(&self, tpe: &SqlType, value: &Value) -> Option<String> {
+        let text = match (tpe, value) {
+            (_, Value::Null) => return None,
+            (SqlType::Timestamp { format }, Value::String(s)) => {
+                let timestamp = DateTime::parse_from_str(s, format)
+                    .map(|timestamp| timestamp.naive_utc())
+                    .or_else(|_| NaiveDateTime::parse_from_str(s, format));
+                match timestamp {
+                    Ok(timestamp) => timestamp.format("%F %T%.f").to_string(),
+                    Err(_) => s.clone(),
+                }
+            }
+            (SqlType::Date { format }, Value::String(s)) => NaiveDate::parse_from_str(s, format)
+                .map_or_else(|_| s.clone(), |date| date.format("%F").to_string()),
+            (SqlType::Time { format }, Value::String(s)) => NaiveTime::parse_from_str(s, format)
+                .map_or_else(|_| s.clone(), |time| time.format("%T%.f").to_string()),
+            (SqlType::Array(element), Value::Array(elements)) if *self == Dialect::Postgres => {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|value| match (self.text(element, value), value) {
+                        (None, _) => "NULL".to_owned(),
+                        (Some(text), Value::Number(_) | Value::Bool(_)) => text,
+                        (Some(text), _) => {
+                            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+                        }
+                    })
+                    .collect();
+                format!("{{{}}}", elements.join(","))
+            }
+            (_, Value::String(s)) => s.clone(),
+            (_, value) => value.to_string(),
+        };
+
+        Some(text)
+    }
+
+    pub(crate) fn create_table(&self, table: &str, columns: &[Column]) -> String {
+        let definitions: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                let mut definition = format!(
+                    "  {} {}",
+                    self.identifier(&column.name),
+                    self.type_name(&column.tpe)
+                );
+                if !column.nullable {
+                    definition.push_str(" NOT NULL");
+                }
+                definition
+            })
+            .collect();
+
+        format!(
+            "CREATE TABLE {} (\n{}\n);\n",
+            self.identifier(table),
+            definitions.join(",\n")
+        )
+    }
+}
+
+/// Writes values as batched multi-row `INSERT` statements.
+pub struct InsertWriter {
+    out: Sink,
+    dialect: Dialect,
+    table: String,
+    columns: Vec<Column>,
+    create_table: bool,
+    batch: Vec<String>,
+    batch_size: usize,
+}
+
+impl InsertWriter {
+    pub fn new(
+        out: Sink,
+        schema: &Schema,
+        options: &SqlOptions,
+        dialect: Dialect,
+        batch_size: usize,
+    ) -> InsertWriter {
+        InsertWriter {
+            out,
+            dialect,
+            table: options.table.clone(),
+            columns: columns(schema),
+            create_table: options.create_table,
+            batch: Vec::new(),
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.create_table {
+            let create_table = self.dialect.create_table(&self.table, &self.columns);
+            write!(self.out, "{}", create_table).context("failed to write SQL")?;
+            self.create_table = false;
+        }
+
+        if !self.batch.is_empty() {
+            let columns: Vec<_> = self
+                .columns
+                .iter()
+                .map(|column| self.dialect.identifier(&column.name))
+                .collect();
+            writeln!(
+                self.out,
+                "INSERT INTO {} ({}) VALUES\n{};",
+                self.dialect.identifier(&self.table),
+                columns.join(", "),
+                self.batch.join(",\n")
+            )
+            .context("failed to write SQL")?;
+            self.batch.clear();
+        }
+
+        self.out.flush().context("failed to write SQL")
+    }
+}
+
+impl Writer for InsertWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        let values: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let value = v.get(&column.name).unwrap_or(&Value::Null);
+                self.dialect.literal(&column.tpe, value)
+            })
+            .collect();
+        self.batch.push(format!("  ({})", values.join(", ")));
+
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.flush()
+    }
+}
+
+/// Writes values as a Postgres `COPY ... FROM STDIN` block,
+/// using either the text or CSV format.
+pub struct CopyWriter {
+    out: Sink,
+    table: String,
+    columns: Vec<Column>,
+    create_table: bool,
+    csv: bool,
+    header: bool,
+}
+
+impl CopyWriter {
+    pub fn new(out: Sink, schema: &Schema, options: &SqlOptions, csv: bool) -> CopyWriter {
+        CopyWriter {
+            out,
+            table: options.table.clone(),
+            columns: columns(schema),
+            create_table: options.create_table,
+            csv,
+            header: true,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let dialect = Dialect::Postgres;
+        if self.create_table {
+            let create_table = dialect.create_table(&self.table, &self.columns);
+            write!(self.out, "{}", create_table).context("failed to write SQL")?;
+        }
+
+        let columns: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| dialect.identifier(&column.name))
+            .collect();
+        writeln!(
+            self.out,
+            "COPY {} ({}) FROM STDIN{};",
+            dialect.identifier(&self.table),
+            columns.join(", "),
+            if self.csv { " WITH (FORMAT csv)" } else { "" }
+        )
+        .context("failed to write SQL")?;
+
+        self.header = false;
+        Ok(())
+    }
+
+    fn field(&self, tpe: &SqlType, value: &Value) -> String {
+        copy_field(tpe, value, self.csv)
+    }
+}
+
+/// Renders a value as a field of a Postgres `COPY` row
+/// in the text format, or in the CSV format.
+pub(crate) fn copy_field(tpe: &SqlType, value: &Value, csv: bool) -> String {
+    let text = Dialect::Postgres.text(tpe, value);
+    match (text, csv) {
+        (None, true) => String::new(),
+        (None, false) => "\\N".to_owned(),
+        (Some(text), true) => {
+            if text.is_empty() || text == "\\." || text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text
+            }
+        }
+        (Some(text), false) => text
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    }
+}
+
+impl Writer for CopyWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+
+        let fields: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| self.field(&column.tpe, v.get(&column.name).unwrap_or(&Value::Null)))
+            .collect();
+        let delimiter = if self.csv { "," } else { "\t" };
+        writeln!(self.out, "{}", fields.join(delimiter)).context("failed to write SQL")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+        writeln!(self.out, "\\.").context("failed to write SQL")?;
+        self.out.flush().context("failed to write SQL")
+    }
+}
+
+/// Whether the string is the digits of a decimal, such as `-123.45`.
+fn is_decimal(s: &str) -> bool {
+    s.strip_prefix('-')
+        .unwrap_or(s)
+        .split('.')
+        .enumerate()
+        .all(|(i, part)| i < 2 && !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{columns, CopyWriter, Dialect, SqlOptions, SqlType};
+    use crate::sink::Sink;
+    use data_gen_lib::schema::Schema;
+    use serde_json::json;
+
+    #[test]
+    fn test_literal() {
+        let text = json!("it's a \\ test");
+        assert_eq!(
+            "'it''s a \\ test'",
+            Dialect::Postgres.literal(&SqlType::Text, &text)
+        );
+        assert_eq!(
+            "'it''s a \\\\ test'",
+            Dialect::Mysql.literal(&SqlType::Text, &text)
+        );
+
+        assert_eq!(
+            "TRUE",
+            Dialect::Postgres.literal(&SqlType::Boolean, &json!(true))
+        );
+        assert_eq!(
+            "1",
+            Dialect::Sqlite.literal(&SqlType::Boolean, &json!(true))
+        );
+        assert_eq!("42", Dialect::Mysql.literal(&SqlType::Integer, &json!(42)));
+        assert_eq!("'42'", Dialect::Mysql.literal(&SqlType::Text, &json!(42)));
+
+        let numeric = SqlType::Numeric {
+            precision: 30,
+            scale: 10,
+        };
+        assert_eq!(
+            "-12345678901234567890.1234567891",
+            Dialect::Postgres.literal(&numeric, &json!("-12345678901234567890.1234567891"))
+        );
+
+        let timestamp = SqlType::Timestamp {
+            format: "%F %r".to_owned(),
+        };
+        assert_eq!(
+            "'2023-11-14 22:13:20'",
+            Dialect::Mysql.literal(&timestamp, &json!("2023-11-14 10:13:20 PM"))
+        );
+
+        let array = SqlType::Array(Box::new(SqlType::Text));
+        let elements = json!(["a", "b \"c\"", null]);
+        assert_eq!(
+            r#"'{"a","b \"c\"",NULL}'"#,
+            Dialect::Postgres.literal(&array, &elements)
+        );
+        assert_eq!(
+            r#"'["a","b \"c\"",null]'"#,
+            Dialect::Sqlite.literal(&array, &elements)
+        );
+    }
+
+    #[test]
+    fn test_create_table() {
+        let schema: Schema = serde_json::from_str(
+            r#"{
+                "id": { "type": "serial" },
+                "email": { "type": "nullable", "element": { "type": "email" }, "null_rate": 0.1 },
+                "price": { "type": "decimal", "precision": 8, "scale": 2 },
+                "created_at": { "type": "timestamp" },
+                "tags": { "type": "array", "element": { "type": "uuid" }, "size": 3 },
+                "address": { "type": "object", "fields": {} }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "CREATE TABLE \"public\".\"users\" (\n  \"address\" jsonb NOT NULL,\n  \"created_at\" timestamp NOT NULL,\n  \"email\" text,\n  \"id\" serial NOT NULL,\n  \"price\" numeric(8, 2) NOT NULL,\n  \"tags\" uuid[] NOT NULL\n);\n",
+            Dialect::Postgres.create_table("public.users", &columns(&schema))
+        );
+        assert_eq!(
+            "CREATE TABLE `users` (\n  `address` json NOT NULL,\n  `created_at` datetime(6) NOT NULL,\n  `email` text,\n  `id` int NOT NULL,\n  `price` decimal(8, 2) NOT NULL,\n  `tags` json NOT NULL\n);\n",
+            Dialect::Mysql.create_table("users", &columns(&schema))
+        );
+    }
+
+    #[test]
+    fn test_copy_field() {
+        let options = SqlOptions {
+            table: "data".to_owned(),
+            create_table: false,
+        };
+        let text = CopyWriter::new(Sink::discard(), &Schema::default(), &options, false);
+        let csv = CopyWriter::new(Sink::discard(), &Schema::default(), &options, true);
+
+        let value = json!("a\tb\\c\n");
+        assert_eq!("a\\tb\\\\c\\n", text.field(&SqlType::Text, &value));
+        assert_eq!("\"a\tb\\c\n\"", csv.field(&SqlType::Text, &value));
+
+        assert_eq!("\\N", text.field(&SqlType::Text, &json!(null)));
+        assert_eq!("", csv.field(&SqlType::Text, &json!(null)));
+        assert_eq!("\"\"", csv.field(&SqlType::Text, &json!("")));
+    }
+}
@@ -0,0 +1,186 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use minijinja::{context, Environment};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+const RECORD: &str = "record";
+const HEADER: &str = "header";
+const FOOTER: &str = "footer";
+
+#[derive(Args)]
+pub struct TemplateOptions {
+    /// The template rendered once per value.
+    path: String,
+
+    /// A template rendered once before the first value.
+    #[clap(long)]
+    header: Option<String>,
+
+    /// A template rendered once after the last value.
+    #[clap(long)]
+    footer: Option<String>,
+}
+
+/// Renders each value with a [Jinja](https://jinja.palletsprojects.com)
+/// like template, whose variables are the fields of the value.
+pub struct TemplateWriter {
+    out: Sink,
+    env: Environment<'static>,
+    header: bool,
+    footer: bool,
+}
+
+impl TemplateWriter {
+    pub fn new(out: Sink, options: &TemplateOptions) -> Result<TemplateWriter> {
+        let read = |path: &str| {
+            std::fs::read_to_string(path).with_context(|| format!("failed to read file: {}", path))
+        };
+
+        // Values are escaped based on the extension of
+        // the template, such as `.xml` or `.html`.
+        let name = Path::new(&options.path)
+            .file_name()
+            .map_or(RECORD.to_owned(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+        let header = options.header.as_deref().map(read).transpose()?;
+        let footer = options.footer.as_deref().map(read).transpose()?;
+
+        TemplateWriter::from_sources(out, &name, read(&options.path)?, header, footer)
+    }
+
+    fn from_sources(
+        out: Sink,
+        name: &str,
+        record: String,
+        header: Option<String>,
+        footer: Option<String>,
+    ) -> Result<TemplateWriter> {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+
+        let auto_escape = minijinja::default_auto_escape_callback(name);
+        env.set_auto_escape_callback(move |_| auto_escape);
+
+        for (section, source) in [(RECORD, Some(record)), (HEADER, header), (FOOTER, footer)] {
+            if let Some(source) = source {
+                env.add_template_owned(section, source)
+                    .with_context(|| format!("invalid {} template", section))?;
+            }
+        }
+
+        Ok(TemplateWriter {
+            out,
+            header: env.get_template(HEADER).is_ok(),
+            footer: env.get_template(FOOTER).is_ok(),
+            env,
+        })
+    }
+
+    fn render(&self, section: &str, ctx: impl Serialize) -> Result<String> {
+        self.env
+            .get_template(section)
+            .and_then(|template| template.render(ctx))
+            .with_context(|| format!("failed to render {} template", section))
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let header = self.render(HEADER, context! {})?;
+        write!(self.out, "{}", header).context("failed to write template")?;
+        self.header = false;
+        Ok(())
+    }
+}
+
+impl Writer for TemplateWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+
+        let record = self.render(RECORD, &v)?;
+        write!(self.out, "{}", record).context("failed to write template")?;
+        self.out.flush().context("failed to write template")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+        if self.footer {
+            let footer = self.render(FOOTER, context! {})?;
+            write!(self.out, "{}", footer).context("failed to write template")?;
+        }
+        self.out.flush().context("failed to write template")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TemplateWriter, HEADER, RECORD};
+    use crate::sink::Sink;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let writer = TemplateWriter::from_sources(
+            Sink::discard(),
+            "access.log",
+            r#"{{ ip }} - [{{ time }}] "{{ request.method }} {{ request.path }}" {{ status }}{% if agent is defined %} "{{ agent }}"{% endif %}
+"#
+            .to_owned(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let value = json!({
+            "ip": "10.0.0.1",
+            "time": "10/Oct/2000:13:55:36 +0000",
+            "request": {"method": "GET", "path": "/a?b=<c>"},
+            "status": 200,
+        });
+        assert_eq!(
+            writer.render(RECORD, &value).unwrap(),
+            "10.0.0.1 - [10/Oct/2000:13:55:36 +0000] \"GET /a?b=<c>\" 200\n"
+        );
+    }
+
+    #[test]
+    fn test_render_xml() {
+        let writer = TemplateWriter::from_sources(
+            Sink::discard(),
+            "orders.xml",
+            r#"<order id="{{ id }}">{% for item in items %}<item>{{ item }}</item>{% endfor %}</order>
+"#
+            .to_owned(),
+            Some("<orders>\n".to_owned()),
+            Some("</orders>\n".to_owned()),
+        )
+        .unwrap();
+
+        let value = json!({"id": 1, "items": ["fish & chips", "<tea>"]});
+        assert_eq!(
+            writer.render(RECORD, &value).unwrap(),
+            "<order id=\"1\"><item>fish &amp; chips</item><item>&lt;tea&gt;</item></order>\n"
+        );
+        assert!(writer.header && writer.footer);
+        assert_eq!(writer.render(HEADER, json!({})).unwrap(), "<orders>\n");
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        let error =
+            TemplateWriter::from_sources(Sink::discard(), "t", "{% for %}".to_owned(), None, None)
+                .err()
+                .unwrap();
+        assert_eq!(error.to_string(), "invalid record template");
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+#[derive(Args)]
+pub struct XmlOptions {
+    /// The name of the root element.
+    #[clap(long, default_value = "records")]
+    root: String,
+
+    /// The name of the element of each value.
+    #[clap(long, default_value = "record")]
+    record: String,
+
+    /// The name of the elements of array items.
+    #[clap(long, default_value = "item")]
+    item: String,
+
+    /// A field to write as an attribute instead of an element, such as
+    /// `id` or `customer.id`. Fields of array items are addressed by the
+    /// path of the array, for example `lines.sku`.
+    #[clap(long = "attribute", multiple_occurrences = true)]
+    attributes: Vec<String>,
+
+    /// Write every field holding a string, number or
+    /// boolean as an attribute instead of an element.
+    #[clap(long)]
+    all_attributes: bool,
+
+    /// Indent nested elements.
+    #[clap(short, long)]
+    pretty: bool,
+}
+
+/// Writes values as XML elements of a single document, preserving nested
+/// objects as elements and arrays as sequences of item elements. Null and
+/// missing fields are omitted.
+pub struct XmlWriter {
+    out: Sink,
+    root: String,
+    record: String,
+    item: String,
+    attributes: HashSet<String>,
+    all_attributes: bool,
+    pretty: bool,
+    header: bool,
+}
+
+impl XmlWriter {
+    pub fn new(out: Sink, options: &XmlOptions) -> XmlWriter {
+        XmlWriter {
+            out,
+            root: name(&options.root),
+            record: name(&options.record),
+            item: name(&options.item),
+            attributes: options.attributes.iter().cloned().collect(),
+            all_attributes: options.all_attributes,
+            pretty: options.pretty,
+            header: true,
+        }
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        writeln!(self.out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")
+            .and_then(|_| writeln!(self.out, "<{}>", self.root))
+            .context("failed to write XML")?;
+        self.header = false;
+        Ok(())
+    }
+
+    fn is_attribute(&self, path: &str, value: &Value) -> bool {
+        matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_))
+            && (self.all_attributes || self.attributes.contains(path))
+    }
+
+    /// Appends the element of a value, whose path is
+    /// used to find the fields written as attributes.
+    fn element(&self, out: &mut String, name: &str, path: &str, value: &Value, depth: usize) {
+        let indent = |out: &mut String, depth| {
+            if self.pretty {
+                out.push_str(&"  ".repeat(depth));
+            }
+        };
+        let newline = |out: &mut String| {
+            if self.pretty {
+                out.push('\n');
+            }
+        };
+
+        indent(out, depth);
+        out.push('<');
+        out.push_str(name);
+
+        // Array items are always elements, since
+        // attributes of an element must be unique.
+        let (attributes, elements): (Vec<_>, Vec<_>) = match value {
+            Value::Object(fields) => fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(field, value)| (self::name(field), join(path, field), value))
+                .partition(|(_, path, value)| self.is_attribute(path, value)),
+            Value::Array(items) => (
+                Vec::new(),
+                items
+                    .iter()
+                    .filter(|value| !value.is_null())
+                    .map(|value| (self.item.clone(), path.to_owned(), value))
+                    .collect(),
+            ),
+            Value::String(s) => {
+                out.push('>');
+                out.push_str(&escape(s, false));
+                close(out, name);
+                return newline(out);
+            }
+            value => {
+                out.push('>');
+                out.push_str(&value.to_string());
+                close(out, name);
+                return newline(out);
+            }
+        };
+
+        // Distinct map keys may have the same name once sanitized,
+        // in which case the later fields are written as elements.
+        let mut names = HashSet::new();
+        let (attributes, duplicates): (Vec<_>, Vec<_>) = attributes
+            .into_iter()
+            .partition(|(name, _, _)| names.insert(name.clone()));
+        let elements: Vec<_> = duplicates.into_iter().chain(elements).collect();
+
+        for (name, _, value) in attributes {
+            let text = match value {
+                Value::String(s) => escape(s, true),
+                value => value.to_string(),
+            };
+            out.push_str(&format!(" {}=\"{}\"", name, text));
+        }
+
+        if elements.is_empty() {
+            out.push_str("/>");
+            return newline(out);
+        }
+
+        out.push('>');
+        newline(out);
+        for (name, path, value) in elements {
+            self.element(out, &name, &path, value, depth + 1);
+        }
+        indent(out, depth);
+        close(out, name);
+        newline(out);
+    }
+}
+
+impl Writer for XmlWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+
+        let mut element = String::new();
+        self.element(&mut element, &self.record, "", &v, 1);
+        if !self.pretty {
+            element.push('\n');
+        }
+        write!(self.out, "{}", element).context("failed to write XML")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.header {
+            self.write_header()?;
+        }
+        writeln!(self.out, "</{}>", self.root).context("failed to write XML")?;
+        self.out.flush().context("failed to write XML")
+    }
+}
+
+fn close(out: &mut String, name: &str) {
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+fn join(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_owned()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+/// Replaces the characters which are not allowed in XML names,
+/// since the keys of maps may be arbitrary strings.
+fn name(s: &str) -> String {
+    let mut name: String = s
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Escapes text or attribute values, dropping the
+/// control characters which XML does not allow.
+fn escape(s: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' if attribute => escaped.push_str(&format!("&#{};", c as u32)),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XmlOptions, XmlWriter};
+    use crate::sink::Sink;
+    use serde_json::json;
+
+    fn xml_writer(attributes: &[&str], all_attributes: bool, pretty: bool) -> XmlWriter {
+        XmlWriter::new(
+            Sink::discard(),
+            &XmlOptions {
+                root: "orders".to_owned(),
+                record: "order".to_owned(),
+                item: "line".to_owned(),
+                attributes: attributes.iter().map(|path| path.to_string()).collect(),
+                all_attributes,
+                pretty,
+            },
+        )
+    }
+
+    #[test]
+    fn test_element() {
+        let value = json!({
+            "id": 7,
+            "customer": {"id": "c-1", "name": "Fish & Chips <Ltd>"},
+            "lines": [{"sku": "A\"1", "qty": 2}, {"sku": "B2", "qty": null}],
+            "tags": [],
+            "note": null,
+            "attrs": {"1st key": true, "1st_key": false},
+        });
+
+        let mut out = String::new();
+        xml_writer(&["id", "customer.id", "lines.sku"], false, false)
+            .element(&mut out, "order", "", &value, 1);
+        assert_eq!(
+            out,
+            concat!(
+                r#"<order id="7"><attrs><_1st_key>true</_1st_key><_1st_key>false</_1st_key></attrs>"#,
+                r#"<customer id="c-1"><name>Fish &amp; Chips &lt;Ltd&gt;</name></customer>"#,
+                r#"<lines><line sku="A&quot;1"><qty>2</qty></line><line sku="B2"/></lines>"#,
+                r#"<tags/></order>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_pretty_attributes() {
+        let value = json!({
+            "id": 1,
+            "name": "a\nb",
+            "tags": ["x"],
+            "owner": {"id": 2, "the id": 3, "the_id": 4},
+        });
+
+        let mut out = String::new();
+        xml_writer(&[], true, true).element(&mut out, "order", "", &value, 1);
+        assert_eq!(
+            out,
+            concat!(
+                "  <order id=\"1\" name=\"a&#10;b\">\n",
+                "    <owner id=\"2\" the_id=\"3\">\n",
+                "      <the_id>4</the_id>\n",
+                "    </owner>\n",
+                "    <tags>\n",
+                "      <line>x</line>\n",
+                "    </tags>\n",
+                "  </order>\n"
+            )
+        );
+    }
+}
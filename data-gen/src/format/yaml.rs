@@ -0,0 +1,26 @@
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use super::Writer;
+use crate::sink::Sink;
+
+/// Writes values as a stream of YAML
+/// documents, each starting with a `---` marker.
+pub struct YamlWriter {
+    out: Sink,
+}
+
+impl YamlWriter {
+    pub fn new(out: Sink) -> YamlWriter {
+        YamlWriter { out }
+    }
+}
+
+impl Writer for YamlWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        let yaml = serde_yaml::to_string(&v).context("failed to serialize value as YAML")?;
+        write!(self.out, "---\n{}", yaml).context("failed to write YAML")
+    }
+}
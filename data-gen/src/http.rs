@@ -0,0 +1,477 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgEnum, Args};
+use serde_json::Value;
+
+use crate::format::Writer;
+use crate::sink::parse_duration;
+
+#[derive(Args)]
+pub struct HttpOptions {
+    /// The URL to send values to.
+    url: String,
+
+    /// The method of the requests.
+    #[clap(short = 'X', long, default_value = "POST")]
+    method: String,
+
+    /// A header of the requests, given as `<name>: <value>`.
+    #[clap(short = 'H', long = "header", multiple_occurrences = true)]
+    headers: Vec<String>,
+
+    /// The body of the requests, a single value as an object,
+    /// or a batch of values as an array or as NDJSON.
+    #[clap(long, arg_enum, default_value = "object")]
+    body: Body,
+
+    /// The number of values per request of array or
+    /// NDJSON bodies. Defaults to 100.
+    #[clap(long)]
+    batch_size: Option<usize>,
+
+    /// The number of requests sent at the same time.
+    #[clap(long, default_value = "1")]
+    concurrency: usize,
+
+    /// The number of times a request is retried after a connection
+    /// error, or a `429 Too Many Requests` or `5xx` response.
+    #[clap(long, default_value = "3")]
+    retries: u32,
+
+    /// The delay before the first retry, which is doubled for
+    /// every following retry of the same request.
+    #[clap(long, default_value = "100ms", parse(try_from_str = parse_duration))]
+    backoff: Duration,
+
+    /// The timeout of each request.
+    #[clap(long, default_value = "30s", parse(try_from_str = parse_duration))]
+    timeout: Duration,
+
+    /// The number of requests which may fail, after their retries,
+    /// with a connection error or a response other than `2xx` before
+    /// exiting with an error once all requests are sent.
+    #[clap(long, default_value = "0")]
+    max_errors: u64,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Body {
+    Object,
+    Array,
+    Ndjson,
+}
+
+/// The outcome of the requests sent, reported once all are sent.
+#[derive(Default, Debug)]
+struct Stats {
+    requests: u64,
+    values: u64,
+    retries: u64,
+    /// The number of requests by the status of their last response.
+    statuses: BTreeMap<u16, u64>,
+    /// The number of requests whose last attempt failed
+    /// without a response, along with the last error.
+    errors: u64,
+    last_error: Option<String>,
+}
+
+/// Sends values to an HTTP endpoint, one value or a batch of values per
+/// request, from a pool of worker threads so that requests are sent
+/// concurrently. Failed requests are retried with exponential backoff.
+pub struct HttpWriter {
+    body: Body,
+    batch_size: usize,
+    batch: Vec<Value>,
+    requests: Option<SyncSender<Request>>,
+    workers: Vec<JoinHandle<()>>,
+    stats: Arc<Mutex<Stats>>,
+    max_errors: u64,
+    started: Instant,
+}
+
+/// A request body, along with the number of values it holds.
+struct Request {
+    body: Vec<u8>,
+    values: u64,
+}
+
+/// The options shared by the worker threads.
+struct Client {
+    agent: ureq::Agent,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl HttpWriter {
+    pub fn new(options: &HttpOptions) -> Result<HttpWriter> {
+        let mut headers = options
+            .headers
+            .iter()
+            .map(|header| {
+                let (name, value) = header.split_once(':').ok_or_else(|| {
+                    anyhow!("invalid header `{}`, expected <name>: <value>", header)
+                })?;
+                Ok((name.trim().to_owned(), value.trim().to_owned()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        {
+            let content_type = match options.body {
+                Body::Object | Body::Array => "application/json",
+                Body::Ndjson => "application/x-ndjson",
+            };
+            headers.push(("Content-Type".to_owned(), content_type.to_owned()));
+        }
+
+        let batch_size = match (options.body, options.batch_size) {
+            (Body::Object, Some(_)) => bail!("--batch-size requires --body array or ndjson"),
+            (Body::Object, None) => 1,
+            (_, batch_size) => batch_size.unwrap_or(100).max(1),
+        };
+
+        let client = Arc::new(Client {
+            agent: ureq::AgentBuilder::new().timeout(options.timeout).build(),
+            method: options.method.to_uppercase(),
+            url: options.url.clone(),
+            headers,
+            retries: options.retries,
+            backoff: options.backoff,
+        });
+
+        // Bodies are queued for the workers, so that the values
+        // of the next requests are generated while sending.
+        let concurrency = options.concurrency.max(1);
+        let (sender, receiver) = sync_channel::<Request>(concurrency);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let stats = Arc::new(Mutex::new(Stats::default()));
+
+        let workers = (0..concurrency)
+            .map(|_| {
+                let client = client.clone();
+                let receiver = receiver.clone();
+                let stats = stats.clone();
+                std::thread::spawn(move || loop {
+                    let request = match receiver.lock().unwrap().recv() {
+                        Ok(request) => request,
+                        Err(_) => return,
+                    };
+                    client.send(&request, &stats);
+                })
+            })
+            .collect();
+
+        Ok(HttpWriter {
+            body: options.body,
+            batch_size,
+            batch: Vec::with_capacity(batch_size),
+            requests: Some(sender),
+            workers,
+            stats,
+            max_errors: options.max_errors,
+            started: Instant::now(),
+        })
+    }
+
+    fn send_batch(&mut self) -> Result<()> {
+        let values = std::mem::take(&mut self.batch);
+        let body = match self.body {
+            Body::Object => serde_json::to_vec(&values[0]),
+            Body::Array => serde_json::to_vec(&values),
+            Body::Ndjson => values.iter().try_fold(Vec::new(), |mut body, value| {
+                serde_json::to_writer(&mut body, value)?;
+                body.push(b'\n');
+                Ok(body)
+            }),
+        }
+        .context("failed to serialize request body")?;
+
+        let request = Request {
+            body,
+            values: values.len() as u64,
+        };
+        self.requests
+            .as_ref()
+            .and_then(|requests| requests.send(request).ok())
+            .ok_or_else(|| anyhow!("the HTTP workers stopped"))
+    }
+
+    /// Prints the number of requests by status to stderr.
+    fn report(&self) {
+        let stats = self.stats.lock().unwrap();
+        let elapsed = self.started.elapsed().as_secs_f64();
+        eprintln!(
+            "sent {} values in {} requests in {:.2}s ({:.1} requests/s), {} retries",
+            stats.values,
+            stats.requests,
+            elapsed,
+            stats.requests as f64 / elapsed.max(f64::EPSILON),
+            stats.retries
+        );
+        for (status, requests) in &stats.statuses {
+            eprintln!("  {}: {}", status, requests);
+        }
+        if let Some(error) = &stats.last_error {
+            eprintln!("  failed: {} ({})", stats.errors, error);
+        }
+    }
+}
+
+impl Writer for HttpWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        self.batch.push(v);
+        if self.batch.len() >= self.batch_size {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.batch.is_empty() {
+            self.send_batch()?;
+        }
+
+        // Closing the queue stops the workers once it is empty.
+        self.requests = None;
+        for worker in self.workers.drain(..) {
+            worker
+                .join()
+                .map_err(|_| anyhow!("an HTTP worker panicked"))?;
+        }
+
+        self.report();
+        let failed = self.stats.lock().unwrap().failed();
+        if failed > self.max_errors {
+            bail!(
+                "{} requests failed, more than --max-errors {}",
+                failed,
+                self.max_errors
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Stats {
+    /// The number of requests which failed without a
+    /// response, or whose last response was not `2xx`.
+    fn failed(&self) -> u64 {
+        let statuses: u64 = self
+            .statuses
+            .iter()
+            .filter(|(status, _)| !(200..300).contains(*status))
+            .map(|(_, requests)| requests)
+            .sum();
+        self.errors + statuses
+    }
+}
+
+impl Client {
+    /// Sends a request, retrying it after connection errors
+    /// and responses that indicate a temporary failure.
+    fn send(&self, request: &Request, stats: &Mutex<Stats>) {
+        let mut attempt = 0;
+        let outcome = loop {
+            let mut call = self.agent.request(&self.method, &self.url);
+            for (name, value) in &self.headers {
+                call = call.set(name, value);
+            }
+
+            let (outcome, retry) = match call.send_bytes(&request.body) {
+                Ok(response) => (Ok(drain(response)), false),
+                Err(ureq::Error::Status(status, response)) => {
+                    (Ok(drain(response)), status == 429 || status >= 500)
+                }
+                Err(ureq::Error::Transport(e)) => (Err(e.to_string()), true),
+            };
+
+            if !retry || attempt >= self.retries {
+                break outcome;
+            }
+            std::thread::sleep(self.backoff.saturating_mul(1 << attempt.min(16)));
+            attempt += 1;
+        };
+
+        let mut stats = stats.lock().unwrap();
+        stats.requests += 1;
+        stats.values += request.values;
+        stats.retries += attempt as u64;
+        match outcome {
+            Ok(status) => *stats.statuses.entry(status).or_default() += 1,
+            Err(e) => {
+                stats.errors += 1;
+                stats.last_error = Some(e);
+            }
+        }
+    }
+}
+
+/// Reads the body of a response, so that the connection can be
+/// reused, and returns its status.
+fn drain(response: ureq::Response) -> u16 {
+    let status = response.status();
+    let _ = io::copy(&mut response.into_reader(), &mut io::sink());
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::{Body, HttpOptions, HttpWriter};
+    use crate::format::Writer;
+
+    /// Serves the given number of requests, responding with the status
+    /// of the index of each request, and returns the request bodies.
+    fn serve(
+        listener: TcpListener,
+        requests: usize,
+        status: fn(usize) -> &'static str,
+    ) -> Vec<String> {
+        let mut bodies = Vec::new();
+        for (i, stream) in listener.incoming().take(requests).enumerate() {
+            let mut stream = BufReader::new(stream.unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+            bodies.push(String::from_utf8(body).unwrap());
+
+            write!(
+                stream.get_mut(),
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status(i)
+            )
+            .unwrap();
+        }
+        bodies
+    }
+
+    #[test]
+    fn test_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        // The first request is answered with `503 Service Unavailable`
+        let server = std::thread::spawn(move || {
+            serve(listener, 3, |i| match i {
+                0 => "503 Service Unavailable",
+                _ => "200 OK",
+            })
+        });
+
+        let mut writer = HttpWriter::new(&HttpOptions {
+            url,
+            method: "post".to_owned(),
+            headers: vec!["X-Source: data-gen".to_owned()],
+            body: Body::Array,
+            batch_size: Some(2),
+            concurrency: 1,
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+            max_errors: 0,
+        })
+        .unwrap();
+        for id in 1..=3 {
+            writer.write(json!({ "id": id })).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // The first request is retried after the 503 response.
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                r#"[{"id":1},{"id":2}]"#,
+                r#"[{"id":1},{"id":2}]"#,
+                r#"[{"id":3}]"#
+            ]
+        );
+
+        let stats = writer.stats.lock().unwrap();
+        assert_eq!((stats.requests, stats.values, stats.retries), (2, 3, 1));
+        assert_eq!(stats.statuses.get(&200), Some(&2));
+    }
+
+    #[test]
+    fn test_failed_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let server =
+            std::thread::spawn(move || serve(listener, 4, |_| "500 Internal Server Error"));
+
+        let options = |url: String, max_errors| HttpOptions {
+            url,
+            method: "POST".to_owned(),
+            headers: vec![],
+            body: Body::Object,
+            batch_size: None,
+            concurrency: 1,
+            retries: 0,
+            backoff: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+            max_errors,
+        };
+
+        let mut writer = HttpWriter::new(&options(url.clone(), 0)).unwrap();
+        for id in 1..=2 {
+            writer.write(json!({ "id": id })).unwrap();
+        }
+        let e = writer.finish().unwrap_err();
+        assert_eq!(e.to_string(), "2 requests failed, more than --max-errors 0");
+
+        let mut writer = HttpWriter::new(&options(url, 2)).unwrap();
+        for id in 1..=2 {
+            writer.write(json!({ "id": id })).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(server.join().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let mut options = HttpOptions {
+            url: "http://localhost".to_owned(),
+            method: "POST".to_owned(),
+            headers: vec!["X-Source".to_owned()],
+            body: Body::Object,
+            batch_size: None,
+            concurrency: 1,
+            retries: 0,
+            backoff: Duration::from_millis(1),
+            timeout: Duration::from_secs(1),
+            max_errors: 0,
+        };
+        assert!(HttpWriter::new(&options).is_err());
+
+        options.headers.clear();
+        options.batch_size = Some(10);
+        assert!(HttpWriter::new(&options).is_err());
+    }
+}
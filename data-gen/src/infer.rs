@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::{stdin, BufReader, Read};
+
+use anyhow::{Context, Result};
+use clap::ArgEnum;
+use data_gen_lib::infer::Inferrer;
+use serde_json::{Map, Number, Value};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum InputFormat {
+    /// Newline delimited JSON objects.
+    Ndjson,
+
+    /// CSV with a header row.
+    Csv,
+}
+
+/// Infers a schema from the sample files at the given paths,
+/// and prints it to stdout. Samples are read from stdin when
+/// the path is `-`.
+pub fn run(paths: &[String], format: Option<InputFormat>, max_categories: usize) -> Result<()> {
+    let mut inferrer = Inferrer::default().max_categories(max_categories);
+
+    for path in paths {
+        let format = format.unwrap_or(if path.ends_with(".csv") {
+            InputFormat::Csv
+        } else {
+            InputFormat::Ndjson
+        });
+
+        let reader: Box<dyn Read> = if path == "-" {
+            Box::new(stdin())
+        } else {
+            let file =
+                File::open(path).with_context(|| format!("failed to read file: {}", path))?;
+            Box::new(BufReader::new(file))
+        };
+
+        match format {
+            InputFormat::Ndjson => read_ndjson(&mut inferrer, path, reader)?,
+            InputFormat::Csv => read_csv(&mut inferrer, path, reader)?,
+        }
+    }
+
+    let schema = inferrer.infer().context("failed to infer schema")?;
+    let out = serde_json::to_value(&schema)
+        .and_then(|schema| serde_json::to_string_pretty(&schema))
+        .context("failed to serialize schema")?;
+    println!("{}", out);
+
+    Ok(())
+}
+
+/// Reads a stream of JSON values, each of which is either a
+/// sample object or an array of sample objects.
+fn read_ndjson(inferrer: &mut Inferrer, path: &str, reader: impl Read) -> Result<()> {
+    for value in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        let value = value.with_context(|| format!("malformed JSON in {}", path))?;
+        match value {
+            Value::Array(samples) => {
+                for sample in samples.iter() {
+                    inferrer.add(sample).with_context(|| path.to_string())?;
+                }
+            }
+            sample => inferrer.add(&sample).with_context(|| path.to_string())?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads CSV records keyed by the header row. Cells are parsed
+/// as booleans or numbers where possible, and empty cells are null.
+fn read_csv(inferrer: &mut Inferrer, path: &str, reader: impl Read) -> Result<()> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .with_context(|| format!("malformed CSV in {}", path))?
+        .clone();
+
+    for record in reader.records() {
+        let record = record.with_context(|| format!("malformed CSV in {}", path))?;
+        let sample: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(header, cell)| (header.to_string(), cell_value(cell)))
+            .collect();
+        inferrer.add(&Value::Object(sample))?;
+    }
+
+    Ok(())
+}
+
+fn cell_value(cell: &str) -> Value {
+    if cell.is_empty() {
+        Value::Null
+    } else if let Ok(b) = cell.parse::<bool>() {
+        Value::Bool(b)
+    } else if let Ok(n) = cell.parse::<i64>() {
+        Value::Number(n.into())
+    } else if let Some(n) = cell.parse::<f64>().ok().and_then(Number::from_f64) {
+        Value::Number(n)
+    } else {
+        Value::String(cell.to_string())
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgEnum, Args};
+use rdkafka::config::ClientConfig;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{Header, Message, OwnedHeaders};
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::ClientContext;
+use serde_json::Value;
+
+use crate::format::Writer;
+use crate::sink::parse_duration;
+
+#[derive(Args)]
+pub struct KafkaOptions {
+    /// The topic to produce values to.
+    #[clap(long)]
+    topic: String,
+
+    /// The brokers to connect to, separated by commas.
+    #[clap(long, default_value = "localhost:9092")]
+    brokers: String,
+
+    /// The field whose value is the key of the messages, such as `id` or
+    /// `customer.id`. Strings are used as they are, other values as JSON.
+    #[clap(long)]
+    key: Option<String>,
+
+    /// A field whose value is sent as a message header, given as
+    /// `[<header>=]<path>`. The header is named after the field by default.
+    #[clap(long = "header", multiple_occurrences = true)]
+    headers: Vec<String>,
+
+    /// How messages are assigned to partitions. By default messages with a
+    /// key are partitioned by a hash of the key, and others at random.
+    #[clap(long, arg_enum)]
+    partitioner: Option<Partitioner>,
+
+    /// The compression codec of message batches.
+    #[clap(long, arg_enum, default_value = "none")]
+    compression: Compression,
+
+    /// The maximum number of messages per batch.
+    #[clap(long, default_value = "10000")]
+    batch_size: usize,
+
+    /// How long to wait for more messages before sending a batch.
+    #[clap(long, default_value = "5ms", parse(try_from_str = parse_duration))]
+    linger: Duration,
+
+    /// The acknowledgements required for a message to be delivered.
+    #[clap(long, arg_enum, default_value = "all")]
+    acks: Acks,
+
+    /// How long to wait for the remaining messages to be delivered.
+    #[clap(long, default_value = "30s", parse(try_from_str = parse_duration))]
+    timeout: Duration,
+
+    /// Any other producer configuration property, given as `<name>=<value>`,
+    /// such as `security.protocol=SASL_SSL`.
+    #[clap(long = "property", multiple_occurrences = true)]
+    properties: Vec<String>,
+}
+
+/// The partitioners of librdkafka, see its `partitioner` property.
+#[derive(ArgEnum, Clone, Copy)]
+enum Partitioner {
+    Random,
+    Consistent,
+    ConsistentRandom,
+    /// The partitioner of the Java client.
+    Murmur2,
+    Murmur2Random,
+    Fnv1a,
+    Fnv1aRandom,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Compression {
+    None,
+    Gzip,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Acks {
+    /// Don't wait for the broker.
+    None,
+    /// Wait for the leader of the partition.
+    Leader,
+    /// Wait for all in-sync replicas.
+    All,
+}
+
+/// Counts the delivered messages, reported once all are delivered.
+#[derive(Default)]
+struct DeliveryContext {
+    stats: Mutex<Stats>,
+}
+
+#[derive(Default, Debug)]
+struct Stats {
+    /// The number of delivered messages by partition.
+    delivered: BTreeMap<i32, u64>,
+    /// The number of failed messages by error.
+    failed: BTreeMap<String, u64>,
+}
+
+impl ClientContext for DeliveryContext {}
+
+impl ProducerContext for DeliveryContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        let mut stats = self.stats.lock().unwrap();
+        match result {
+            Ok(message) => *stats.delivered.entry(message.partition()).or_default() += 1,
+            Err((e, _)) => *stats.failed.entry(e.to_string()).or_default() += 1,
+        }
+    }
+}
+
+/// Produces values as JSON messages to a Kafka topic. Messages are sent
+/// in batches by a background thread, and their delivery is reported
+/// once the writer is finished.
+pub struct KafkaWriter {
+    producer: ThreadedProducer<DeliveryContext>,
+    topic: String,
+    key: Option<Vec<String>>,
+    headers: Vec<(String, Vec<String>)>,
+    timeout: Duration,
+    started: Instant,
+}
+
+impl KafkaWriter {
+    pub fn new(options: &KafkaOptions) -> Result<KafkaWriter> {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", &options.brokers)
+            .set("compression.codec", compression(options.compression))
+            .set("batch.num.messages", options.batch_size.max(1).to_string())
+            .set("linger.ms", options.linger.as_millis().to_string())
+            .set("acks", acks(options.acks));
+        if let Some(partitioner) = options.partitioner {
+            config.set("partitioner", partitioner_name(partitioner));
+        }
+        for property in &options.properties {
+            let (name, value) = property.split_once('=').ok_or_else(|| {
+                anyhow!("invalid property `{}`, expected <name>=<value>", property)
+            })?;
+            config.set(name.trim(), value.trim());
+        }
+
+        let headers = options
+            .headers
+            .iter()
+            .map(|spec| {
+                let (name, path) = spec.split_once('=').unwrap_or_else(|| {
+                    let name = spec.rsplit('.').next().unwrap_or(spec);
+                    (name, spec)
+                });
+                if name.is_empty() || path.is_empty() {
+                    bail!("invalid header `{}`, expected [<header>=]<path>", spec);
+                }
+                Ok((name.to_owned(), split(path)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let producer = config
+            .create_with_context(DeliveryContext::default())
+            .context("failed to create Kafka producer")?;
+
+        Ok(KafkaWriter {
+            producer,
+            topic: options.topic.clone(),
+            key: options.key.as_deref().map(split),
+            headers,
+            timeout: options.timeout,
+            started: Instant::now(),
+        })
+    }
+
+    fn stats(&self) -> std::sync::MutexGuard<'_, Stats> {
+        self.producer.context().stats.lock().unwrap()
+    }
+
+    /// Prints the number of delivered messages by partition to stderr.
+    fn report(&self) {
+        let stats = self.stats();
+        let delivered: u64 = stats.delivered.values().sum();
+        let elapsed = self.started.elapsed().as_secs_f64();
+        eprintln!(
+            "delivered {} messages to {} in {:.2}s ({:.1} messages/s)",
+            delivered,
+            self.topic,
+            elapsed,
+            delivered as f64 / elapsed.max(f64::EPSILON)
+        );
+        for (partition, messages) in &stats.delivered {
+            eprintln!("  partition {}: {}", partition, messages);
+        }
+        for (error, messages) in &stats.failed {
+            eprintln!("  failed: {} ({})", messages, error);
+        }
+    }
+}
+
+impl Writer for KafkaWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        let payload = serde_json::to_vec(&v).context("failed to serialize message")?;
+        let key = self.key.as_ref().and_then(|path| text(&v, path));
+
+        let mut headers = OwnedHeaders::new_with_capacity(self.headers.len());
+        for (name, path) in &self.headers {
+            headers = headers.insert(Header {
+                key: name,
+                value: text(&v, path).as_ref(),
+            });
+        }
+
+        let mut record = BaseRecord::to(&self.topic)
+            .payload(&payload)
+            .headers(headers);
+        if let Some(key) = &key {
+            record = record.key(key);
+        }
+
+        // The queue of the producer is full while the broker is
+        // slower than the generator, until messages are delivered.
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => return Ok(()),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), rejected)) => {
+                    record = rejected;
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err((e, _)) => return Err(e).context("failed to produce message"),
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let flushed = self.producer.flush(self.timeout);
+        self.report();
+        flushed.context("failed to deliver messages in time")?;
+
+        let failed: u64 = self.stats().failed.values().sum();
+        if failed > 0 {
+            bail!("failed to deliver {} messages", failed);
+        }
+        Ok(())
+    }
+}
+
+fn split(path: &str) -> Vec<String> {
+    path.split('.').map(str::to_owned).collect()
+}
+
+/// The text of the field at the given path, if it is not null.
+fn text(v: &Value, path: &[String]) -> Option<String> {
+    match path
+        .iter()
+        .try_fold(v, |value, segment| value.get(segment))?
+    {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn partitioner_name(partitioner: Partitioner) -> &'static str {
+    match partitioner {
+        Partitioner::Random => "random",
+        Partitioner::Consistent => "consistent",
+        Partitioner::ConsistentRandom => "consistent_random",
+        Partitioner::Murmur2 => "murmur2",
+        Partitioner::Murmur2Random => "murmur2_random",
+        Partitioner::Fnv1a => "fnv1a",
+        Partitioner::Fnv1aRandom => "fnv1a_random",
+    }
+}
+
+fn compression(compression: Compression) -> &'static str {
+    match compression {
+        Compression::None => "none",
+        Compression::Gzip => "gzip",
+        Compression::Snappy => "snappy",
+        Compression::Lz4 => "lz4",
+        Compression::Zstd => "zstd",
+    }
+}
+
+fn acks(acks: Acks) -> &'static str {
+    match acks {
+        Acks::None => "0",
+        Acks::Leader => "1",
+        Acks::All => "all",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{BaseConsumer, Consumer};
+    use rdkafka::message::{Headers, Message};
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::{Offset, TopicPartitionList};
+    use serde_json::json;
+
+    use super::{Acks, Compression, KafkaOptions, KafkaWriter, Partitioner};
+    use crate::format::Writer;
+
+    #[test]
+    fn test_produce() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("orders", 3, 1).unwrap();
+
+        let mut writer = KafkaWriter::new(&KafkaOptions {
+            topic: "orders".to_owned(),
+            brokers: cluster.bootstrap_servers(),
+            key: Some("customer.id".to_owned()),
+            headers: vec!["trace=meta.trace".to_owned(), "region".to_owned()],
+            partitioner: Some(Partitioner::Murmur2),
+            compression: Compression::Zstd,
+            batch_size: 100,
+            linger: Duration::from_millis(1),
+            acks: Acks::All,
+            timeout: Duration::from_secs(10),
+            properties: vec!["client.id=data-gen-test".to_owned()],
+        })
+        .unwrap();
+        for id in 0..10 {
+            writer
+                .write(json!({
+                    "id": id,
+                    "customer": {"id": id % 2},
+                    "meta": {"trace": format!("t-{}", id)},
+                    "region": null,
+                }))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        // Messages with the same key are delivered to the same partition.
+        let delivered = writer.stats().delivered.clone();
+        assert_eq!(delivered.values().sum::<u64>(), 10);
+        assert!(delivered.values().all(|messages| messages % 5 == 0));
+
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", cluster.bootstrap_servers())
+            .set("group.id", "test")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .unwrap();
+        let mut partitions = TopicPartitionList::new();
+        for partition in 0..3 {
+            partitions
+                .add_partition_offset("orders", partition, Offset::Beginning)
+                .unwrap();
+        }
+        consumer.assign(&partitions).unwrap();
+
+        let mut received = 0;
+        while received < 10 {
+            let message = match consumer.poll(Duration::from_secs(10)) {
+                Some(message) => message.unwrap(),
+                None => panic!("received {} of 10 messages", received),
+            };
+            let value: serde_json::Value =
+                serde_json::from_slice(message.payload().unwrap()).unwrap();
+            assert_eq!(
+                message.key(),
+                Some(value["customer"]["id"].to_string().as_bytes())
+            );
+
+            let headers = message.headers().unwrap();
+            assert_eq!(headers.count(), 2);
+            assert_eq!(headers.get(0).key, "trace");
+            assert_eq!(
+                headers.get(0).value,
+                Some(format!("t-{}", value["id"]).as_bytes())
+            );
+            assert_eq!(headers.get(1).key, "region");
+            assert_eq!(headers.get(1).value, None);
+            received += 1;
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use std::io::Write;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use clap::{ArgEnum, Args};
+use data_gen_lib::schema::Schema;
+use postgres::types::{ToSql, Type};
+use postgres::NoTls;
+use serde_json::Value;
+
+use crate::format::sql::{columns, copy_field, Column, Dialect, SqlOptions, SqlType};
+use crate::format::Writer;
+
+/// The most parameters of a statement in Postgres and SQLite.
+const POSTGRES_MAX_PARAMETERS: usize = 65535;
+const SQLITE_MAX_PARAMETERS: usize = 32766;
+
+#[derive(Args)]
+pub struct LoadOptions {
+    /// The database to load into, a SQLite file as `sqlite://<path>`
+    /// or a Postgres connection URL as `postgres://<user>@<host>/<database>`.
+    url: String,
+
+    #[clap(flatten)]
+    sql: SqlOptions,
+
+    /// How rows are inserted. Defaults to COPY for
+    /// Postgres, and INSERT statements for SQLite.
+    #[clap(long, arg_enum)]
+    method: Option<Method>,
+
+    /// The number of rows per INSERT statement or COPY.
+    #[clap(long, default_value = "1000")]
+    batch_size: usize,
+
+    /// The number of rows per transaction, committed after the batch
+    /// which reaches it. Defaults to a transaction per batch.
+    #[clap(long)]
+    transaction_size: Option<u64>,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Method {
+    /// Batched prepared INSERT statements.
+    Insert,
+    /// `COPY ... FROM STDIN`, only supported by Postgres.
+    Copy,
+}
+
+enum Connection {
+    Sqlite(rusqlite::Connection),
+    Postgres(postgres::Client),
+}
+
+/// Loads values into a table of a SQLite or Postgres database,
+/// inserting a batch of rows at a time in transactions.
+pub struct LoadWriter {
+    connection: Connection,
+    dialect: Dialect,
+    method: Method,
+    table: String,
+    columns: Vec<Column>,
+    batch: Vec<Value>,
+    batch_size: usize,
+    transaction_size: u64,
+    /// The number of rows of the open transaction, if any.
+    pending: Option<u64>,
+    /// The prepared Postgres INSERT statement, and its number of rows.
+    prepared: Option<(usize, postgres::Statement)>,
+    rows: u64,
+    transactions: u64,
+    started: Instant,
+}
+
+impl LoadWriter {
+    pub fn new(schema: &Schema, options: &LoadOptions) -> Result<LoadWriter> {
+        let (connection, dialect) = if let Some(path) = options.url.strip_prefix("sqlite://") {
+            let connection = rusqlite::Connection::open(path)
+                .with_context(|| format!("failed to open SQLite database {}", path))?;
+            (Connection::Sqlite(connection), Dialect::Sqlite)
+        } else if options.url.starts_with("postgres://") || options.url.starts_with("postgresql://")
+        {
+            let client = postgres::Client::connect(&options.url, NoTls)
+                .context("failed to connect to Postgres")?;
+            (Connection::Postgres(client), Dialect::Postgres)
+        } else {
+            bail!(
+                "unsupported database `{}`, expected a sqlite:// or postgres:// URL",
+                options.url
+            );
+        };
+
+        let method = match (options.method, dialect) {
+            (Some(Method::Copy), Dialect::Sqlite) => bail!("SQLite does not support COPY"),
+            (Some(method), _) => method,
+            (None, Dialect::Postgres) => Method::Copy,
+            (None, _) => Method::Insert,
+        };
+
+        let columns = columns(schema);
+        let max_parameters = match dialect {
+            Dialect::Postgres => POSTGRES_MAX_PARAMETERS,
+            _ => SQLITE_MAX_PARAMETERS,
+        };
+        let mut batch_size = options.batch_size.max(1);
+        if method == Method::Insert {
+            batch_size = batch_size.min(max_parameters / columns.len().max(1));
+        }
+
+        let mut writer = LoadWriter {
+            connection,
+            dialect,
+            method,
+            table: options.sql.table.clone(),
+            columns,
+            batch: Vec::with_capacity(batch_size),
+            batch_size,
+            transaction_size: options.transaction_size.unwrap_or(1).max(1),
+            pending: None,
+            prepared: None,
+            rows: 0,
+            transactions: 0,
+            started: Instant::now(),
+        };
+
+        if options.sql.create_table {
+            let create_table = dialect.create_table(&writer.table, &writer.columns);
+            writer
+                .execute(&create_table)
+                .with_context(|| format!("failed to create table {}", writer.table))?;
+        }
+
+        Ok(writer)
+    }
+
+    fn execute(&mut self, sql: &str) -> Result<()> {
+        match &mut self.connection {
+            Connection::Sqlite(connection) => connection.execute_batch(sql)?,
+            Connection::Postgres(client) => client.batch_execute(sql)?,
+        }
+        Ok(())
+    }
+
+    /// Inserts the batch, in a new transaction if none is open, and
+    /// commits the transaction once it reaches the transaction size.
+    fn load_batch(&mut self) -> Result<()> {
+        if self.pending.is_none() {
+            self.execute("BEGIN")
+                .context("failed to begin transaction")?;
+            self.pending = Some(0);
+        }
+
+        let batch = std::mem::take(&mut self.batch);
+        match self.method {
+            Method::Insert => self.insert(&batch),
+            Method::Copy => self.copy(&batch),
+        }
+        .with_context(|| format!("failed to load rows into {}", self.table))?;
+
+        let rows = self.pending.unwrap_or_default() + batch.len() as u64;
+        self.rows += batch.len() as u64;
+        self.pending = Some(rows);
+        self.batch = batch;
+        self.batch.clear();
+
+        if rows >= self.transaction_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        if self.pending.take().is_some() {
+            self.execute("COMMIT")
+                .context("failed to commit transaction")?;
+            self.transactions += 1;
+        }
+        Ok(())
+    }
+
+    /// The INSERT statement of the given number of rows, whose
+    /// parameters are numbered from `$1` in Postgres and `?1` in SQLite.
+    fn insert_statement(&self, rows: usize) -> String {
+        let prefix = match self.dialect {
+            Dialect::Postgres => '$',
+            _ => '?',
+        };
+        let names: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| self.dialect.identifier(&column.name))
+            .collect();
+        let rows: Vec<_> = (0..rows)
+            .map(|row| {
+                let parameters: Vec<_> = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let parameter = format!("{}{}", prefix, row * self.columns.len() + i + 1);
+                        match self.dialect {
+                            // Parameters are sent as text and cast to the type of the column.
+                            Dialect::Postgres => format!("{}::{}", parameter, cast(&column.tpe)),
+                            _ => parameter,
+                        }
+                    })
+                    .collect();
+                format!("({})", parameters.join(", "))
+            })
+            .collect();
+
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.dialect.identifier(&self.table),
+            names.join(", "),
+            rows.join(", ")
+        )
+    }
+
+    /// The prepared Postgres INSERT statement of the given number of rows,
+    /// which is prepared once for full batches, and again for a last
+    /// batch with fewer rows.
+    fn prepare(&mut self, rows: usize) -> Result<postgres::Statement> {
+        if let Some((prepared, statement)) = &self.prepared {
+            if *prepared == rows {
+                return Ok(statement.clone());
+            }
+        }
+
+        let sql = self.insert_statement(rows);
+        let types = vec![Type::TEXT; rows * self.columns.len()];
+        let statement = match &mut self.connection {
+            Connection::Postgres(client) => client.prepare_typed(&sql, &types)?,
+            Connection::Sqlite(_) => bail!("only Postgres statements are prepared"),
+        };
+        self.prepared = Some((rows, statement.clone()));
+        Ok(statement)
+    }
+
+    fn insert(&mut self, batch: &[Value]) -> Result<()> {
+        match &self.connection {
+            Connection::Sqlite(_) => {
+                let statement = self.insert_statement(batch.len());
+                let parameters = self.parameters(batch, sqlite_value);
+                if let Connection::Sqlite(connection) = &mut self.connection {
+                    connection
+                        .prepare_cached(&statement)?
+                        .execute(rusqlite::params_from_iter(parameters))?;
+                }
+            }
+            Connection::Postgres(_) => {
+                let statement = self.prepare(batch.len())?;
+                let parameters =
+                    self.parameters(batch, |tpe, value| Dialect::Postgres.text(tpe, value));
+                let parameters: Vec<_> = parameters
+                    .iter()
+                    .map(|parameter| parameter as &(dyn ToSql + Sync))
+                    .collect();
+                if let Connection::Postgres(client) = &mut self.connection {
+                    client.execute(&statement, &parameters)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The parameters of the INSERT statement of a batch, row by row.
+    fn parameters<T>(&self, batch: &[Value], parameter: impl Fn(&SqlType, &Value) -> T) -> Vec<T> {
+        batch
+            .iter()
+            .flat_map(|value| {
+                self.columns
+                    .iter()
+                    .map(move |column| (column, value.get(&column.name).unwrap_or(&Value::Null)))
+            })
+            .map(|(column, value)| parameter(&column.tpe, value))
+            .collect()
+    }
+
+    fn copy(&mut self, batch: &[Value]) -> Result<()> {
+        let client = match &mut self.connection {
+            Connection::Postgres(client) => client,
+            Connection::Sqlite(_) => bail!("SQLite does not support COPY"),
+        };
+
+        let names: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| Dialect::Postgres.identifier(&column.name))
+            .collect();
+        let mut copy = client.copy_in(&format!(
+            "COPY {} ({}) FROM STDIN",
+            Dialect::Postgres.identifier(&self.table),
+            names.join(", ")
+        ))?;
+
+        for value in batch {
+            let fields: Vec<_> = self
+                .columns
+                .iter()
+                .map(|column| {
+                    let value = value.get(&column.name).unwrap_or(&Value::Null);
+                    copy_field(&column.tpe, value, false)
+                })
+                .collect();
+            writeln!(copy, "{}", fields.join("\t"))?;
+        }
+        copy.finish()?;
+        Ok(())
+    }
+
+    /// Prints the number of rows loaded to stderr.
+    fn report(&self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        eprintln!(
+            "loaded {} rows into {} in {} transactions in {:.2}s ({:.1} rows/s)",
+            self.rows,
+            self.table,
+            self.transactions,
+            elapsed,
+            self.rows as f64 / elapsed.max(f64::EPSILON)
+        );
+    }
+}
+
+impl Writer for LoadWriter {
+    fn write(&mut self, v: Value) -> Result<()> {
+        self.batch.push(v);
+        if self.batch.len() >= self.batch_size {
+            self.load_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if !self.batch.is_empty() {
+            self.load_batch()?;
+        }
+        self.commit()?;
+        self.report();
+        Ok(())
+    }
+}
+
+/// The Postgres type a parameter is cast to for a column of the given type.
+fn cast(tpe: &SqlType) -> String {
+    match tpe {
+        SqlType::Serial => "integer".to_owned(),
+        tpe => Dialect::Postgres.type_name(tpe),
+    }
+}
+
+/// The SQLite value of a value, where booleans are stored as integers
+/// and other values as text, normalized as in INSERT statements.
+fn sqlite_value(tpe: &SqlType, value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sqlite;
+
+    match value {
+        Value::Bool(b) => Sqlite::Integer(*b as i64),
+        Value::Number(n) if !matches!(tpe, SqlType::Text) => match n.as_i64() {
+            Some(i) => Sqlite::Integer(i),
+            None => Sqlite::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        value => match Dialect::Sqlite.text(tpe, value) {
+            Some(text) => Sqlite::Text(text),
+            None => Sqlite::Null,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_gen_lib::schema::Schema;
+    use serde_json::json;
+
+    use super::{LoadOptions, LoadWriter, Method};
+    use crate::format::sql::SqlOptions;
+    use crate::format::Writer;
+
+    fn schema() -> Schema<'static> {
+        serde_json::from_str(
+            r#"{
+                "id": { "type": "range", "from": 0, "to": 1000 },
+                "active": { "type": "boolean" },
+                "created_at": { "type": "timestamp", "format": "%Y-%m-%dT%H:%M:%S" },
+                "email": { "type": "nullable", "element": { "type": "email" }, "null_rate": 0.5 },
+                "tags": { "type": "array", "element": { "type": "uuid" }, "size": 2 }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn options(url: String, method: Option<Method>) -> LoadOptions {
+        LoadOptions {
+            url,
+            sql: SqlOptions {
+                table: "users".to_owned(),
+                create_table: true,
+            },
+            method,
+            batch_size: 2,
+            transaction_size: Some(4),
+        }
+    }
+
+    fn values() -> impl Iterator<Item = serde_json::Value> {
+        (0..5).map(|id| {
+            json!({
+                "id": id,
+                "active": id % 2 == 0,
+                "created_at": "2026-10-18T12:30:00",
+                "email": if id == 0 { json!(null) } else { json!(format!("{}@example.com", id)) },
+                "tags": ["6b5d8b5e-1d1a-4a71-9bd3-0c3fcbf9a9a1"]
+            })
+        })
+    }
+
+    #[test]
+    fn test_load_sqlite() {
+        let path = std::env::temp_dir().join(format!("data-gen-load-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let url = format!("sqlite://{}", path.display());
+        let mut writer = LoadWriter::new(&schema(), &options(url, None)).unwrap();
+        values().try_for_each(|v| writer.write(v)).unwrap();
+        writer.finish().unwrap();
+        assert_eq!((writer.rows, writer.transactions), (5, 2));
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let row: (i64, i64, String, Option<String>, String) = connection
+            .query_row(
+                "SELECT id, active, created_at, email, tags FROM users WHERE id = 1",
+                [],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            (
+                1,
+                0,
+                "2026-10-18 12:30:00".to_owned(),
+                Some("1@example.com".to_owned()),
+                r#"["6b5d8b5e-1d1a-4a71-9bd3-0c3fcbf9a9a1"]"#.to_owned()
+            )
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Loads into the Postgres database of `DATA_GEN_POSTGRES_URL`, run with
+    /// `cargo test -p data-gen test_load_postgres -- --ignored`.
+    #[test]
+    #[ignore = "requires DATA_GEN_POSTGRES_URL"]
+    fn test_load_postgres() {
+        let url = std::env::var("DATA_GEN_POSTGRES_URL")
+            .expect("DATA_GEN_POSTGRES_URL must be set to a Postgres database");
+
+        for method in [Method::Insert, Method::Copy] {
+            let mut client = postgres::Client::connect(&url, postgres::NoTls).unwrap();
+            client.batch_execute("DROP TABLE IF EXISTS users").unwrap();
+
+            let mut writer =
+                LoadWriter::new(&schema(), &options(url.clone(), Some(method))).unwrap();
+            values().try_for_each(|v| writer.write(v)).unwrap();
+            writer.finish().unwrap();
+
+            let row = client
+                .query_one(
+                    "SELECT count(*), count(email), bool_and(active), min(created_at)::text, min(tags[1]::text) FROM users",
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(row.get::<_, i64>(0), 5);
+            assert_eq!(row.get::<_, i64>(1), 4);
+            assert!(!row.get::<_, bool>(2));
+            assert_eq!(row.get::<_, String>(3), "2026-10-18 12:30:00");
+            assert_eq!(
+                row.get::<_, String>(4),
+                "6b5d8b5e-1d1a-4a71-9bd3-0c3fcbf9a9a1"
+            );
+        }
+    }
+}
@@ -1,58 +1,456 @@
 mod format;
+mod http;
+mod infer;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod load;
+mod partition;
+mod serve;
+mod sink;
+mod validate;
 
 use std::io::{stdin, Read};
 use std::ops::Div;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use rand::{thread_rng, Rng};
 
-use clap::Parser;
+use clap::{ArgEnum, Parser, Subcommand};
+use data_gen_lib::dataset::{Dataset, DatasetGenerator};
 use data_gen_lib::generator::DataGenerator;
+use data_gen_lib::import::{avro, json_schema, protobuf, sql, Import};
 use data_gen_lib::schema::Schema;
+use data_gen_lib::validate::{validate, validate_schema};
+use serde_json::Value;
 
-use crate::format::Format;
+use crate::format::{Format, Writer};
+use crate::http::{HttpOptions, HttpWriter};
+use crate::infer::InputFormat;
+#[cfg(feature = "kafka")]
+use crate::kafka::{KafkaOptions, KafkaWriter};
+use crate::load::{LoadOptions, LoadWriter};
+use crate::partition::{PartitionOptions, Partitions};
+use crate::serve::ServeOptions;
+use crate::sink::{Destination, SinkOptions};
+
+/// Set once SIGINT or SIGTERM is received, after which
+/// no more values are generated and the output is finished.
+static STOPPED: AtomicBool = AtomicBool::new(false);
 
 /// Generates realistic looking fake JSON data.
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 #[clap(propagate_version = true)]
 struct Args {
-    /// The output data format.
     #[clap(subcommand)]
-    format: Format,
+    command: Command,
 
     /// A file with the schema for data generation. If no
     /// schema file is provide, it will be read from stdin.
     #[clap(short, long, default_value = "-")]
     schema: String,
 
+    /// The format of the schema file.
+    #[clap(long, arg_enum, default_value = "data-gen")]
+    schema_format: SchemaFormat,
+
+    /// The table to generate data for when a SQL schema defines
+    /// more than one table, or the message of a Protobuf schema.
+    #[clap(long, visible_alias = "message")]
+    table: Option<String>,
+
+    /// A file defining a dataset of several tables, generated
+    /// instead of the schema. Each table is written to the
+    /// output, whose name must contain `{table}`.
+    #[clap(long, conflicts_with_all = &["schema", "rate", "count"])]
+    dataset: Option<String>,
+
     /// If set, a JSON value will be produced at a
     /// given rate per second.
     #[clap(short, long)]
     rate: Option<u32>,
+
+    /// The number of values to produce. Defaults to a single
+    /// value, or an unbounded stream if a rate is set.
+    #[clap(short = 'n', long)]
+    count: Option<u64>,
+
+    #[clap(flatten)]
+    sink: SinkOptions,
+
+    #[clap(flatten)]
+    partition: PartitionOptions,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum SchemaFormat {
+    /// The native data-gen schema format.
+    DataGen,
+
+    /// A JSON Schema document describing an object.
+    JsonSchema,
+
+    /// An Avro schema (.avsc) whose top level type is a record.
+    Avro,
+
+    /// SQL DDL with one or more CREATE TABLE statements.
+    Sql,
+
+    /// A Protobuf definition (.proto) with one or more messages.
+    Protobuf,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check a schema and report every problem found.
+    Validate {
+        /// The schema file to validate, or `-` for stdin.
+        #[clap(default_value = "-")]
+        schema: String,
+
+        /// Print the diagnostics as JSON.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Print a JSON Schema describing the schema file format.
+    SchemaSpec,
+
+    /// Infer a schema from sample NDJSON or CSV data.
+    Infer {
+        /// The sample files, or `-` for stdin.
+        #[clap(default_value = "-")]
+        samples: Vec<String>,
+
+        /// The format of the samples. Defaults to CSV for files
+        /// with a `.csv` extension, and NDJSON otherwise.
+        #[clap(long, arg_enum)]
+        input_format: Option<InputFormat>,
+
+        /// The maximum number of distinct values of a string
+        /// field for it to be inferred as a `one_of`.
+        #[clap(long, default_value = "20")]
+        max_categories: usize,
+    },
+
+    /// Send values to an HTTP endpoint.
+    Http(HttpOptions),
+
+    /// Produce values to a Kafka topic.
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaOptions),
+
+    /// Load values into a SQLite or Postgres table.
+    Load(LoadOptions),
+
+    /// Serve values over HTTP as a mock REST API.
+    Serve(ServeOptions),
+
+    #[clap(flatten)]
+    Format(Format),
+}
+
+/// Where the generated values are sent.
+enum Target {
+    Format(Format),
+    Http(HttpOptions),
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaOptions),
+    Load(LoadOptions),
 }
 
 fn main() -> Result<()> {
     let args: Args = Args::parse();
 
+    let target = match args.command {
+        Command::Validate { schema, json } => return validate::run(&schema, json),
+        Command::SchemaSpec => {
+            let spec = serde_json::to_string_pretty(&Schema::spec())
+                .context("failed to serialize schema specification")?;
+            println!("{}", spec);
+            return Ok(());
+        }
+        Command::Infer {
+            samples,
+            input_format,
+            max_categories,
+        } => return infer::run(&samples, input_format, max_categories),
+        Command::Http(options) => Target::Http(options),
+        #[cfg(feature = "kafka")]
+        Command::Kafka(options) => Target::Kafka(options),
+        Command::Load(options) => Target::Load(options),
+        Command::Serve(options) => {
+            return serve(
+                &options,
+                &args.schema,
+                args.schema_format,
+                args.table.as_deref(),
+                args.rate,
+            )
+        }
+        Command::Format(format) => Target::Format(format),
+    };
+
+    stop_on_signal()?;
+
+    if let Some(path) = &args.dataset {
+        return match target {
+            Target::Format(mut format) => dataset(path, &mut format, &args.sink, &args.partition),
+            _ => Err(anyhow!("datasets can only be written to files")),
+        };
+    }
+
     let data = &read_schema(&args.schema)?;
 
-    let schema: Schema = serde_json::from_str(data)
-        .with_context(move || format!("failed to deserialize schema\n{}", data.clone()))?;
+    let schema = load_schema(
+        args.schema_format,
+        &args.schema,
+        args.table.as_deref(),
+        data,
+    )?;
 
     let gen = DataGenerator::new(&schema);
 
-    let writer = args.format.new_writer(&schema);
+    let count = match (args.count, args.rate) {
+        (Some(count), _) => Some(count),
+        (None, Some(_)) => None,
+        (None, None) => Some(1),
+    };
+
+    let sleep_duration = args.rate.map(|rate| Duration::from_secs(1).div(rate));
+
+    match target {
+        Target::Format(format) => {
+            let destination =
+                Destination::new(&args.sink, format.path(), sleep_duration.is_some())?;
+            let mut partitions = Partitions::new(&args.partition, &format, &schema, destination)?;
+
+            generate(count, sleep_duration, format.batch_size(), |rows| {
+                partitions.write(&gen, &mut thread_rng(), rows)
+            })?;
+            partitions.finish()
+        }
+        Target::Http(options) => {
+            let mut writer = HttpWriter::new(&options)?;
+
+            generate(count, sleep_duration, None, |rows| {
+                (0..rows).try_for_each(|_| writer.write(sample(&gen)?))
+            })?;
+            writer.finish()
+        }
+        #[cfg(feature = "kafka")]
+        Target::Kafka(options) => {
+            let mut writer = KafkaWriter::new(&options)?;
+
+            generate(count, sleep_duration, None, |rows| {
+                (0..rows).try_for_each(|_| writer.write(sample(&gen)?))
+            })?;
+            writer.finish()
+        }
+        Target::Load(options) => {
+            let mut writer = LoadWriter::new(&schema, &options)?;
+
+            generate(count, sleep_duration, None, |rows| {
+                (0..rows).try_for_each(|_| writer.write(sample(&gen)?))
+            })?;
+            writer.finish()
+        }
+    }?;
 
-    if let Some(rate) = args.rate {
-        let sleep_duration = std::time::Duration::from_secs(1).div(rate);
-        loop {
-            writer.write(thread_rng().sample(&gen))?;
+    report_keys(&gen, None);
+    Ok(())
+}
+
+/// Prints how often the keys of the selections of the fields were
+/// used, with the field names prefixed by the table of a dataset.
+fn report_keys(gen: &DataGenerator, table: Option<&str>) {
+    for mut frequencies in gen.key_frequencies() {
+        if let Some(table) = table {
+            frequencies.field = format!("{}.{}", table, frequencies.field);
+        }
+        eprintln!("key frequencies of {}", frequencies);
+    }
+}
+
+/// Samples a single value, failing if the values were exhausted.
+fn sample(gen: &DataGenerator) -> Result<Value> {
+    let value = thread_rng().sample(gen);
+    gen.check()?;
+    Ok(value)
+}
+
+/// Stops generating on SIGINT or SIGTERM, so that the files are
+/// finished with their footers, while a second signal exits at once.
+fn stop_on_signal() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if STOPPED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+    })
+    .context("failed to handle signals")
+}
+
+/// Calls `write` with the number of rows to generate until the count is
+/// reached, or forever without a count, until stopped by a signal. Rows
+/// are written in batches of the given size, or one at a time at the
+/// rate of the sleep duration.
+fn generate(
+    count: Option<u64>,
+    sleep_duration: Option<Duration>,
+    batch_size: Option<usize>,
+    mut write: impl FnMut(usize) -> Result<()>,
+) -> Result<()> {
+    let mut written = 0;
+    while count.is_none_or(|count| written < count) && !STOPPED.load(Ordering::SeqCst) {
+        let mut rows = match (sleep_duration, batch_size) {
+            (None, Some(batch_size)) => batch_size as u64,
+            _ => 1,
+        };
+        rows = count.map_or(rows, |count| rows.min(count - written));
+
+        write(rows as usize)?;
+        written += rows;
+
+        if let Some(sleep_duration) = sleep_duration {
             std::thread::sleep(sleep_duration)
         }
-    } else {
-        writer.write(thread_rng().sample(&gen))?;
+    }
+    Ok(())
+}
+
+/// Generates each table of a dataset, after the tables it references,
+/// and writes it to the output with `{table}` replaced by its name.
+fn dataset(
+    path: &str,
+    format: &mut Format,
+    sink: &SinkOptions,
+    partition: &PartitionOptions,
+) -> Result<()> {
+    let data = read_schema(path)?;
+    let dataset: Dataset = serde_json::from_str(&data).context("failed to deserialize dataset")?;
+    let gen = DatasetGenerator::new(&dataset)?;
+    for table in gen.tables() {
+        let schema = gen.schema(table).context("unknown table")?;
+        check_schema(schema, table)
+            .with_context(|| format!("the schema of table `{}` is invalid", table))?;
+    }
+
+    let destination = Destination::new(sink, format.path(), false)?;
+    if !destination.is_file() || !destination.has_table() {
+        bail!("the output file name must contain `{{table}}` to write datasets");
+    }
+
+    let mut rng = thread_rng();
+    for table in gen.tables() {
+        let schema = gen.schema(table).context("unknown table")?;
+        let (table_gen, rows) = gen.table(table, &mut rng)?;
+        format.set_table(table);
+        let mut partitions = Partitions::new(partition, format, schema, destination.table(table))?;
+
+        generate(Some(rows), None, format.batch_size(), |rows| {
+            partitions.write(&table_gen, &mut rng, rows)
+        })?;
+        partitions.finish()?;
+        report_keys(&table_gen, Some(table));
+
+        if STOPPED.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Serves the schemas of the serve options, or the schema file.
+fn serve(
+    options: &ServeOptions,
+    schema_path: &str,
+    format: SchemaFormat,
+    table: Option<&str>,
+    rate: Option<u32>,
+) -> Result<()> {
+    let files = options.resources(schema_path)?;
+    let data = files
+        .iter()
+        .map(|(_, path)| read_schema(path))
+        .collect::<Result<Vec<_>>>()?;
+    let schemas = files
+        .iter()
+        .zip(&data)
+        .map(|((_, path), data)| load_schema(format, path, table, data))
+        .collect::<Result<Vec<_>>>()?;
+    let resources = files
+        .into_iter()
+        .map(|(name, _)| name)
+        .zip(schemas)
+        .collect();
+
+    serve::run(options, resources, rate)
+}
+
+fn load_schema<'a>(
+    format: SchemaFormat,
+    schema_path: &str,
+    table: Option<&str>,
+    data: &'a str,
+) -> Result<Schema<'a>> {
+    let import = match format {
+        SchemaFormat::DataGen => {
+            let diagnostics = validate(data);
+            if !diagnostics.is_empty() {
+                let diagnostics: Vec<_> = diagnostics
+                    .iter()
+                    .map(|diagnostic| format!("{}:{}", schema_path, diagnostic))
+                    .collect();
+                bail!("invalid schema\n{}", diagnostics.join("\n"));
+            }
+
+            return serde_json::from_str(data)
+                .map_err(|e| anyhow!("failed to deserialize schema: {}", e));
+        }
+        SchemaFormat::JsonSchema => json_schema::import(data)
+            .with_context(|| format!("failed to import JSON Schema {}", schema_path))?,
+        SchemaFormat::Avro => avro::import(data)
+            .with_context(|| format!("failed to import Avro schema {}", schema_path))?,
+        SchemaFormat::Sql => sql::import_table(data, table)
+            .with_context(|| format!("failed to import SQL schema {}", schema_path))?,
+        SchemaFormat::Protobuf => {
+            // Files are compiled from their path so that their imports resolve.
+            let pool = if schema_path != "-" {
+                protobuf::compile_file(Path::new(schema_path))
+            } else {
+                protobuf::compile(data)
+            };
+
+            pool.and_then(|pool| protobuf::find_message(&pool, table))
+                .map(|message| protobuf::from_descriptor(&message))
+                .with_context(|| format!("failed to import Protobuf schema {}", schema_path))?
+        }
+    };
+
+    let Import { schema, warnings } = import;
+    for warning in warnings {
+        eprintln!("warning: {}: {}", schema_path, warning);
+    }
+    check_schema(&schema, schema_path).context("the imported schema is invalid")?;
+
+    Ok(schema)
+}
+
+/// Fails with every problem of a schema which was not read from a
+/// data-gen schema file, rather than failing while generating values.
+fn check_schema(schema: &Schema, name: &str) -> Result<()> {
+    let diagnostics: Vec<_> = validate_schema(schema)
+        .iter()
+        .map(|diagnostic| format!("{}: {}: {}", name, diagnostic.path, diagnostic.message))
+        .collect();
+
+    if diagnostics.is_empty() {
         Ok(())
+    } else {
+        Err(anyhow!("{}", diagnostics.join("\n")))
     }
 }
 
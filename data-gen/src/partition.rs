@@ -0,0 +1,647 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch, UInt32Array};
+use arrow::compute::take_record_batch;
+use arrow::datatypes::{DataType as ArrowType, Date32Type, TimestampMicrosecondType};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use clap::{ArgEnum, Args};
+use data_gen_lib::columnar::{arrow_field, temporal, Temporal};
+use data_gen_lib::data_type::DataType;
+use data_gen_lib::generator::DataGenerator;
+use data_gen_lib::schema::Schema;
+use rand::Rng;
+use serde_json::Value;
+
+use crate::format::{find, Format, Output, Records};
+use crate::sink::{Destination, Sink};
+
+/// The directory of null, missing and empty values, as named by Hive.
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+#[derive(Args)]
+pub struct PartitionOptions {
+    /// Write each record below a Hive-style `<column>=<value>` directory
+    /// of the output, given as `[<column>=]<path>[:<bucket>]`, such as
+    /// `region` or `dt=created_at:day`. Timestamps are bucketed by `year`,
+    /// `month`, `day` or `hour`, and by `day` if no bucket is given.
+    #[clap(long, multiple_occurrences = true)]
+    partition_by: Vec<String>,
+
+    /// The largest number of partitions with an open file. The file of
+    /// the least recently written partition is closed to open another,
+    /// and the partition is continued in its next `{seq}` file.
+    #[clap(long, default_value = "128")]
+    max_open_partitions: usize,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Granularity {
+    Year,
+    Month,
+    Day,
+    Hour,
+}
+
+impl Granularity {
+    fn format(&self) -> &'static str {
+        match self {
+            Granularity::Year => "%Y",
+            Granularity::Month => "%Y-%m",
+            Granularity::Day => "%Y-%m-%d",
+            Granularity::Hour => "%Y-%m-%dT%H",
+        }
+    }
+}
+
+/// The date bucket of a timestamp partition column.
+struct Bucket {
+    granularity: Granularity,
+    /// The format of the timestamp field.
+    format: String,
+    kind: Temporal,
+}
+
+impl Bucket {
+    /// Formats microseconds since the epoch, or days since the epoch
+    /// for dates, as the date of the bucket they belong to.
+    fn text(&self, value: i64) -> Option<String> {
+        let timestamp = match self.kind {
+            Temporal::Timestamp => DateTime::from_timestamp_micros(value)?.naive_utc(),
+            Temporal::Date => NaiveDate::from_num_days_from_ce_opt(
+                i32::try_from(value).ok()?.checked_add(EPOCH_DAYS_FROM_CE)?,
+            )
+            .map(NaiveDateTime::from)?,
+            Temporal::Time => return None,
+        };
+        Some(timestamp.format(self.granularity.format()).to_string())
+    }
+}
+
+/// The number of days from 0001-01-01 to 1970-01-01.
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// A column the records are partitioned by.
+struct Column {
+    name: String,
+    path: Vec<String>,
+    bucket: Option<Bucket>,
+}
+
+impl Column {
+    /// Parses a column given as `[<column>=]<path>[:<bucket>]`.
+    fn new(spec: &str, schema: &Schema) -> Result<Column> {
+        let (name, rest) = match spec.split_once('=') {
+            Some((name, rest)) => (Some(name), rest),
+            None => (None, spec),
+        };
+        let (path, granularity) = match rest.rsplit_once(':') {
+            Some((path, granularity)) => {
+                let granularity = Granularity::from_str(granularity, true).map_err(|_| {
+                    anyhow!(
+                        "invalid bucket `{}` of partition `{}`, expected year, month, day or hour",
+                        granularity,
+                        spec
+                    )
+                })?;
+                (path, Some(granularity))
+            }
+            None => (rest, None),
+        };
+
+        let path: Vec<_> = path.split('.').map(str::to_owned).collect();
+        let name = name.unwrap_or_else(|| &path[path.len() - 1]).to_owned();
+        if name.is_empty() || name.contains(['/', '\\']) {
+            bail!("invalid column name of partition `{}`", spec);
+        }
+
+        let dt = find(schema, &path)
+            .ok_or_else(|| anyhow!("the partition field `{}` is not in the schema", rest))?;
+        let bucket = match dt {
+            DataType::Timestamp { format } => {
+                let format = format.as_deref().unwrap_or("%F %r").to_owned();
+                let kind = match arrow_field(&name, dt).data_type() {
+                    ArrowType::Timestamp(..) => Temporal::Timestamp,
+                    ArrowType::Date32 => Temporal::Date,
+                    _ => bail!(
+                        "the timestamp format `{}` of partition `{}` has no date",
+                        format,
+                        spec
+                    ),
+                };
+                Some(Bucket {
+                    granularity: granularity.unwrap_or(Granularity::Day),
+                    format,
+                    kind,
+                })
+            }
+            DataType::Object { .. } | DataType::Array { .. } | DataType::Map { .. } => {
+                bail!("the partition field `{}` must not be nested", rest)
+            }
+            _ if granularity.is_some() => {
+                bail!(
+                    "only timestamps can be bucketed, found partition `{}`",
+                    spec
+                )
+            }
+            _ => None,
+        };
+
+        Ok(Column { name, path, bucket })
+    }
+
+    /// The text of the column in a value, if it is not null.
+    fn value_text(&self, v: &Value) -> Result<Option<String>> {
+        let value = self
+            .path
+            .iter()
+            .try_fold(v, |value, segment| value.get(segment));
+
+        let text = match (value, &self.bucket) {
+            (None | Some(Value::Null), _) => None,
+            (Some(Value::String(s)), Some(bucket)) => {
+                let text = temporal(s, &bucket.format, bucket.kind)
+                    .and_then(|value| bucket.text(value))
+                    .ok_or_else(|| anyhow!("failed to parse timestamp `{}`", s))?;
+                Some(text)
+            }
+            (Some(Value::String(s)), None) => Some(s.clone()),
+            (Some(value), _) => Some(value.to_string()),
+        };
+        Ok(text)
+    }
+
+    /// The text of the column in each row of a record batch.
+    fn batch_texts(&self, batch: &RecordBatch) -> Result<Vec<Option<String>>> {
+        let missing = || anyhow!("missing partition column `{}`", self.path.join("."));
+
+        // The arrays along the path, where a value is
+        // null if it or any of its parents is null.
+        let mut arrays: Vec<ArrayRef> = vec![batch
+            .column_by_name(&self.path[0])
+            .ok_or_else(missing)?
+            .clone()];
+        for segment in &self.path[1..] {
+            let parent = &arrays[arrays.len() - 1];
+            let array = parent
+                .as_struct_opt()
+                .and_then(|parent| parent.column_by_name(segment))
+                .ok_or_else(missing)?;
+            arrays.push(array.clone());
+        }
+
+        let array = &arrays[arrays.len() - 1];
+        let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default())
+            .context("failed to format partition column")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                if arrays.iter().any(|array| array.is_null(i)) {
+                    return Ok(None);
+                }
+
+                let bucket = match &self.bucket {
+                    None => return Ok(Some(formatter.value(i).to_string())),
+                    Some(bucket) => bucket,
+                };
+                let value = match array.data_type() {
+                    ArrowType::Timestamp(..) => {
+                        array.as_primitive::<TimestampMicrosecondType>().value(i)
+                    }
+                    _ => array.as_primitive::<Date32Type>().value(i) as i64,
+                };
+                bucket
+                    .text(value)
+                    .map(Some)
+                    .ok_or_else(|| anyhow!("invalid timestamp {}", value))
+            })
+            .collect()
+    }
+}
+
+/// Writes the records of each partition to its own files, which
+/// are rotated independently. Without partition columns all records
+/// are written to the single partition of the output.
+pub struct Partitions<'a> {
+    format: &'a Format,
+    schema: &'a Schema<'a>,
+    destination: Destination,
+    columns: Vec<Column>,
+    parts: HashMap<String, Part>,
+    max_open: usize,
+    /// The destinations of the partitions whose files were closed,
+    /// which count the files written for each partition.
+    closed: HashMap<String, Destination>,
+    /// The number of writes, which orders the parts by their last write.
+    writes: u64,
+}
+
+impl<'a> Partitions<'a> {
+    pub fn new(
+        options: &PartitionOptions,
+        format: &'a Format,
+        schema: &'a Schema<'a>,
+        destination: Destination,
+    ) -> Result<Partitions<'a>> {
+        let columns = options
+            .partition_by
+            .iter()
+            .map(|spec| Column::new(spec, schema))
+            .collect::<Result<Vec<_>>>()?;
+
+        if destination.is_datagram() && !format.is_line_delimited() {
+            bail!(
+                "datagram sockets send each line as a datagram, which requires \
+                 a format writing a line per record, which are json and log"
+            );
+        }
+
+        let mut parts = HashMap::new();
+        if columns.is_empty() {
+            parts.insert(
+                String::new(),
+                Part::open(destination.clone(), format, schema)?,
+            );
+        } else if !destination.is_file() {
+            bail!("partitioning requires an --output file name");
+        }
+
+        Ok(Partitions {
+            format,
+            schema,
+            destination,
+            columns,
+            parts,
+            max_open: options.max_open_partitions.max(1),
+            closed: HashMap::new(),
+            writes: 0,
+        })
+    }
+
+    /// Generates and writes the given number of rows.
+    pub fn write<R: Rng>(&mut self, gen: &DataGenerator, rng: &mut R, rows: usize) -> Result<()> {
+        let records = self.format.generate(gen, rng, rows)?;
+        self.write_records(records)
+    }
+
+    /// Writes records to the files of their partitions.
+    fn write_records(&mut self, records: Records) -> Result<()> {
+        for (dir, records) in self.split(records)? {
+            if !self.parts.contains_key(&dir) && self.parts.len() >= self.max_open {
+                self.close_least_recent()?;
+            }
+
+            let part = match self.parts.entry(dir) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let destination = match self.closed.remove(entry.key()) {
+                        Some(destination) if !destination.has_seq() => bail!(
+                            "more than {} partitions were written, which requires an \
+                             output file name containing `{{seq}}` to continue partition `{}` \
+                             in a new file, or a larger --max-open-partitions",
+                            self.max_open,
+                            entry.key()
+                        ),
+                        Some(destination) => destination,
+                        None => self.destination.partition(entry.key()),
+                    };
+                    entry.insert(Part::open(destination, self.format, self.schema)?)
+                }
+            };
+            self.writes += 1;
+            part.written = self.writes;
+            part.write(self.format, self.schema, records)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the files of every partition.
+    pub fn finish(self) -> Result<()> {
+        self.parts
+            .into_values()
+            .try_for_each(|part| part.finish().map(drop))
+    }
+
+    /// Closes the file of the least recently written partition.
+    fn close_least_recent(&mut self) -> Result<()> {
+        let least_recent = self
+            .parts
+            .iter()
+            .min_by_key(|(_, part)| part.written)
+            .map(|(dir, _)| dir.clone());
+        if let Some((dir, part)) = least_recent.and_then(|dir| self.parts.remove_entry(&dir)) {
+            self.closed.insert(dir, part.finish()?);
+        }
+        Ok(())
+    }
+
+    /// Splits records by the directory of their partition.
+    fn split(&self, records: Records) -> Result<Vec<(String, Records)>> {
+        if self.columns.is_empty() {
+            return Ok(vec![(String::new(), records)]);
+        }
+
+        match records {
+            Records::Values(values) => {
+                let mut partitions: Vec<(String, Vec<Value>)> = Vec::new();
+                for value in values {
+                    let texts = self
+                        .columns
+                        .iter()
+                        .map(|column| column.value_text(&value))
+                        .collect::<Result<Vec<_>>>()?;
+                    let dir = self.dir(texts);
+                    match partitions.iter_mut().find(|(other, _)| *other == dir) {
+                        Some((_, values)) => values.push(value),
+                        None => partitions.push((dir, vec![value])),
+                    }
+                }
+                Ok(partitions
+                    .into_iter()
+                    .map(|(dir, values)| (dir, Records::Values(values)))
+                    .collect())
+            }
+            Records::Batch(batch) => {
+                let mut columns = self
+                    .columns
+                    .iter()
+                    .map(|column| column.batch_texts(&batch).map(Vec::into_iter))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let mut partitions: Vec<(String, Vec<u32>)> = Vec::new();
+                for row in 0..batch.num_rows() as u32 {
+                    let texts = columns.iter_mut().map(|texts| texts.next().flatten());
+                    let dir = self.dir(texts);
+                    match partitions.iter_mut().find(|(other, _)| *other == dir) {
+                        Some((_, rows)) => rows.push(row),
+                        None => partitions.push((dir, vec![row])),
+                    }
+                }
+
+                partitions
+                    .into_iter()
+                    .map(|(dir, rows)| {
+                        let batch = take_record_batch(&batch, &UInt32Array::from(rows))
+                            .context("failed to partition record batch")?;
+                        Ok((dir, Records::Batch(batch)))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The directory of a partition, given the text of each column.
+    fn dir(&self, texts: impl IntoIterator<Item = Option<String>>) -> String {
+        self.columns
+            .iter()
+            .zip(texts)
+            .map(|(column, text)| {
+                let value = match text {
+                    Some(text) if !text.is_empty() => escape(&text),
+                    _ => DEFAULT_PARTITION.to_owned(),
+                };
+                format!("{}={}", escape(&column.name), value)
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// The file currently written for a partition, along with the writer
+/// of the format, which is replaced whenever the file is rotated.
+struct Part {
+    destination: Destination,
+    sink: Sink,
+    output: Output,
+    /// The number of writes of all partitions when this one was last written.
+    written: u64,
+}
+
+impl Part {
+    fn open(mut destination: Destination, format: &Format, schema: &Schema) -> Result<Part> {
+        let sink = destination.open()?;
+        let output = format.new_output(schema, sink.clone())?;
+        Ok(Part {
+            destination,
+            sink,
+            output,
+            written: 0,
+        })
+    }
+
+    fn write(&mut self, format: &Format, schema: &Schema, mut records: Records) -> Result<()> {
+        while !records.is_empty() {
+            if self.destination.should_rotate(&self.sink) {
+                self.output.finish()?;
+                self.sink.close()?;
+                self.sink = self.destination.open()?;
+                self.output = format.new_output(schema, self.sink.clone())?;
+            }
+
+            // Records beyond the rotation limit are
+            // written to the next file of the partition.
+            let rows = self
+                .destination
+                .remaining()
+                .map_or(records.len(), |remaining| {
+                    records.len().min(remaining as usize)
+                });
+            let rest = records.split_off(rows);
+            self.output.write(records)?;
+            self.destination.written(rows as u64);
+            records = rest;
+        }
+        Ok(())
+    }
+
+    /// Finishes the file, returning the destination
+    /// to continue the partition in its next file.
+    fn finish(mut self) -> Result<Destination> {
+        self.output.finish()?;
+        self.sink.close()?;
+        Ok(self.destination)
+    }
+}
+
+/// Escapes the characters which Hive does not allow in partition
+/// directory names, such as `/` and `=`, as `%` followed by their code.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{FromArgMatches, Subcommand};
+    use data_gen_lib::generator::DataGenerator;
+    use data_gen_lib::schema::Schema;
+    use rand::thread_rng;
+    use serde_json::json;
+
+    use super::{escape, Column, Granularity, PartitionOptions, Partitions};
+    use crate::format::{Format, Records};
+    use crate::sink::{Destination, SinkOptions};
+
+    const SCHEMA: &str = r#"{
+        "region": {"type": "one_of", "options": ["eu", "us"]},
+        "created": {"type": "timestamp", "format": "%Y-%m-%dT%H:%M:%S%:z"},
+        "customer": {"type": "nullable", "null_rate": 0.5, "element": {"type": "object", "fields": {
+            "tier": {"type": "one_of", "options": ["gold", "a/b"]}
+        }}}
+    }"#;
+
+    #[test]
+    fn test_column() {
+        let schema: Schema = serde_json::from_str(SCHEMA).unwrap();
+
+        let column = Column::new("dt=created:hour", &schema).unwrap();
+        assert_eq!(column.name, "dt");
+        assert_eq!(column.bucket.unwrap().granularity, Granularity::Hour);
+
+        let column = Column::new("customer.tier", &schema).unwrap();
+        assert_eq!(column.name, "tier");
+        assert_eq!(column.path, ["customer", "tier"]);
+        assert!(column.bucket.is_none());
+
+        assert!(Column::new("region:day", &schema).is_err());
+        assert!(Column::new("created:week", &schema).is_err());
+        assert!(Column::new("customer", &schema).is_err());
+        assert!(Column::new("country", &schema).is_err());
+    }
+
+    #[test]
+    fn test_split() {
+        let schema: Schema = serde_json::from_str(SCHEMA).unwrap();
+        let options = PartitionOptions {
+            partition_by: vec!["dt=created".to_owned(), "customer.tier".to_owned()],
+            max_open_partitions: 128,
+        };
+        let sink = SinkOptions::default();
+        let destination = Destination::new(&sink, Some("table/part-{seq}.json"), false).unwrap();
+        let format = Format::Json { pretty: false };
+        let partitions = Partitions::new(&options, &format, &schema, destination).unwrap();
+
+        let values = vec![
+            json!({"created": "2026-10-18T23:30:00-02:00", "customer": {"tier": "a/b"}}),
+            json!({"created": "2026-10-18T10:00:00+00:00", "customer": null}),
+            json!({"created": "2026-10-19T01:30:00+00:00", "customer": {"tier": "a/b"}}),
+        ];
+        let dirs: Vec<_> = partitions
+            .split(Records::Values(values))
+            .unwrap()
+            .into_iter()
+            .map(|(dir, records)| (dir, records.len()))
+            .collect();
+        assert_eq!(
+            dirs,
+            [
+                ("dt=2026-10-19/tier=a%2Fb".to_owned(), 2),
+                (
+                    "dt=2026-10-18/tier=__HIVE_DEFAULT_PARTITION__".to_owned(),
+                    1
+                )
+            ]
+        );
+
+        // Batches are partitioned the same as values.
+        let gen = DataGenerator::new(&schema);
+        let batch = gen.record_batch(&mut thread_rng(), 100).unwrap();
+        for (dir, records) in partitions.split(Records::Batch(batch)).unwrap() {
+            let Records::Batch(batch) = records else {
+                panic!("expected a record batch")
+            };
+            let customers = batch.column_by_name("customer").unwrap();
+            assert!(dir.starts_with("dt=20"), "{}", dir);
+            assert_eq!(
+                dir.ends_with("tier=__HIVE_DEFAULT_PARTITION__"),
+                customers.null_count() == batch.num_rows(),
+                "{}",
+                dir
+            );
+        }
+    }
+
+    #[test]
+    fn test_datagram_formats() {
+        let schema: Schema = serde_json::from_str(SCHEMA).unwrap();
+        let options = PartitionOptions {
+            partition_by: vec![],
+            max_open_partitions: 128,
+        };
+        let sink = SinkOptions::default();
+        let destination = Destination::new(&sink, Some("udp://127.0.0.1:9"), false).unwrap();
+
+        let parse = |args: &[&str]| {
+            let command = Format::augment_subcommands(clap::Command::new("data-gen"));
+            Format::from_arg_matches(&command.get_matches_from(args)).unwrap()
+        };
+
+        for args in [&["", "json"][..], &["", "log", "combined"]] {
+            let format = parse(args);
+            assert!(Partitions::new(&options, &format, &schema, destination.clone()).is_ok());
+        }
+        // CSV has a header and templates may render several lines
+        for args in [
+            &["", "json", "--pretty"][..],
+            &["", "csv"],
+            &["", "copy"],
+            &["", "template", "record.j2"],
+            &["", "msgpack"],
+        ] {
+            let format = parse(args);
+            assert!(Partitions::new(&options, &format, &schema, destination.clone()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_max_open_partitions() {
+        let schema: Schema = serde_json::from_str(SCHEMA).unwrap();
+        let dir = std::env::temp_dir().join(format!("data-gen-partitions-{}", std::process::id()));
+        let options = PartitionOptions {
+            partition_by: vec!["region".to_owned()],
+            max_open_partitions: 1,
+        };
+        let sink = SinkOptions::default();
+        let path = dir.join("part-{seq}.json").display().to_string();
+        let destination = Destination::new(&sink, Some(&path), false).unwrap();
+        let format = Format::Json { pretty: false };
+        let mut partitions = Partitions::new(&options, &format, &schema, destination).unwrap();
+
+        for region in ["eu", "us", "eu"] {
+            let values = vec![json!({ "region": region })];
+            partitions.write_records(Records::Values(values)).unwrap();
+            assert_eq!(partitions.parts.len(), 1);
+        }
+        partitions.finish().unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap();
+        assert_eq!(read("region=eu/part-00000.json"), "{\"region\":\"eu\"}\n");
+        assert_eq!(read("region=us/part-00000.json"), "{\"region\":\"us\"}\n");
+        assert_eq!(read("region=eu/part-00001.json"), "{\"region\":\"eu\"}\n");
+
+        // Without `{seq}` the closed partition can not be continued.
+        let path = dir.join("part.json").display().to_string();
+        let destination = Destination::new(&sink, Some(&path), false).unwrap();
+        let mut partitions = Partitions::new(&options, &format, &schema, destination).unwrap();
+        let values = vec![json!({ "region": "eu" }), json!({ "region": "us" })];
+        partitions.write_records(Records::Values(values)).unwrap();
+        let values = vec![json!({ "region": "eu" })];
+        assert!(partitions.write_records(Records::Values(values)).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("eu-west"), "eu-west");
+        assert_eq!(escape("a=b/c:d%"), "a%3Db%2Fc%3Ad%25");
+    }
+}
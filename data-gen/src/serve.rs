@@ -0,0 +1,566 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use data_gen_lib::generator::DataGenerator;
+use data_gen_lib::schema::Schema;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// The name of the resource of the `--schema` file.
+const DEFAULT_RESOURCE: &str = "records";
+
+/// The number of values of previous pages generated again
+/// for a page of a resource with unique fields.
+const MAX_REPLAYED: u64 = 1_000_000;
+
+#[derive(Args)]
+pub struct ServeOptions {
+    /// The schemas to serve, given as `<name>=<file>` or as a file named
+    /// after its file stem. Defaults to `--schema`, named `records`.
+    schemas: Vec<String>,
+
+    /// The address to listen on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    bind: String,
+
+    /// The maximum number of values per page.
+    #[clap(long, default_value = "10000")]
+    max_count: u64,
+}
+
+impl ServeOptions {
+    /// The name and schema file of each resource.
+    pub fn resources(&self, schema: &str) -> Result<Vec<(String, String)>> {
+        if self.schemas.is_empty() {
+            return Ok(vec![(DEFAULT_RESOURCE.to_owned(), schema.to_owned())]);
+        }
+
+        let mut resources: Vec<(String, String)> = Vec::new();
+        for spec in &self.schemas {
+            let (name, path) = match spec.split_once('=') {
+                Some((name, path)) => (name.to_owned(), path.to_owned()),
+                None => {
+                    let stem = Path::new(spec).file_stem().unwrap_or_default();
+                    (stem.to_string_lossy().into_owned(), spec.clone())
+                }
+            };
+            if name.is_empty() || name.contains('/') {
+                bail!("invalid resource name `{}`", name);
+            }
+            if resources.iter().any(|(other, _)| *other == name) {
+                bail!("duplicate resource name `{}`", name);
+            }
+            resources.push((name, path));
+        }
+        Ok(resources)
+    }
+}
+
+/// Serves values of each resource as pages of JSON arrays at
+/// `/<name>`, and as streams of server-sent events or NDJSON
+/// at `/<name>/stream`. Pages are reproducible given a seed, since
+/// each request generates values with a generator of its own.
+struct Service<'a> {
+    resources: Vec<(String, Schema<'a>)>,
+    max_count: u64,
+    /// The default number of streamed values per second.
+    rate: u32,
+}
+
+/// The response to a request.
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Json {
+        status: u16,
+        body: Value,
+        /// The seed the values were generated with.
+        seed: Option<u64>,
+    },
+    Stream {
+        resource: usize,
+        format: StreamFormat,
+        rate: u32,
+        count: Option<u64>,
+        seed: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StreamFormat {
+    /// Server-sent events, with one value per event.
+    Sse,
+    Ndjson,
+}
+
+pub fn run(
+    options: &ServeOptions,
+    resources: Vec<(String, Schema)>,
+    rate: Option<u32>,
+) -> Result<()> {
+    let server = Server::http(&options.bind)
+        .map_err(|e| anyhow!("failed to listen on {}: {}", options.bind, e))?;
+
+    let service = Service {
+        resources,
+        max_count: options.max_count,
+        rate: rate.unwrap_or(1),
+    };
+    for (name, _) in &service.resources {
+        eprintln!("serving http://{}/{}", server.server_addr(), name);
+    }
+
+    serve(&server, &service);
+    Ok(())
+}
+
+/// Handles each request on its own thread, until the server is unblocked.
+fn serve(server: &Server, service: &Service) {
+    std::thread::scope(|scope| {
+        for request in server.incoming_requests() {
+            scope.spawn(move || service.handle(request));
+        }
+    });
+}
+
+impl Service<'_> {
+    fn handle(&self, request: Request) {
+        let accept = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Accept"))
+            .map(|header| header.value.to_string());
+        let reply = self.reply(request.method(), request.url(), accept.as_deref());
+
+        // Responses fail when clients disconnect, which is expected for streams.
+        let _ = match reply {
+            Reply::Json { status, body, seed } => {
+                let mut response = Response::from_string(body.to_string())
+                    .with_status_code(status)
+                    .with_header(header("Content-Type", "application/json"));
+                if let Some(seed) = seed {
+                    response.add_header(header("X-Seed", &seed.to_string()));
+                }
+                request.respond(response)
+            }
+            Reply::Stream {
+                resource,
+                format,
+                rate,
+                count,
+                seed,
+            } => {
+                let gen = DataGenerator::new(&self.resources[resource].1);
+                self.stream(request.into_writer(), &gen, format, rate, count, seed)
+            }
+        };
+    }
+
+    fn reply(&self, method: &Method, url: &str, accept: Option<&str>) -> Reply {
+        if *method != Method::Get {
+            return error(405, "only GET requests are supported");
+        }
+
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let query: Vec<_> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .collect();
+        let segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let find = |name: &str| self.resources.iter().position(|(other, _)| other == name);
+        let (resource, stream) = match segments[..] {
+            [] => {
+                let index = self
+                    .resources
+                    .iter()
+                    .map(|(name, _)| {
+                        (
+                            name.clone(),
+                            json!([format!("/{}", name), format!("/{}/stream", name)]),
+                        )
+                    })
+                    .collect();
+                return Reply::Json {
+                    status: 200,
+                    body: Value::Object(index),
+                    seed: None,
+                };
+            }
+            [name] if find(name).is_some() => (find(name), false),
+            // A single resource is also streamed at `/stream`.
+            ["stream"] if self.resources.len() == 1 => (Some(0), true),
+            [name, "stream"] => (find(name), true),
+            _ => (None, false),
+        };
+        let resource = match resource {
+            Some(resource) => resource,
+            None => return error(404, &format!("no resource at {}", path)),
+        };
+
+        let reply = if stream {
+            stream_reply(resource, &query, accept, self.rate)
+        } else {
+            self.page(resource, &query)
+        };
+        reply.unwrap_or_else(|message| error(400, &message))
+    }
+
+    /// A page of `count` values, 10 by default.
+    fn page(&self, resource: usize, query: &[(&str, &str)]) -> Result<Reply, String> {
+        let seed = param(query, "seed")?.unwrap_or_else(|| thread_rng().gen());
+        let count: u64 = param(query, "count")?.unwrap_or(10);
+        let page: u64 = param(query, "page")?.unwrap_or(1);
+        if count > self.max_count {
+            return Err(format!("the count must be at most {}", self.max_count));
+        }
+        if page == 0 {
+            return Err("pages are numbered from 1".to_owned());
+        }
+
+        // Pages continue the serials of the previous pages, and
+        // share the pools of cardinalities and their members.
+        let gen = DataGenerator::new(&self.resources[resource].1);
+        let offset = (page - 1).saturating_mul(count);
+        if gen.has_unique() {
+            // The previous pages are generated again, so
+            // that unique values are not repeated on this page.
+            if offset > MAX_REPLAYED {
+                return Err(format!(
+                    "pages of resources with unique fields end after {} values",
+                    MAX_REPLAYED
+                ));
+            }
+            gen.seek(seed, 0);
+            for previous in 1..page {
+                let mut rng = page_rng(seed, previous);
+                for _ in 0..count {
+                    let _: Value = rng.sample(&gen);
+                }
+            }
+        } else {
+            gen.seek(seed, offset);
+        }
+        let mut rng = page_rng(seed, page);
+        let values: Vec<Value> = (0..count).map(|_| rng.sample(&gen)).collect();
+        gen.check().map_err(|e| e.to_string())?;
+        Ok(Reply::Json {
+            status: 200,
+            body: Value::Array(values),
+            seed: Some(seed),
+        })
+    }
+
+    /// Writes a chunked response of values, until the
+    /// count is reached or the client disconnects.
+    fn stream(
+        &self,
+        mut out: Box<dyn Write + Send>,
+        gen: &DataGenerator,
+        format: StreamFormat,
+        rate: u32,
+        count: Option<u64>,
+        seed: u64,
+    ) -> io::Result<()> {
+        let content_type = match format {
+            StreamFormat::Sse => "text/event-stream",
+            StreamFormat::Ndjson => "application/x-ndjson",
+        };
+        write!(
+            out,
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nCache-Control: no-cache\r\nX-Seed: {}\r\n\
+             Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+            content_type, seed
+        )?;
+        out.flush()?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let interval = Duration::from_secs(1) / rate;
+        let mut sent = 0;
+        while count.is_none_or(|count| sent < count) {
+            let value: Value = rng.sample(gen);
+            // Exhausted values end the stream, like reaching the count
+            if gen.check().is_err() {
+                break;
+            }
+            let data = match format {
+                StreamFormat::Sse => format!("data: {}\n\n", value),
+                StreamFormat::Ndjson => format!("{}\n", value),
+            };
+            write!(out, "{:x}\r\n{}\r\n", data.len(), data)?;
+            out.flush()?;
+            sent += 1;
+
+            if count.is_none_or(|count| sent < count) {
+                std::thread::sleep(interval);
+            }
+        }
+
+        write!(out, "0\r\n\r\n")?;
+        out.flush()
+    }
+}
+
+/// A stream of `count` values at `rate` values per second, as server-sent
+/// events if requested by `format=sse` or the `Accept` header.
+fn stream_reply(
+    resource: usize,
+    query: &[(&str, &str)],
+    accept: Option<&str>,
+    rate: u32,
+) -> Result<Reply, String> {
+    let format = match query.iter().find(|(name, _)| *name == "format") {
+        Some((_, "sse")) => StreamFormat::Sse,
+        Some((_, "ndjson")) => StreamFormat::Ndjson,
+        Some((_, format)) => {
+            return Err(format!(
+                "unknown format `{}`, expected sse or ndjson",
+                format
+            ))
+        }
+        None if accept.is_some_and(|accept| accept.contains("text/event-stream")) => {
+            StreamFormat::Sse
+        }
+        None => StreamFormat::Ndjson,
+    };
+    let rate = param(query, "rate")?.unwrap_or(rate);
+    if rate == 0 {
+        return Err("the rate must be positive".to_owned());
+    }
+
+    Ok(Reply::Stream {
+        resource,
+        format,
+        rate,
+        count: param(query, "count")?,
+        seed: param(query, "seed")?.unwrap_or_else(|| thread_rng().gen()),
+    })
+}
+
+/// The random number generator of a page, seeded so that each
+/// page of the same seed holds different values.
+fn page_rng(seed: u64, page: u64) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_add((page - 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+}
+
+fn param<T: FromStr>(query: &[(&str, &str)], name: &str) -> Result<Option<T>, String> {
+    query
+        .iter()
+        .find(|(other, _)| *other == name)
+        .map(|(_, value)| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {} `{}`", name, value))
+        })
+        .transpose()
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    Reply::Json {
+        status,
+        body: json!({ "error": message }),
+        seed: None,
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::io::Read;
+
+    use serde_json::{json, Value};
+    use tiny_http::{Method, Server};
+
+    use super::{serve, Reply, ServeOptions, Service, StreamFormat};
+
+    const SCHEMA: &str = r#"{
+        "id": {"type": "range", "from": 1, "to": 1000000},
+        "name": {"type": "regex", "pattern": "[a-z]{8}"}
+    }"#;
+
+    fn service(names: &[&str]) -> Service<'static> {
+        Service {
+            resources: names
+                .iter()
+                .map(|name| (name.to_string(), serde_json::from_str(SCHEMA).unwrap()))
+                .collect(),
+            max_count: 100,
+            rate: 1,
+        }
+    }
+
+    #[test]
+    fn test_resources() {
+        let mut options = ServeOptions {
+            schemas: vec![
+                "users=schemas/user.json".to_owned(),
+                "orders.json".to_owned(),
+            ],
+            bind: String::new(),
+            max_count: 1,
+        };
+        assert_eq!(
+            options.resources("-").unwrap(),
+            [
+                ("users".to_owned(), "schemas/user.json".to_owned()),
+                ("orders".to_owned(), "orders.json".to_owned())
+            ]
+        );
+
+        options.schemas.push("users.json".to_owned());
+        assert!(options.resources("-").is_err());
+
+        options.schemas.clear();
+        assert_eq!(
+            options.resources("-").unwrap(),
+            [("records".to_owned(), "-".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_page() {
+        let service = service(&["users", "orders"]);
+        let page = |url| match service.reply(&Method::Get, url, None) {
+            Reply::Json { status, body, .. } => (status, body),
+            reply => panic!("unexpected reply {:?}", reply),
+        };
+
+        // Pages of the same seed are the same, while other pages differ.
+        let (status, users) = page("/users?count=5&seed=7");
+        assert_eq!((status, users.as_array().unwrap().len()), (200, 5));
+        assert_eq!(page("/users?seed=7&count=5&page=1").1, users);
+        assert_ne!(page("/users?count=5&seed=7&page=2").1, users);
+        assert_ne!(page("/users?count=5&seed=8").1, users);
+
+        assert_eq!(
+            page("/"),
+            (
+                200,
+                json!({
+                    "users": ["/users", "/users/stream"],
+                    "orders": ["/orders", "/orders/stream"]
+                })
+            )
+        );
+        assert_eq!(page("/products").0, 404);
+        assert_eq!(page("/stream").0, 404);
+        assert_eq!(page("/users?count=101").0, 400);
+        assert_eq!(page("/users?page=0").0, 400);
+        assert_eq!(page("/users?seed=x").0, 400);
+        assert_eq!(page("/users/stream?format=xml").0, 400);
+    }
+
+    #[test]
+    fn test_pages_share_serials_and_pools() {
+        let schema = r#"{
+            "$pools": {"users": {"count": 5, "schema": {"name": {"type": "regex", "pattern": "[a-z]{8}"}}}},
+            "id": {"type": "serial"},
+            "store": {"type": "regex", "pattern": "[a-z]{8}", "cardinality": 3},
+            "user": {"type": "member", "pool": "users", "field": "name"}
+        }"#;
+        let service = Service {
+            resources: vec![("orders".to_owned(), serde_json::from_str(schema).unwrap())],
+            max_count: 100,
+            rate: 1,
+        };
+        let page = |url: &str| match service.reply(&Method::Get, url, None) {
+            Reply::Json { body, .. } => body.as_array().unwrap().clone(),
+            reply => panic!("unexpected reply {:?}", reply),
+        };
+        let values = |pages: &[Vec<Value>], field| -> HashSet<String> {
+            pages
+                .iter()
+                .flatten()
+                .map(|value| value[field].to_string())
+                .collect()
+        };
+
+        let pages: Vec<_> = (1..=3)
+            .map(|n| page(&format!("/orders?count=50&seed=7&page={}", n)))
+            .collect();
+        let ids: Vec<_> = pages
+            .iter()
+            .flatten()
+            .map(|value| value["id"].clone())
+            .collect();
+        assert_eq!(ids, (1..=150).map(|id| json!(id)).collect::<Vec<_>>());
+        assert_eq!(values(&pages, "store").len(), 3);
+        assert_eq!(values(&pages, "user").len(), 5);
+
+        let other = page("/orders?count=50&seed=8");
+        assert!(values(&pages, "store").is_disjoint(&values(&[other], "store")));
+    }
+
+    #[test]
+    fn test_pages_do_not_repeat_unique_values() {
+        let schema = r#"{
+            "id": {"type": "range", "from": 0, "to": 1000, "unique": true},
+            "code": {"type": "regex", "pattern": "[a-d]{6}", "unique": true}
+        }"#;
+        let service = Service {
+            resources: vec![("orders".to_owned(), serde_json::from_str(schema).unwrap())],
+            max_count: 100,
+            rate: 1,
+        };
+
+        let mut ids = HashSet::new();
+        let mut codes = HashSet::new();
+        for n in 1..=10 {
+            let url = format!("/orders?count=90&seed=7&page={}", n);
+            let values = match service.reply(&Method::Get, &url, None) {
+                Reply::Json { body, .. } => body.as_array().unwrap().clone(),
+                reply => panic!("unexpected reply {:?}", reply),
+            };
+            for value in values {
+                assert!(ids.insert(value["id"].to_string()), "{}", value);
+                assert!(codes.insert(value["code"].to_string()), "{}", value);
+            }
+        }
+        assert_eq!(ids.len(), 900);
+    }
+
+    #[test]
+    fn test_stream() {
+        let service = service(&["users"]);
+        assert_eq!(
+            service.reply(
+                &Method::Get,
+                "/stream?count=2&rate=5&seed=1",
+                Some("text/event-stream")
+            ),
+            Reply::Stream {
+                resource: 0,
+                format: StreamFormat::Sse,
+                rate: 5,
+                count: Some(2),
+                seed: 1
+            }
+        );
+
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve(&server, &service));
+
+            let mut body = String::new();
+            let response = ureq::get(&format!("{}/users/stream?count=3&rate=1000", url))
+                .call()
+                .unwrap();
+            assert_eq!(response.content_type(), "application/x-ndjson");
+            response.into_reader().read_to_string(&mut body).unwrap();
+            let values: Vec<_> = body.lines().collect();
+            assert_eq!(values.len(), 3);
+
+            server.unblock();
+        });
+    }
+}
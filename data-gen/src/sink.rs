@@ -0,0 +1,532 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Local;
+use clap::{ArgEnum, Args};
+use flate2::write::GzEncoder;
+
+pub use self::socket::{Reconnect, Socket};
+
+mod socket;
+
+/// Where the output is written, files are rotated
+/// when any of the rotation limits is reached.
+#[derive(Args, Default)]
+pub struct SinkOptions {
+    /// Write to a file instead of stdout. The name may contain `{seq}`,
+    /// replaced by the sequence number of the file, and `{timestamp}`,
+    /// replaced by the time the file is created, and `{table}`, replaced
+    /// by the name of each table of a dataset. Sockets are given as
+    /// `tcp://<host>:<port>`, `tcp-listen://<address>:<port>`,
+    /// `udp://<host>:<port>`, `unix://<path>` or `unixgram://<path>`.
+    #[clap(short, long)]
+    output: Option<String>,
+
+    /// Start a new file after this many records.
+    #[clap(long)]
+    rotate_records: Option<u64>,
+
+    /// Start a new file after this many bytes, such as `512K` or
+    /// `64M`. The size is counted before compression.
+    #[clap(long, parse(try_from_str = parse_size))]
+    rotate_bytes: Option<u64>,
+
+    /// Start a new file after this interval, such as `30s`, `5m` or `1h`.
+    #[clap(long, parse(try_from_str = parse_duration))]
+    rotate_interval: Option<Duration>,
+
+    /// Compress the output. By default files ending
+    /// in `.gz` or `.zst` are compressed accordingly.
+    #[clap(long, arg_enum)]
+    compress: Option<Compress>,
+
+    /// The number of times to retry connecting to a TCP or Unix
+    /// socket, when connecting first and after losing the connection.
+    #[clap(long, default_value = "10")]
+    reconnect_attempts: u32,
+
+    /// The delay before reconnecting to a socket, which is
+    /// doubled after each failed attempt, up to 10s.
+    #[clap(long, default_value = "100ms", parse(try_from_str = parse_duration))]
+    reconnect_delay: Duration,
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compress {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Opens the files of the output in sequence, and
+/// keeps track of when the current file is rotated.
+#[derive(Clone)]
+pub struct Destination {
+    path: Option<String>,
+    socket: Option<Socket>,
+    reconnect: Reconnect,
+    compress: Compress,
+    rotate_records: Option<u64>,
+    rotate_bytes: Option<u64>,
+    rotate_interval: Option<Duration>,
+    line_buffered: bool,
+    seq: u64,
+    records: u64,
+    opened: Instant,
+}
+
+impl Destination {
+    /// Creates the destination for the given options, where `path` is
+    /// used when no output is set. Line buffered files are flushed after
+    /// each line, so that rate limited streams can be tailed.
+    pub fn new(
+        options: &SinkOptions,
+        path: Option<&str>,
+        line_buffered: bool,
+    ) -> Result<Destination> {
+        let path = options.output.as_deref().or(path).map(str::to_owned);
+        let socket = path.as_deref().map(Socket::parse).transpose()?.flatten();
+        let rotate = options.rotate_records.is_some()
+            || options.rotate_bytes.is_some()
+            || options.rotate_interval.is_some();
+
+        match &path {
+            None if rotate => bail!("rotating files requires an --output file name"),
+            Some(path) if rotate && socket.is_some() => {
+                bail!(
+                    "rotating files requires an --output file name, not `{}`",
+                    path
+                )
+            }
+            // `{timestamp}` alone repeats within a second
+            Some(path) if rotate && !path.contains("{seq}") => {
+                bail!(
+                    "the output file name `{}` must contain `{{seq}}` to rotate files",
+                    path
+                )
+            }
+            _ => {}
+        }
+
+        let compress = options.compress.unwrap_or_else(|| match &path {
+            Some(path) if path.ends_with(".gz") => Compress::Gzip,
+            Some(path) if path.ends_with(".zst") => Compress::Zstd,
+            _ => Compress::None,
+        });
+        if compress != Compress::None && socket.as_ref().is_some_and(Socket::is_datagram) {
+            bail!("the output of a datagram socket can not be compressed, as each line is sent as a datagram");
+        }
+
+        Ok(Destination {
+            path,
+            socket,
+            reconnect: Reconnect {
+                attempts: options.reconnect_attempts,
+                delay: options.reconnect_delay,
+            },
+            compress,
+            rotate_records: options.rotate_records.map(|records| records.max(1)),
+            rotate_bytes: options.rotate_bytes,
+            rotate_interval: options.rotate_interval,
+            line_buffered,
+            seq: 0,
+            records: 0,
+            opened: Instant::now(),
+        })
+    }
+
+    /// Whether the output is written to files, rather than stdout or a socket.
+    pub fn is_file(&self) -> bool {
+        self.path.is_some() && self.socket.is_none()
+    }
+
+    /// Whether each line of the output is sent as a datagram.
+    pub fn is_datagram(&self) -> bool {
+        self.socket.as_ref().is_some_and(Socket::is_datagram)
+    }
+
+    /// Whether the output file name contains `{seq}`.
+    pub fn has_seq(&self) -> bool {
+        self.path
+            .as_ref()
+            .is_some_and(|path| path.contains("{seq}"))
+    }
+
+    /// Whether the output file name contains `{table}`.
+    pub fn has_table(&self) -> bool {
+        self.path
+            .as_ref()
+            .is_some_and(|path| path.contains("{table}"))
+    }
+
+    /// The destination of a table of a dataset, whose files
+    /// are named with `{table}` replaced by the table name.
+    pub fn table(&self, table: &str) -> Destination {
+        Destination {
+            path: self
+                .path
+                .as_ref()
+                .map(|path| path.replace("{table}", table)),
+            ..self.clone()
+        }
+    }
+
+    /// The destination of a partition, whose files are written to the
+    /// given directory below the directory of the output file name.
+    pub fn partition(&self, dir: &str) -> Destination {
+        let path = self.path.as_deref().map(|path| {
+            let path = Path::new(path);
+            let parent = path.parent().unwrap_or_else(|| Path::new(""));
+            let file_name = path.file_name().unwrap_or_default();
+            parent.join(dir).join(file_name).display().to_string()
+        });
+
+        Destination {
+            path,
+            seq: 0,
+            records: 0,
+            opened: Instant::now(),
+            ..self.clone()
+        }
+    }
+
+    /// Opens the next file, the socket, or stdout when no output is set.
+    pub fn open(&mut self) -> Result<Sink> {
+        let out: Box<dyn Write + Send> = match (&self.socket, &self.path) {
+            (Some(socket), _) => socket.open(self.reconnect)?,
+            (None, None) => Box::new(io::stdout()),
+            (None, Some(path)) => {
+                let path = path.replace("{seq}", &format!("{:05}", self.seq)).replace(
+                    "{timestamp}",
+                    &Local::now().format("%Y%m%dT%H%M%S").to_string(),
+                );
+                if let Some(parent) = Path::new(&path).parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("failed to create directory: {}", path))?;
+                }
+
+                // Files after the first are never overwritten, so
+                // that a repeated name can not lose records.
+                let file = if self.rotates() || self.seq > 0 {
+                    OpenOptions::new().write(true).create_new(true).open(&path)
+                } else {
+                    File::create(&path)
+                }
+                .with_context(|| format!("failed to create file: {}", path))?;
+                if self.line_buffered {
+                    Box::new(LineWriter::new(file))
+                } else {
+                    Box::new(BufWriter::new(file))
+                }
+            }
+        };
+
+        self.seq += 1;
+        self.records = 0;
+        self.opened = Instant::now();
+
+        Sink::new(out, self.compress)
+    }
+
+    /// Whether files are rotated when any of the rotation limits is reached.
+    pub fn rotates(&self) -> bool {
+        self.rotate_records.is_some()
+            || self.rotate_bytes.is_some()
+            || self.rotate_interval.is_some()
+    }
+
+    /// The number of records which can be written
+    /// before the current file is rotated.
+    pub fn remaining(&self) -> Option<u64> {
+        self.rotate_records
+            .map(|records| records.saturating_sub(self.records))
+    }
+
+    /// Records that the given number of records were written.
+    pub fn written(&mut self, records: u64) {
+        self.records += records;
+    }
+
+    /// Whether the current file should be rotated before writing more
+    /// records. Files are only rotated once a record is written to them.
+    pub fn should_rotate(&self, sink: &Sink) -> bool {
+        self.records > 0
+            && (self.remaining() == Some(0)
+                || self.rotate_bytes.is_some_and(|bytes| sink.bytes() >= bytes)
+                || self
+                    .rotate_interval
+                    .is_some_and(|interval| self.opened.elapsed() >= interval))
+    }
+}
+
+/// A shared handle to an output file or stdout, which compresses the
+/// bytes written to it. The writers of each format write to a clone of
+/// the sink, so that it can be closed once the writer is finished.
+#[derive(Clone)]
+pub struct Sink(Arc<Mutex<SinkState>>);
+
+struct SinkState {
+    encoder: Option<Encoder>,
+    /// The number of bytes written before compression.
+    bytes: u64,
+}
+
+enum Encoder {
+    Plain(Box<dyn Write + Send>),
+    Gzip(GzEncoder<Box<dyn Write + Send>>),
+    Zstd(zstd::Encoder<'static, Box<dyn Write + Send>>),
+}
+
+impl Sink {
+    pub fn new(out: Box<dyn Write + Send>, compress: Compress) -> Result<Sink> {
+        let encoder = match compress {
+            Compress::None => Encoder::Plain(out),
+            Compress::Gzip => Encoder::Gzip(GzEncoder::new(out, flate2::Compression::default())),
+            Compress::Zstd => Encoder::Zstd(
+                zstd::Encoder::new(out, zstd::DEFAULT_COMPRESSION_LEVEL)
+                    .context("failed to initialize zstd compression")?,
+            ),
+        };
+
+        Ok(Sink(Arc::new(Mutex::new(SinkState {
+            encoder: Some(encoder),
+            bytes: 0,
+        }))))
+    }
+
+    /// A sink discarding everything written to it.
+    #[cfg(test)]
+    pub fn discard() -> Sink {
+        Sink::new(Box::new(io::sink()), Compress::None).unwrap()
+    }
+
+    /// The number of bytes written before compression.
+    pub fn bytes(&self) -> u64 {
+        self.lock().bytes
+    }
+
+    /// Writes the end of the compressed stream and flushes the output,
+    /// after which nothing more can be written to the sink.
+    pub fn close(&self) -> Result<()> {
+        let encoder = self.lock().encoder.take();
+        let mut out = match encoder {
+            None => return Ok(()),
+            Some(Encoder::Plain(out)) => out,
+            Some(Encoder::Gzip(encoder)) => encoder.finish().context("failed to finish gzip")?,
+            Some(Encoder::Zstd(encoder)) => encoder.finish().context("failed to finish zstd")?,
+        };
+        out.flush().context("failed to flush output")
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SinkState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+        let written = match state.encoder.as_mut() {
+            Some(Encoder::Plain(out)) => out.write(buf)?,
+            Some(Encoder::Gzip(encoder)) => encoder.write(buf)?,
+            Some(Encoder::Zstd(encoder)) => encoder.write(buf)?,
+            None => return Err(io::Error::other("the output is closed")),
+        };
+        state.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.lock().encoder.as_mut() {
+            Some(Encoder::Plain(out)) => out.flush(),
+            Some(Encoder::Gzip(encoder)) => encoder.flush(),
+            Some(Encoder::Zstd(encoder)) => encoder.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a number of bytes with an optional
+/// `K`, `M` or `G` suffix, such as `64M`.
+fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let factor = match unit.to_ascii_uppercase().trim_end_matches(['B', 'I']) {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => bail!("unknown size unit `{}`, expected K, M or G", unit),
+    };
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size `{}`", s))?;
+
+    number
+        .checked_mul(factor)
+        .ok_or_else(|| anyhow!("size `{}` is too large", s))
+}
+
+/// Parses a duration in seconds with an optional
+/// `ms`, `s`, `m` or `h` suffix, such as `5m`.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let seconds = match unit {
+        "ms" => 0.001,
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => bail!("unknown duration unit `{}`, expected ms, s, m or h", unit),
+    };
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("invalid duration `{}`", s))?;
+
+    Duration::try_from_secs_f64(number * seconds)
+        .with_context(|| format!("invalid duration `{}`", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    use super::{parse_duration, parse_size, Compress, Destination, SinkOptions};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("64MiB").unwrap(), 64 * 1024 * 1024);
+        assert!(parse_size("1T").is_err());
+
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert!(parse_duration("1d").is_err());
+    }
+
+    #[test]
+    fn test_rotate() {
+        let dir = std::env::temp_dir().join(format!("data-gen-sink-{}", std::process::id()));
+        let options = SinkOptions {
+            output: Some(dir.join("part-{seq}.json.gz").display().to_string()),
+            rotate_records: Some(2),
+            rotate_bytes: None,
+            rotate_interval: None,
+            compress: None,
+            reconnect_attempts: 0,
+            reconnect_delay: Duration::ZERO,
+        };
+        let mut destination = Destination::new(&options, None, false).unwrap();
+        assert_eq!(destination.compress, Compress::Gzip);
+
+        for _ in 0..2 {
+            let mut sink = destination.open().unwrap();
+            assert!(!destination.should_rotate(&sink));
+            for _ in 0..2 {
+                writeln!(sink, "{{}}").unwrap();
+                destination.written(1);
+            }
+            assert_eq!(destination.remaining(), Some(0));
+            assert!(destination.should_rotate(&sink));
+            assert_eq!(sink.bytes(), 6);
+            sink.close().unwrap();
+            assert!(writeln!(sink, "{{}}").is_err());
+        }
+
+        let mut text = String::new();
+        let file = std::fs::File::open(dir.join("part-00001.json.gz")).unwrap();
+        flate2::read::GzDecoder::new(file)
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "{}\n{}\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_within_a_second() {
+        let dir = std::env::temp_dir().join(format!("data-gen-second-{}", std::process::id()));
+        let options = SinkOptions {
+            output: Some(dir.join("out-{timestamp}-{seq}.json").display().to_string()),
+            rotate_records: Some(1),
+            rotate_bytes: None,
+            rotate_interval: None,
+            compress: None,
+            reconnect_attempts: 0,
+            reconnect_delay: Duration::ZERO,
+        };
+
+        let mut destination = Destination::new(&options, None, false).unwrap();
+        for i in 0..5 {
+            let mut sink = destination.open().unwrap();
+            writeln!(sink, "{}", i).unwrap();
+            destination.written(1);
+            sink.close().unwrap();
+        }
+
+        let mut lines: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        lines.sort();
+        assert_eq!(lines, vec!["0\n", "1\n", "2\n", "3\n", "4\n"]);
+
+        // a file of the same name is never overwritten
+        let options = SinkOptions {
+            output: Some(dir.join("out-{seq}.json").display().to_string()),
+            ..options
+        };
+        std::fs::write(dir.join("out-00000.json"), "0\n").unwrap();
+        let mut destination = Destination::new(&options, None, false).unwrap();
+        assert!(destination.open().is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out-00000.json")).unwrap(),
+            "0\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let mut options = SinkOptions {
+            output: None,
+            rotate_records: None,
+            rotate_bytes: Some(1024),
+            rotate_interval: None,
+            compress: None,
+            reconnect_attempts: 0,
+            reconnect_delay: Duration::ZERO,
+        };
+        assert!(Destination::new(&options, None, false).is_err());
+
+        options.output = Some("out.json".to_owned());
+        assert!(Destination::new(&options, None, false).is_err());
+
+        options.output = Some("out-{timestamp}.json".to_owned());
+        assert!(Destination::new(&options, None, false).is_err());
+
+        options.output = Some("out-{timestamp}-{seq}.json".to_owned());
+        assert!(Destination::new(&options, None, false).is_ok());
+
+        options.output = Some("tcp://localhost:5170".to_owned());
+        assert!(Destination::new(&options, None, false).is_err());
+
+        options.rotate_bytes = None;
+        options.output = Some("udp://localhost:514".to_owned());
+        options.compress = Some(Compress::Gzip);
+        assert!(Destination::new(&options, None, false).is_err());
+    }
+}
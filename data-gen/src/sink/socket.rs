@@ -0,0 +1,410 @@
+use std::io::{self, LineWriter, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::{UnixDatagram, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+/// The longest delay between attempts to reconnect.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// A socket the output is written to, given as a URL such as
+/// `tcp://localhost:5170`. Stream sockets are written to as the
+/// output of the format, while datagram sockets send each line
+/// of the output as a datagram of its own.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Socket {
+    /// Connects to a TCP server.
+    Tcp(String),
+    /// Listens for TCP clients, and writes to every connected client.
+    TcpListen(String),
+    Udp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+    #[cfg(unix)]
+    Unixgram(PathBuf),
+}
+
+/// How often and how long to wait between attempts to
+/// connect, where the delay is doubled after each attempt.
+#[derive(Clone, Copy, Debug)]
+pub struct Reconnect {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl Socket {
+    /// Parses the URL of a socket, or returns `None` if the output is a file.
+    pub fn parse(url: &str) -> Result<Option<Socket>> {
+        let (scheme, address) = match url.split_once("://") {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+
+        let socket = match scheme {
+            "tcp" => Socket::Tcp(address.to_owned()),
+            "tcp-listen" => Socket::TcpListen(address.to_owned()),
+            "udp" => Socket::Udp(address.to_owned()),
+            #[cfg(unix)]
+            "unix" => Socket::Unix(address.into()),
+            #[cfg(unix)]
+            "unixgram" => Socket::Unixgram(address.into()),
+            _ => bail!(
+                "unknown socket `{}`, expected tcp, tcp-listen, udp, unix or unixgram",
+                scheme
+            ),
+        };
+        Ok(Some(socket))
+    }
+
+    /// Whether each line written to the socket is sent as a datagram.
+    pub fn is_datagram(&self) -> bool {
+        match self {
+            Socket::Udp(_) => true,
+            #[cfg(unix)]
+            Socket::Unixgram(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Connects to the socket, or starts listening for clients.
+    pub fn open(&self, reconnect: Reconnect) -> Result<Box<dyn Write + Send>> {
+        let out: Box<dyn Write + Send> = match self.clone() {
+            Socket::Tcp(address) => {
+                let stream = Reconnecting::new(
+                    format!("tcp://{}", address),
+                    Box::new(move || TcpStream::connect(&address)),
+                    reconnect,
+                )?;
+                Box::new(LineWriter::new(stream))
+            }
+            Socket::TcpListen(address) => {
+                let listener = TcpListener::bind(&address)
+                    .with_context(|| format!("failed to listen on {}", address))?;
+                Box::new(LineWriter::new(Broadcast::new(listener)?))
+            }
+            Socket::Udp(address) => {
+                let target = address
+                    .to_socket_addrs()
+                    .ok()
+                    .and_then(|mut addresses| addresses.next())
+                    .with_context(|| format!("failed to resolve {}", address))?;
+                let local = if target.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(local)
+                    .and_then(|socket| socket.connect(target).map(|_| socket))
+                    .with_context(|| format!("failed to connect to udp://{}", address))?;
+                Box::new(Datagrams::new(Box::new(move |buf| socket.send(buf))))
+            }
+            #[cfg(unix)]
+            Socket::Unix(path) => {
+                let stream = Reconnecting::new(
+                    format!("unix://{}", path.display()),
+                    Box::new(move || UnixStream::connect(&path)),
+                    reconnect,
+                )?;
+                Box::new(LineWriter::new(stream))
+            }
+            #[cfg(unix)]
+            Socket::Unixgram(path) => {
+                let socket = UnixDatagram::unbound()
+                    .and_then(|socket| socket.connect(&path).map(|_| socket))
+                    .with_context(|| {
+                        format!("failed to connect to unixgram://{}", path.display())
+                    })?;
+                Box::new(Datagrams::new(Box::new(move |buf| socket.send(buf))))
+            }
+        };
+        Ok(out)
+    }
+}
+
+type Connect<S> = Box<dyn Fn() -> io::Result<S> + Send>;
+
+/// A stream socket which is reconnected whenever
+/// writing fails, after which the write is retried.
+struct Reconnecting<S> {
+    name: String,
+    connect: Connect<S>,
+    stream: Option<S>,
+    reconnect: Reconnect,
+}
+
+impl<S: Write> Reconnecting<S> {
+    fn new(name: String, connect: Connect<S>, reconnect: Reconnect) -> Result<Reconnecting<S>> {
+        let mut socket = Reconnecting {
+            name,
+            connect,
+            stream: None,
+            reconnect,
+        };
+        let stream = socket
+            .connect()
+            .with_context(|| format!("failed to connect to {}", socket.name))?;
+        socket.stream = Some(stream);
+        Ok(socket)
+    }
+
+    fn connect(&self) -> io::Result<S> {
+        let mut delay = self.reconnect.delay;
+        let mut attempt = 0;
+        loop {
+            match (self.connect)() {
+                Ok(stream) => return Ok(stream),
+                Err(e) if attempt >= self.reconnect.attempts => return Err(e),
+                Err(_) => {
+                    std::thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<S: Write> Write for Reconnecting<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut failures = 0;
+        loop {
+            let stream = match self.stream.as_mut() {
+                Some(stream) => stream,
+                None => self.stream.insert(self.connect()?),
+            };
+
+            let e = match stream.write(buf) {
+                Ok(0) if !buf.is_empty() => io::Error::from(io::ErrorKind::WriteZero),
+                Ok(written) => return Ok(written),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => e,
+            };
+
+            // Peers accepting connections only to close them
+            // again must not make the writer retry forever.
+            failures += 1;
+            if failures > self.reconnect.attempts {
+                return Err(e);
+            }
+            eprintln!("warning: reconnecting to {}: {}", self.name, e);
+            self.stream = None;
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.stream.as_mut() {
+            Some(stream) => stream.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes to every client connected to a TCP listener,
+/// waiting for a client to connect if there is none.
+struct Broadcast {
+    clients: Arc<(Mutex<Vec<TcpStream>>, Condvar)>,
+}
+
+impl Broadcast {
+    fn new(listener: TcpListener) -> Result<Broadcast> {
+        eprintln!("listening on tcp://{}", listener.local_addr()?);
+
+        let clients = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+        let accepted = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (clients, joined) = &*accepted;
+                clients.lock().unwrap().push(stream);
+                joined.notify_all();
+            }
+        });
+
+        Ok(Broadcast { clients })
+    }
+}
+
+impl Write for Broadcast {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (clients, joined) = &*self.clients;
+        let mut clients = joined
+            .wait_while(clients.lock().unwrap(), |clients| clients.is_empty())
+            .unwrap();
+
+        // Clients which disconnected are dropped.
+        clients.retain_mut(|client| client.write_all(buf).is_ok());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+type SendDatagram = Box<dyn FnMut(&[u8]) -> io::Result<usize> + Send>;
+
+/// Sends each line written as a datagram, without its line ending.
+struct Datagrams {
+    send: SendDatagram,
+    buffer: Vec<u8>,
+}
+
+impl Datagrams {
+    fn new(send: SendDatagram) -> Datagrams {
+        Datagrams {
+            send,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn send(&mut self, end: usize) -> io::Result<()> {
+        let line = self.buffer[..end]
+            .strip_suffix(b"\r")
+            .unwrap_or(&self.buffer[..end]);
+        match (self.send)(line) {
+            // A datagram is refused when nothing listens on the
+            // port, which must not stop the following datagrams.
+            Err(e) if e.kind() != io::ErrorKind::ConnectionRefused => return Err(e),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Write for Datagrams {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            self.send(end)?;
+            self.buffer.drain(..=end);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Datagrams {
+    /// Sends the last line, if it does not end with a line ending.
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            let _ = self.send(self.buffer.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream, UdpSocket};
+    use std::time::Duration;
+
+    use super::{Reconnect, Socket};
+
+    const RECONNECT: Reconnect = Reconnect {
+        attempts: 3,
+        delay: Duration::from_millis(10),
+    };
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Socket::parse("out/part.json").unwrap(), None);
+        assert_eq!(
+            Socket::parse("tcp://localhost:5170").unwrap(),
+            Some(Socket::Tcp("localhost:5170".to_owned()))
+        );
+        assert_eq!(
+            Socket::parse("unixgram:///dev/log").unwrap(),
+            Some(Socket::Unixgram("/dev/log".into()))
+        );
+        assert!(Socket::parse("http://localhost").is_err());
+    }
+
+    #[test]
+    fn test_is_datagram() {
+        assert!(Socket::Udp("localhost:514".to_owned()).is_datagram());
+        assert!(Socket::Unixgram("/dev/log".into()).is_datagram());
+        assert!(!Socket::Tcp("localhost:5170".to_owned()).is_datagram());
+    }
+
+    #[test]
+    fn test_tcp_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // The server closes each connection after reading a line.
+        let server = std::thread::spawn(move || {
+            let mut lines = Vec::new();
+            for stream in listener.incoming().take(2) {
+                let mut line = String::new();
+                BufReader::new(stream.unwrap())
+                    .read_line(&mut line)
+                    .unwrap();
+                lines.push(line);
+            }
+            lines
+        });
+
+        let mut out = Socket::Tcp(address).open(RECONNECT).unwrap();
+        writeln!(out, "first").unwrap();
+        let lines = loop {
+            writeln!(out, "second").unwrap();
+            if server.is_finished() {
+                break server.join().unwrap();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(lines, ["first\n", "second\n"]);
+    }
+
+    #[test]
+    fn test_tcp_listen() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client = std::thread::spawn(move || {
+            let stream = loop {
+                match TcpStream::connect(address) {
+                    Ok(stream) => break stream,
+                    Err(_) => std::thread::sleep(Duration::from_millis(10)),
+                }
+            };
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            line
+        });
+
+        // Writing waits for the client to connect.
+        let mut out = Socket::TcpListen(address.to_string())
+            .open(RECONNECT)
+            .unwrap();
+        writeln!(out, "{{\"id\":1}}").unwrap();
+        assert_eq!(client.join().unwrap(), "{\"id\":1}\n");
+    }
+
+    #[test]
+    fn test_udp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap().to_string();
+
+        let mut out = Socket::Udp(address).open(RECONNECT).unwrap();
+        write!(out, "<14>first\r\n<14>sec").unwrap();
+        write!(out, "ond\n<14>last").unwrap();
+        drop(out);
+
+        let mut buf = [0; 64];
+        let mut datagrams = Vec::new();
+        for _ in 0..3 {
+            let len = server.recv(&mut buf).unwrap();
+            datagrams.push(String::from_utf8_lossy(&buf[..len]).into_owned());
+        }
+        assert_eq!(datagrams, ["<14>first", "<14>second", "<14>last"]);
+    }
+}
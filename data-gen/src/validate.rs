@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use data_gen_lib::validate::validate;
+
+use crate::read_schema;
+
+/// Validates the schema at the given path, printing every problem
+/// found. Exits with a non-zero status if the schema is invalid.
+pub fn run(schema_path: &str, json: bool) -> Result<()> {
+    let data = read_schema(schema_path)?;
+    let diagnostics = validate(&data);
+
+    if json {
+        let out = serde_json::to_string_pretty(&diagnostics)
+            .context("failed to serialize diagnostics as JSON")?;
+        println!("{}", out);
+    } else {
+        for diagnostic in diagnostics.iter() {
+            println!("{}:{}", schema_path, diagnostic);
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        std::process::exit(1)
+    }
+
+    Ok(())
+}